@@ -1,5 +1,6 @@
 mod cg_def;
 use expander_compiler::zkcuda::{
+    artifact::{save_artifact, ArtifactContext},
     context::{ComputationGraph, ComputationGraphDefine},
     proving_system::{
         expander::config::{GetFieldConfig, GetPCS, ZKCudaBN254MIMCKZGBatchPCS, ZKCudaConfig},
@@ -7,7 +8,6 @@ use expander_compiler::zkcuda::{
     },
 };
 use gkr_engine::ExpanderPCS;
-use serdes::ExpSerde;
 
 use cg_def::MyCGDef;
 
@@ -17,7 +17,7 @@ where
         AsRef<<GetPCS<ZC> as ExpanderPCS<GetFieldConfig<ZC>>>::Commitment>,
 {
     let input = CG::get_input();
-    let (_computation_graph, extended_witness) = CG::gen_computation_graph_and_witness(Some(input));
+    let (computation_graph, extended_witness) = CG::gen_computation_graph_and_witness(Some(input));
 
     // Note: we've saved the computation graph and setup in the server. In order to generate a proof, we only need to submit the witness.
     let dummy_prover_setup =
@@ -30,9 +30,8 @@ where
         extended_witness.unwrap(),
     );
 
-    let mut bytes = vec![];
-    proof.serialize_into(&mut bytes).unwrap();
-    std::fs::write("/tmp/proof.bin", &bytes).unwrap();
+    let context = ArtifactContext::new::<ZC::ECCConfig, GetPCS<ZC>>(&computation_graph);
+    save_artifact("/tmp/proof.bin", &context, &proof).unwrap();
 }
 
 fn main() {
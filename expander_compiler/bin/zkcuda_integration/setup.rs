@@ -1,5 +1,6 @@
 mod cg_def;
 use expander_compiler::zkcuda::{
+    artifact::{save_artifact, ArtifactContext},
     context::ComputationGraphDefine,
     proving_system::{
         expander::config::{GetFieldConfig, GetPCS, ZKCudaBN254MIMCKZGBatchPCS, ZKCudaConfig},
@@ -7,7 +8,6 @@ use expander_compiler::zkcuda::{
     },
 };
 use gkr_engine::ExpanderPCS;
-use serdes::ExpSerde;
 
 use crate::cg_def::MyCGDef;
 
@@ -19,13 +19,10 @@ where
     let (computation_graph, _) = CG::gen_computation_graph_and_witness(None);
     let (prover_setup, verifier_setup) = ExpanderNoOverSubscribe::<ZC>::setup(&computation_graph);
 
-    let mut bytes = vec![];
-    prover_setup.serialize_into(&mut bytes).unwrap();
-    std::fs::write("/tmp/prover_setup.bin", &bytes).unwrap();
-
-    bytes.clear();
-    verifier_setup.serialize_into(&mut bytes).unwrap();
-    std::fs::write("/tmp/verifier_setup.bin", &bytes).unwrap();
+    let context = ArtifactContext::new::<ZC::ECCConfig, GetPCS<ZC>>(&computation_graph);
+    save_artifact("/tmp/computation_graph.bin", &context, &computation_graph).unwrap();
+    save_artifact("/tmp/prover_setup.bin", &context, &prover_setup).unwrap();
+    save_artifact("/tmp/verifier_setup.bin", &context, &verifier_setup).unwrap();
 }
 
 fn main() {
@@ -1,7 +1,6 @@
 mod cg_def;
-use std::io::Cursor;
-
 use expander_compiler::zkcuda::{
+    artifact::{computation_graph_hash, load_artifact, load_computation_graph},
     context::ComputationGraphDefine,
     proving_system::{
         expander::config::{GetFieldConfig, GetPCS, ZKCudaBN254MIMCKZGBatchPCS, ZKCudaConfig},
@@ -9,7 +8,6 @@ use expander_compiler::zkcuda::{
     },
 };
 use gkr_engine::ExpanderPCS;
-use serdes::ExpSerde;
 
 use cg_def::MyCGDef;
 
@@ -20,18 +18,19 @@ where
 {
     let (computation_graph, _) = CG::gen_computation_graph_and_witness(None);
 
-    let verifier_setup_bytes = std::fs::read("/tmp/verifier_setup.bin").unwrap();
-    let verifier_setup = <ExpanderNoOverSubscribe<ZC> as ProvingSystem<
-        ZC::ECCConfig,
-    >>::VerifierSetup::deserialize_from(Cursor::new(verifier_setup_bytes))
-    .unwrap();
-
-    let proof_bytes = std::fs::read("/tmp/proof.bin").unwrap();
-    let proof =
-        <ExpanderNoOverSubscribe<ZC> as ProvingSystem<ZC::ECCConfig>>::Proof::deserialize_from(
-            Cursor::new(proof_bytes),
-        )
-        .unwrap();
+    // the setup and the proof must have been produced for this computation graph
+    let (_, context) =
+        load_computation_graph::<ZC::ECCConfig, GetPCS<ZC>, _>("/tmp/computation_graph.bin")
+            .unwrap();
+    assert_eq!(
+        context.graph_hash,
+        computation_graph_hash(&computation_graph),
+        "Computation graph mismatch"
+    );
+    let verifier_setup: <ExpanderNoOverSubscribe<ZC> as ProvingSystem<ZC::ECCConfig>>::VerifierSetup =
+        load_artifact("/tmp/verifier_setup.bin", &context).unwrap();
+    let proof: <ExpanderNoOverSubscribe<ZC> as ProvingSystem<ZC::ECCConfig>>::Proof =
+        load_artifact("/tmp/proof.bin", &context).unwrap();
 
     let verified = <ExpanderNoOverSubscribe<ZC> as ProvingSystem<ZC::ECCConfig>>::verify(
         &verifier_setup,
@@ -85,6 +85,7 @@ pub fn memorized(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let fn_name = &input_fn.sig.ident;
     let user_fn_name = format_ident!("{}", fn_name);
     let memorized_fn_name = format_ident!("memorized_{}", fn_name);
+    let fn_name_str = fn_name.to_string();
     let stmts = &input_fn.block.stmts;
 
     let generics = &input_fn.sig.generics;
@@ -258,6 +259,7 @@ pub fn memorized(_attr: TokenStream, item: TokenStream) -> TokenStream {
             hasher.finalize(&mut hash);
 
             let circuit_id = #api_arg_ident.hash_to_sub_circuit_id(&hash);
+            #api_arg_ident.set_sub_circuit_name(circuit_id, #fn_name_str);
 
             let f = |#api_arg, inputs: &Vec<Variable>| -> Vec<Variable> {
                 let mut s = inputs.as_slice();
@@ -308,24 +310,33 @@ fn replace_array_with_vec(ty: &Type) -> proc_macro2::TokenStream {
         Type::Path(path) => {
             let ident = &path.path.segments.last().unwrap().ident;
             match ident.to_string().as_str() {
-                "InputVariable" | "OutputVariable" | "InputOutputVariable" => {
+                "InputVariable"
+                | "OutputVariable"
+                | "InputOutputVariable"
+                | "PublicInputVariable"
+                | "PublicOutputVariable"
+                | "PublicInputOutputVariable" => {
                     quote! { Variable }
                 }
-                _ => panic!("Unsupported type: {ident}. Expected InputVariable, OutputVariable, or InputOutputVariable"),
+                _ => panic!("Unsupported type: {ident}. Expected InputVariable, OutputVariable, InputOutputVariable, or their Public variants"),
             }
         }
         _ => panic!("Unsupported type structure. Expected array of Variable types."),
     }
 }
 
-fn get_variable_spec(ty: &Type) -> (bool, bool) {
+// returns (is_input, is_output, is_public)
+fn get_variable_spec(ty: &Type) -> (bool, bool, bool) {
     if let Type::Path(path) = ty {
         let ident = &path.path.segments.last().unwrap().ident;
         match ident.to_string().as_str() {
-            "InputVariable" => (true, false),
-            "OutputVariable" => (false, true),
-            "InputOutputVariable" => (true, true),
-            _ => panic!("Unsupported variable type. Expected InputVariable, OutputVariable, or InputOutputVariable"),
+            "InputVariable" => (true, false, false),
+            "OutputVariable" => (false, true, false),
+            "InputOutputVariable" => (true, true, false),
+            "PublicInputVariable" => (true, false, true),
+            "PublicOutputVariable" => (false, true, true),
+            "PublicInputOutputVariable" => (true, true, true),
+            _ => panic!("Unsupported variable type. Expected InputVariable, OutputVariable, InputOutputVariable, or their Public variants"),
         }
     } else {
         panic!("Expected a Variable type")
@@ -528,7 +539,7 @@ pub fn kernel(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     while let Type::Array(arr) = inner_ty {
                         inner_ty = &*arr.elem;
                     }
-                    let (is_input, is_output) = get_variable_spec(inner_ty);
+                    let (is_input, is_output, is_public) = get_variable_spec(inner_ty);
                     let total_len = calculate_array_total_len(&ref_type.elem);
                     let shape = get_array_shape(&ref_type.elem);
                     shapes.push(quote! { vec![#shape] });
@@ -538,6 +549,7 @@ pub fn kernel(_attr: TokenStream, item: TokenStream) -> TokenStream {
                             len: #total_len,
                             is_input: #is_input,
                             is_output: #is_output,
+                            is_public: #is_public,
                         }
                     });
 
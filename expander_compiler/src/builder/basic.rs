@@ -1,14 +1,19 @@
 use std::collections::{BinaryHeap, HashMap};
 
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serdes::ExpSerde;
+
 use crate::{
     circuit::{
         config::Config,
+        costs::CostModel,
         ir::{
             self,
-            common::{Constraint, Instruction as _, IrConfig},
+            common::{Constraint, Instruction as _, IrConfig, SourceLocation},
             expr::{Expression, LinComb, Term, VarSpec},
         },
     },
+    compile::cache::{CacheKey, CacheKeyHasher, CompileCache},
     field::{Field, FieldArith},
     frontend::CircuitField,
     utils::{error::Error, pool::Pool},
@@ -29,6 +34,21 @@ pub struct RootBuilder<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrCon
     pub rc: &'a ir::common::RootCircuit<IrcIn>,
     pub builders: HashMap<usize, Builder<'a, C, IrcIn, IrcOut>>,
     pub out_circuits: HashMap<usize, ir::common::Circuit<IrcOut>>,
+    // used by the transforms choosing between equivalent lowerings, e.g. of a mux
+    pub cost_model: CostModel,
+}
+
+impl<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<Config = C>>
+    RootBuilder<'a, C, IrcIn, IrcOut>
+{
+    pub fn new(rc: &'a ir::common::RootCircuit<IrcIn>) -> Self {
+        RootBuilder {
+            rc,
+            builders: HashMap::new(),
+            out_circuits: HashMap::new(),
+            cost_model: CostModel::of::<C>(),
+        }
+    }
 }
 
 pub struct Builder<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<Config = C>> {
@@ -60,6 +80,8 @@ pub struct Builder<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<
 
     // output instructions
     pub out_insns: Vec<IrcOut::Instruction>,
+
+    pub cost_model: CostModel,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -72,7 +94,8 @@ pub struct OutVarRef<C: Config> {
 #[derive(Debug, Clone)]
 pub enum ConstraintStatus {
     Marked,
-    Asserted,
+    // with the frontend location of the first assertion, if known
+    Asserted(Option<SourceLocation>),
 }
 
 pub struct LinMeta {
@@ -133,7 +156,11 @@ pub trait InsnTransformAndExecute<
 impl<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<Config = C>>
     Builder<'a, C, IrcIn, IrcOut>
 {
-    pub fn new(in_circuit_id: usize, in_circuit: &'a ir::common::Circuit<IrcIn>) -> Self {
+    pub fn new(
+        in_circuit_id: usize,
+        in_circuit: &'a ir::common::Circuit<IrcIn>,
+        cost_model: CostModel,
+    ) -> Self {
         let mut res: Builder<'a, C, IrcIn, IrcOut> = Builder {
             in_circuit,
             in_circuit_id,
@@ -144,6 +171,7 @@ impl<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<Config = C>>
             mid_expr_to_out: HashMap::new(),
             in_to_out: vec![0],
             out_insns: Vec::new(),
+            cost_model,
         };
         res.mid_vars.add(&Expression::invalid());
         res.mid_to_out.push(None);
@@ -223,13 +251,22 @@ impl<'a, C: Config, IrcIn: IrConfig<Config = C>, IrcOut: IrConfig<Config = C>>
         &mut self,
         constraint_type: <IrcOut::Constraint as Constraint<C>>::Type,
         out_var_id: usize,
+    ) {
+        self.assert_with_origin(constraint_type, out_var_id, None);
+    }
+
+    pub fn assert_with_origin(
+        &mut self,
+        constraint_type: <IrcOut::Constraint as Constraint<C>>::Type,
+        out_var_id: usize,
+        origin: Option<SourceLocation>,
     ) {
         let expr = self.out_var_exprs[out_var_id].clone();
         self.constraints
             .entry(constraint_type)
             .or_default()
             .entry(expr)
-            .or_insert(ConstraintStatus::Asserted);
+            .or_insert(ConstraintStatus::Asserted(origin));
     }
 
     pub fn mark(
@@ -372,7 +409,7 @@ where
     fn process_con(&mut self, in_con: &IrcIn::Constraint) -> Result<(), Error> {
         let in_mapped = in_con.replace_var(|x| self.in_to_out[x]);
         let out_con = self.transform_in_con_to_out(&in_mapped)?;
-        self.assert(out_con.typ(), out_con.var());
+        self.assert_with_origin(out_con.typ(), out_con.var(), in_con.origin());
         Ok(())
     }
 }
@@ -589,6 +626,13 @@ fn mul_two_expr<C: Config>(
 pub type ProcessOk<'a, C, IrcIn, IrcOut> =
     (ir::common::Circuit<IrcOut>, Builder<'a, C, IrcIn, IrcOut>);
 
+// the key of a circuit, and the circuit either loaded from the cache or processed
+type Processed<'a, C, IrcIn, IrcOut> = (
+    Option<CacheKey>,
+    Option<ir::common::Circuit<IrcOut>>,
+    Option<ProcessOk<'a, C, IrcIn, IrcOut>>,
+);
+
 pub fn process_circuit<
     'b,
     'a: 'b,
@@ -596,7 +640,7 @@ pub fn process_circuit<
     IrcIn: IrConfig<Config = C>,
     IrcOut: IrConfig<Config = C>,
 >(
-    root: &'b mut RootBuilder<'a, C, IrcIn, IrcOut>,
+    root: &'b RootBuilder<'a, C, IrcIn, IrcOut>,
     circuit_id: usize,
     circuit: &'a ir::common::Circuit<IrcIn>,
 ) -> Result<ProcessOk<'a, C, IrcIn, IrcOut>, Error>
@@ -604,7 +648,7 @@ where
     Builder<'a, C, IrcIn, IrcOut>: InsnTransformAndExecute<'a, C, IrcIn, IrcOut>,
 {
     //let circuit = root.rc.circuits.get(&circuit_id).unwrap();
-    let mut builder = Builder::new(circuit_id, circuit);
+    let mut builder = Builder::new(circuit_id, circuit, root.cost_model);
     builder.add_input();
     for insn in circuit.instructions.iter() {
         builder.process_insn(insn, root)?;
@@ -620,8 +664,10 @@ where
             let status = cons.get(expr).unwrap();
             match status {
                 ConstraintStatus::Marked => {}
-                ConstraintStatus::Asserted => {
-                    constraints.push(Constraint::new(builder.mid_expr_to_out[expr], *typ));
+                ConstraintStatus::Asserted(origin) => {
+                    constraints.push(
+                        Constraint::new(builder.mid_expr_to_out[expr], *typ).with_origin(*origin),
+                    );
                 }
             }
         }
@@ -639,6 +685,34 @@ where
     Ok((new_circuit, builder))
 }
 
+// sub circuits are keyed by their id, which is the hash of the function building them, their
+// content, since the id doesn't cover the body of the function, and the keys of the circuits
+// they call
+fn cache_key<C: Config, IrcIn: IrConfig<Config = C>>(
+    stage: &str,
+    circuit_id: usize,
+    circuit: &ir::common::Circuit<IrcIn>,
+    cost_model: &CostModel,
+    keys: &HashMap<usize, CacheKey>,
+) -> CacheKey
+where
+    ir::common::Circuit<IrcIn>: ExpSerde,
+{
+    let mut hasher = CacheKeyHasher::new::<C>(stage);
+    hasher.update_cost_model(cost_model);
+    hasher.update_usize(circuit_id);
+    hasher.update_serde(circuit);
+    for insn in circuit.instructions.iter() {
+        if let Some((sub_circuit_id, _, _)) = insn.as_sub_circuit_call() {
+            hasher.update(&keys[&sub_circuit_id]);
+        }
+    }
+    hasher.finalize()
+}
+
+/// Processes all circuits of `rc`. With a cache, the circuits processed by previous
+/// compilations are reused, stored under `cache_stage`. The serialized circuits don't keep the
+/// origins of the constraints, so the cached ones have none.
 pub fn process_root_circuit<
     'a,
     C: Config + 'a,
@@ -646,21 +720,59 @@ pub fn process_root_circuit<
     IrcOut: IrConfig<Config = C> + 'a,
 >(
     rc: &'a ir::common::RootCircuit<IrcIn>,
+    cost_model: &CostModel,
+    cache: Option<&CompileCache>,
+    cache_stage: &str,
 ) -> Result<ir::common::RootCircuit<IrcOut>, Error>
 where
     Builder<'a, C, IrcIn, IrcOut>: InsnTransformAndExecute<'a, C, IrcIn, IrcOut>,
+    RootBuilder<'a, C, IrcIn, IrcOut>: Sync,
+    ProcessOk<'a, C, IrcIn, IrcOut>: Send,
+    ir::common::Circuit<IrcIn>: ExpSerde,
+    ir::common::Circuit<IrcOut>: ExpSerde + Send,
 {
     let mut root: RootBuilder<'a, C, IrcIn, IrcOut> = RootBuilder {
-        builders: HashMap::new(),
-        rc,
-        out_circuits: HashMap::new(),
+        cost_model: *cost_model,
+        ..RootBuilder::new(rc)
     };
-    let order = rc.topo_order();
-    for &circuit_id in order.iter().rev() {
-        let (new_circuit, final_builder) =
-            process_circuit(&mut root, circuit_id, rc.circuits.get(&circuit_id).unwrap())?;
-        root.out_circuits.insert(circuit_id, new_circuit);
-        root.builders.insert(circuit_id, final_builder);
+    let mut keys: HashMap<usize, CacheKey> = HashMap::new();
+    // a circuit only depends on its sub circuits, so each level is processed in parallel
+    for level in rc.topo_levels() {
+        let results: Vec<Result<Processed<'a, C, IrcIn, IrcOut>, Error>> = level
+            .par_iter()
+            .map(|&circuit_id| {
+                let circuit = rc.circuits.get(&circuit_id).unwrap();
+                let key = cache.map(|_| {
+                    cache_key::<C, IrcIn>(cache_stage, circuit_id, circuit, cost_model, &keys)
+                });
+                let cached = cache.zip(key.as_ref()).and_then(|(cache, key)| {
+                    cache.get::<ir::common::Circuit<IrcOut>>(cache_stage, key)
+                });
+                if cached.is_some() {
+                    return Ok((key, cached, None));
+                }
+                let processed = process_circuit(&root, circuit_id, circuit)?;
+                if let Some((cache, key)) = cache.zip(key.as_ref()) {
+                    cache.put(cache_stage, key, &processed.0);
+                }
+                Ok((key, None, Some(processed)))
+            })
+            .collect();
+        for (&circuit_id, res) in level.iter().zip(results) {
+            let (key, cached, processed) = res?;
+            if let Some(key) = key {
+                keys.insert(circuit_id, key);
+            }
+            // the builders of the sub circuits aren't read by their callers, so the cached
+            // circuits don't need one
+            if let Some(new_circuit) = cached {
+                root.out_circuits.insert(circuit_id, new_circuit);
+            }
+            if let Some((new_circuit, final_builder)) = processed {
+                root.out_circuits.insert(circuit_id, new_circuit);
+                root.builders.insert(circuit_id, final_builder);
+            }
+        }
     }
     Ok(ir::common::RootCircuit {
         num_public_inputs: rc.num_public_inputs,
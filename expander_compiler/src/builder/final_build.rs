@@ -1,7 +1,7 @@
 use core::panic;
 use std::collections::HashMap;
 
-use crate::circuit::ir::common::{Instruction, RawConstraint};
+use crate::circuit::ir::common::{Instruction, TracedConstraint};
 use crate::circuit::ir::expr::Expression;
 use crate::circuit::{config::Config, ir, layered::Coef};
 use crate::field::FieldArith;
@@ -24,7 +24,10 @@ impl<'a, C: Config> InsnTransformAndExecute<'a, C, IrcIn<C>, IrcOut<C>> for Buil
         InsnTransformResult::Insn(in_insn.clone())
     }
 
-    fn transform_in_con_to_out(&mut self, in_con: &RawConstraint) -> Result<RawConstraint, Error> {
+    fn transform_in_con_to_out(
+        &mut self,
+        in_con: &TracedConstraint,
+    ) -> Result<TracedConstraint, Error> {
         Ok(*in_con)
     }
 
@@ -74,7 +77,7 @@ impl<'a, C: Config> Builder<'a, C> {
         for (expr, status) in self.constraints.entry(()).or_default().iter() {
             match status {
                 ConstraintStatus::Marked => {}
-                ConstraintStatus::Asserted => {
+                ConstraintStatus::Asserted(_) => {
                     to_really_single(&mut self.mid_vars, expr);
                 }
             }
@@ -186,7 +189,7 @@ impl<'a, C: Config> Builder<'a, C> {
         for (expr, status) in self.constraints[&()].iter() {
             match status {
                 ConstraintStatus::Marked => {}
-                ConstraintStatus::Asserted => {
+                ConstraintStatus::Asserted(_) => {
                     if let Some(v) = expr.constant_value() {
                         if v.is_zero() {
                             continue;
@@ -211,15 +214,11 @@ impl<'a, C: Config> Builder<'a, C> {
 pub fn process<'a, C: Config>(
     rc: &'a ir::common::RootCircuit<IrcIn<C>>,
 ) -> Result<ir::dest::RootCircuitRelaxed<C>, Error> {
-    let mut root: RootBuilder<'a, C, IrcIn<C>, IrcOut<C>> = RootBuilder {
-        builders: HashMap::new(),
-        rc,
-        out_circuits: HashMap::new(),
-    };
+    let mut root: RootBuilder<'a, C, IrcIn<C>, IrcOut<C>> = RootBuilder::new(rc);
     let order = rc.topo_order();
     for &circuit_id in order.iter().rev() {
         let (new_circuit, final_builder) =
-            process_circuit(&mut root, circuit_id, rc.circuits.get(&circuit_id).unwrap())?;
+            process_circuit(&root, circuit_id, rc.circuits.get(&circuit_id).unwrap())?;
         root.out_circuits.insert(circuit_id, new_circuit);
         root.builders.insert(circuit_id, final_builder);
     }
@@ -241,16 +240,13 @@ mod tests {
 
     use mersenne31::M31;
 
+    use crate::circuit::ir::{
+        self,
+        common::rand_gen::*,
+        expr::{Expression, Term},
+    };
     use crate::field::FieldArith;
     use crate::frontend::M31Config as C;
-    use crate::{
-        circuit::ir::{
-            self,
-            common::rand_gen::*,
-            expr::{Expression, Term},
-        },
-        utils::error::Error,
-    };
 
     type CField = M31;
 
@@ -273,7 +269,7 @@ mod tests {
                     ],
                     constant: CField::from(3 as u32),
                 })],
-                constraints: vec![3],
+                constraints: vec![3.into()],
                 outputs: vec![],
                 num_inputs: 2,
             },
@@ -302,7 +298,7 @@ mod tests {
             0,
             ir::common::Circuit::<super::IrcIn<C>> {
                 instructions: vec![ir::hint_less::Instruction::Mul(vec![1, 2, 3, 4])],
-                constraints: vec![5],
+                constraints: vec![5.into()],
                 outputs: vec![5],
                 num_inputs: 4,
             },
@@ -352,12 +348,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -393,12 +388,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -1,9 +1,15 @@
 use std::collections::{BinaryHeap, HashMap};
 
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serdes::ExpSerde;
+
 use crate::{
     circuit::{
         config::Config,
-        costs::{cost_of_compress, cost_of_multiply, cost_of_possible_references, cost_of_relay},
+        costs::{
+            cost_of_compress, cost_of_multiply, cost_of_possible_references, cost_of_relay,
+            CostModel,
+        },
         ir::{
             common::Instruction,
             dest::{
@@ -17,6 +23,7 @@ use crate::{
         },
         layered::Coef,
     },
+    compile::cache::{CacheKey, CacheKeyHasher, CompileCache},
     field::{Field, FieldArith},
     frontend::CircuitField,
     utils::{error::Error, pool::Pool},
@@ -27,8 +34,10 @@ use super::basic::LinMeta;
 const COMPRESS_THRESHOLD: usize = 64;
 
 struct RootBuilder<C: Config> {
-    builders: HashMap<usize, Builder<C>>,
+    // layer of the outputs of each processed circuit
+    output_layers: HashMap<usize, usize>,
     out_circuits: HashMap<usize, OutCircuit<C>>,
+    cost_model: CostModel,
 }
 
 struct Builder<C: Config> {
@@ -51,6 +60,8 @@ struct Builder<C: Config> {
     out_insns: Vec<(usize, OutInstruction<C>)>,
 
     output_layer: usize,
+
+    cost_model: CostModel,
 }
 
 #[derive(Hash, PartialEq, Eq, Clone)]
@@ -84,7 +95,7 @@ impl<C: Config> Default for MidVarCoef<C> {
 }
 
 impl<C: Config> Builder<C> {
-    fn new() -> Self {
+    fn new(cost_model: CostModel) -> Self {
         let mut res = Builder {
             in_var_ref_counts: vec![InVarRefCounts::default()],
             in_var_exprs: vec![Expression::default()],
@@ -93,6 +104,7 @@ impl<C: Config> Builder<C> {
             mid_var_layer: vec![0],
             out_insns: Vec::new(),
             output_layer: 0,
+            cost_model,
         };
         res.stripped_mid_vars.add(&MidVarKey {
             expr: Expression::invalid(),
@@ -419,14 +431,15 @@ impl<C: Config> Builder<C> {
             assert!(dcnt2[2] == 0);
             let v1layer = self.layer_of_expr(&expr1);
             let v2layer = self.layer_of_expr(&expr2);
-            let mut cost_direct = cost_of_multiply::<C>(dcnt1[0], dcnt1[1], dcnt2[0], dcnt2[1]);
+            let cm = self.cost_model;
+            let mut cost_direct = cost_of_multiply(&cm, dcnt1[0], dcnt1[1], dcnt2[0], dcnt2[1]);
             let mut cost_compress_v1 =
-                cost_of_multiply::<C>(0, 1, dcnt2[0], dcnt2[1]) + cost_of_compress::<C>(&dcnt1);
+                cost_of_multiply(&cm, 0, 1, dcnt2[0], dcnt2[1]) + cost_of_compress(&cm, &dcnt1);
             let mut cost_compress_v2 =
-                cost_of_multiply::<C>(dcnt1[0], dcnt1[1], 0, 1) + cost_of_compress::<C>(&dcnt2);
-            let cost_compress_both = cost_of_multiply::<C>(0, 1, 0, 1)
-                + cost_of_compress::<C>(&dcnt1)
-                + cost_of_compress::<C>(&dcnt2);
+                cost_of_multiply(&cm, dcnt1[0], dcnt1[1], 0, 1) + cost_of_compress(&cm, &dcnt2);
+            let cost_compress_both = cost_of_multiply(&cm, 0, 1, 0, 1)
+                + cost_of_compress(&cm, &dcnt1)
+                + cost_of_compress(&cm, &dcnt2);
             let (compress_some, compress_1) = if v1layer == v2layer {
                 (
                     cost_compress_v1
@@ -436,9 +449,9 @@ impl<C: Config> Builder<C> {
                     cost_compress_v1 < cost_compress_v2.min(cost_compress_both),
                 )
             } else {
-                cost_direct += cost_of_relay::<C>(v1layer, v2layer);
-                cost_compress_v1 += cost_of_relay::<C>(v1layer + 1, v2layer);
-                cost_compress_v2 += cost_of_relay::<C>(v1layer, v2layer + 1);
+                cost_direct += cost_of_relay(&cm, v1layer, v2layer);
+                cost_compress_v1 += cost_of_relay(&cm, v1layer + 1, v2layer);
+                cost_compress_v2 += cost_of_relay(&cm, v1layer, v2layer + 1);
                 if cost_compress_v1 < cost_direct {
                     (expr1.len() > 2, true)
                 } else if cost_compress_v2 < cost_direct {
@@ -474,10 +487,11 @@ impl<C: Config> Builder<C> {
         let degree_count = e.count_of_degrees();
         let mut should_compress = ref_count.single > 0;
         should_compress |= degree_count.iter().sum::<usize>() > COMPRESS_THRESHOLD;
+        let cm = self.cost_model;
         let cost_no_compress =
-            cost_of_possible_references::<C>(&degree_count, ref_count.add, ref_count.mul);
-        let cost_compress = cost_of_compress::<C>(&degree_count)
-            + cost_of_possible_references::<C>(&[0, 1, 0], ref_count.add, ref_count.mul);
+            cost_of_possible_references(&cm, &degree_count, ref_count.add, ref_count.mul);
+        let cost_compress = cost_of_compress(&cm, &degree_count)
+            + cost_of_possible_references(&cm, &[0, 1, 0], ref_count.add, ref_count.mul);
         should_compress |= cost_compress < cost_no_compress;
         should_compress &= e.degree() > 0;
         if should_compress {
@@ -534,10 +548,10 @@ fn unstrip_constants_single<C: Config>(
 }
 
 fn process_circuit<C: Config>(
-    root: &mut RootBuilder<C>,
+    root: &RootBuilder<C>,
     circuit: &InCircuit<C>,
 ) -> Result<(OutCircuit<C>, Builder<C>), Error> {
-    let mut builder = Builder::new();
+    let mut builder = Builder::new(root.cost_model);
 
     // initialize in_var_ref_counts
     for _ in 0..circuit.get_num_inputs_all() {
@@ -575,7 +589,7 @@ fn process_circuit<C: Config>(
         builder.in_var_ref_counts[*out].single += 1;
     }
     for con in circuit.constraints.iter() {
-        builder.in_var_ref_counts[*con].single += 1;
+        builder.in_var_ref_counts[con.var].single += 1;
     }
 
     // add inputs
@@ -620,7 +634,7 @@ fn process_circuit<C: Config>(
                 inputs,
                 num_outputs,
             } => {
-                let sub_builder = root.builders.get(sub_circuit_id).unwrap();
+                let sub_output_layer = *root.output_layers.get(sub_circuit_id).unwrap();
                 let single_inputs: Vec<usize> = inputs
                     .iter()
                     .map(|&var| builder.make_really_single(builder.in_var_exprs[var].clone()))
@@ -638,7 +652,7 @@ fn process_circuit<C: Config>(
                         num_outputs: *num_outputs,
                     },
                 ));
-                builder.add_in_vars(*num_outputs, max_input_layer + sub_builder.output_layer);
+                builder.add_in_vars(*num_outputs, max_input_layer + sub_output_layer);
             }
             InInstruction::CustomGate { gate_type, inputs } => {
                 let single_inputs: Vec<usize> = inputs
@@ -656,7 +670,7 @@ fn process_circuit<C: Config>(
 
     // constraints and outputs
     let mut constraints: Vec<usize> = Vec::new();
-    let mut cons_keys = circuit.constraints.clone();
+    let mut cons_keys: Vec<usize> = circuit.constraints.iter().map(|x| x.var).collect();
     cons_keys.sort();
     for con in cons_keys.iter() {
         let e = builder.in_var_exprs[*con].clone();
@@ -719,16 +733,93 @@ fn process_circuit<C: Config>(
 }
 
 pub fn process<C: Config>(rc: &InRootCircuit<C>) -> Result<OutRootCircuit<C>, Error> {
+    process_with_cost_model(rc, &CostModel::of::<C>())
+}
+
+pub fn process_with_cost_model<C: Config>(
+    rc: &InRootCircuit<C>,
+    cost_model: &CostModel,
+) -> Result<OutRootCircuit<C>, Error> {
+    process_inner(rc, cost_model, None)
+}
+
+/// Same as `process_with_cost_model`, but reuses the circuits built by previous compilations
+/// when neither they nor their sub circuits changed.
+pub fn process_with_cache<C: Config>(
+    rc: &InRootCircuit<C>,
+    cost_model: &CostModel,
+    cache: &CompileCache,
+) -> Result<OutRootCircuit<C>, Error> {
+    process_inner(rc, cost_model, Some(cache))
+}
+
+#[derive(ExpSerde)]
+struct CachedCircuit<C: Config> {
+    output_layer: usize,
+    circuit: OutCircuit<C>,
+}
+
+const CACHE_STAGE: &str = "final_build";
+
+fn cache_key<C: Config>(
+    circuit: &InCircuit<C>,
+    cost_model: &CostModel,
+    keys: &HashMap<usize, CacheKey>,
+) -> CacheKey {
+    let mut hasher = CacheKeyHasher::new::<C>(CACHE_STAGE);
+    hasher.update_cost_model(cost_model);
+    hasher.update_serde(circuit);
+    for insn in circuit.instructions.iter() {
+        if let InInstruction::SubCircuitCall { sub_circuit_id, .. } = insn {
+            hasher.update(&keys[sub_circuit_id]);
+        }
+    }
+    hasher.finalize()
+}
+
+fn process_inner<C: Config>(
+    rc: &InRootCircuit<C>,
+    cost_model: &CostModel,
+    cache: Option<&CompileCache>,
+) -> Result<OutRootCircuit<C>, Error> {
     let mut root: RootBuilder<C> = RootBuilder {
-        builders: HashMap::new(),
+        output_layers: HashMap::new(),
         out_circuits: HashMap::new(),
+        cost_model: *cost_model,
     };
-    let order = rc.topo_order();
-    for &circuit_id in order.iter().rev() {
-        let (new_circuit, final_builder) =
-            process_circuit(&mut root, rc.circuits.get(&circuit_id).unwrap())?;
-        root.out_circuits.insert(circuit_id, new_circuit);
-        root.builders.insert(circuit_id, final_builder);
+    let mut keys: HashMap<usize, CacheKey> = HashMap::new();
+    // a circuit only depends on its sub circuits, so each level is processed in parallel
+    for level in rc.topo_levels() {
+        let results: Vec<Result<(Option<CacheKey>, CachedCircuit<C>), Error>> = level
+            .par_iter()
+            .map(|circuit_id| {
+                let circuit = rc.circuits.get(circuit_id).unwrap();
+                let key = cache.map(|_| cache_key(circuit, cost_model, &keys));
+                let cached = cache
+                    .zip(key.as_ref())
+                    .and_then(|(cache, key)| cache.get::<CachedCircuit<C>>(CACHE_STAGE, key));
+                if let Some(entry) = cached {
+                    return Ok((key, entry));
+                }
+                let (new_circuit, final_builder) = process_circuit(&root, circuit)?;
+                let entry = CachedCircuit {
+                    output_layer: final_builder.output_layer,
+                    circuit: new_circuit,
+                };
+                if let Some((cache, key)) = cache.zip(key.as_ref()) {
+                    cache.put(CACHE_STAGE, key, &entry);
+                }
+                Ok((key, entry))
+            })
+            .collect();
+        for (&circuit_id, res) in level.iter().zip(results) {
+            let (key, entry) = res?;
+            if let Some(key) = key {
+                keys.insert(circuit_id, key);
+            }
+            root.out_circuits.insert(circuit_id, entry.circuit);
+            root.output_layers.insert(circuit_id, entry.output_layer);
+        }
     }
     Ok(OutRootCircuit {
         num_public_inputs: rc.num_public_inputs,
@@ -743,16 +834,13 @@ mod tests {
 
     use mersenne31::M31;
 
+    use crate::circuit::ir::{
+        self,
+        common::rand_gen::*,
+        expr::{Expression, Term},
+    };
     use crate::field::FieldArith;
     use crate::frontend::M31Config as C;
-    use crate::{
-        circuit::ir::{
-            self,
-            common::rand_gen::*,
-            expr::{Expression, Term},
-        },
-        utils::error::Error,
-    };
 
     type CField = M31;
 
@@ -775,7 +863,7 @@ mod tests {
                     ],
                     constant: CField::from(3 as u32),
                 })],
-                constraints: vec![3],
+                constraints: vec![3.into()],
                 outputs: vec![],
                 num_inputs: 2,
             },
@@ -804,7 +892,7 @@ mod tests {
             0,
             super::InCircuit::<C> {
                 instructions: vec![ir::hint_less::Instruction::Mul(vec![1, 2, 3, 4])],
-                constraints: vec![5],
+                constraints: vec![5.into()],
                 outputs: vec![5],
                 num_inputs: 4,
             },
@@ -855,12 +943,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -896,12 +983,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -923,7 +1009,7 @@ mod tests {
             0,
             super::InCircuit::<C> {
                 instructions: vec![super::InInstruction::<C>::LinComb(lc.clone())],
-                constraints: vec![100001],
+                constraints: vec![100001.into()],
                 outputs: vec![],
                 num_inputs: 100000,
             },
@@ -952,7 +1038,7 @@ mod tests {
             0,
             super::InCircuit::<C> {
                 instructions: vec![super::InInstruction::<C>::Mul(terms.clone())],
-                constraints: vec![100001],
+                constraints: vec![100001.into()],
                 outputs: vec![],
                 num_inputs: 100000,
             },
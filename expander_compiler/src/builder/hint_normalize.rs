@@ -1,11 +1,13 @@
-use crate::circuit::ir::common::RawConstraint;
+use crate::circuit::ir::common::TracedConstraint;
 use crate::circuit::ir::expr;
+use crate::compile::cache::CompileCache;
 use crate::field::FieldArith;
 use crate::frontend::CircuitField;
 use crate::utils::error::Error;
 use crate::{
     circuit::{
         config::Config,
+        costs::{cost_of_mux_multilinear, cost_of_mux_tree, CostModel},
         ir::{
             self,
             expr::{LinComb, LinCombTerm},
@@ -84,6 +86,105 @@ impl<'a, C: Config> Builder<'a, C> {
         let t = self.bool_cond(a);
         self.mark((), t);
     }
+    fn mux(&mut self, selectors: &[usize], values: &[usize]) -> usize {
+        if selectors.is_empty() {
+            return values[0];
+        }
+        let value_is_const: Vec<bool> = values
+            .iter()
+            .map(|v| self.constant_value(*v).is_some())
+            .collect();
+        if cost_of_mux_multilinear(&self.cost_model, &value_is_const)
+            < cost_of_mux_tree(&self.cost_model, &value_is_const)
+        {
+            self.mux_multilinear(selectors, values)
+        } else {
+            self.mux_tree(selectors, values)
+        }
+    }
+    fn mux_tree(&mut self, selectors: &[usize], values: &[usize]) -> usize {
+        let mut cur = values.to_vec();
+        for &s in selectors.iter() {
+            let mut next = Vec::with_capacity(cur.len() / 2);
+            for pair in cur.chunks(2) {
+                let diff = self.push_sub(pair[1], pair[0]);
+                let t = self.push_mul(s, diff);
+                next.push(self.push_add(pair[0], t));
+            }
+            cur = next;
+        }
+        cur[0]
+    }
+    // product of the selectors in the subset t, computed on demand
+    fn mux_monomial(&mut self, selectors: &[usize], monomials: &mut [usize], t: usize) -> usize {
+        if monomials[t] == 0 {
+            let low = t.trailing_zeros() as usize;
+            let rest = t & (t - 1);
+            monomials[t] = if rest == 0 {
+                selectors[low]
+            } else {
+                let m = self.mux_monomial(selectors, monomials, rest);
+                self.push_mul(m, selectors[low])
+            };
+        }
+        monomials[t]
+    }
+    fn mux_multilinear(&mut self, selectors: &[usize], values: &[usize]) -> usize {
+        let one = CircuitField::<C>::one();
+        let mut monomials = vec![0; values.len()];
+        let mut terms = Vec::new();
+        let mut constant = CircuitField::<C>::zero();
+        for t in 0..values.len() {
+            // c_t = sum_{u subset of t} (-1)^{|t - u|} values[u]
+            let mut c_terms = Vec::new();
+            let mut c_constant = CircuitField::<C>::zero();
+            let mut u = t;
+            loop {
+                let sign = if (t ^ u).count_ones() % 2 == 0 {
+                    one
+                } else {
+                    -one
+                };
+                match self.constant_value(values[u]) {
+                    Some(v) => c_constant += sign * v,
+                    None => c_terms.push(LinCombTerm {
+                        coef: sign,
+                        var: values[u],
+                    }),
+                }
+                if u == 0 {
+                    break;
+                }
+                u = (u - 1) & t;
+            }
+            if t == 0 {
+                terms.extend(c_terms);
+                constant = c_constant;
+            } else if c_terms.is_empty() {
+                if !c_constant.is_zero() {
+                    let m = self.mux_monomial(selectors, &mut monomials, t);
+                    terms.push(LinCombTerm {
+                        coef: c_constant,
+                        var: m,
+                    });
+                }
+            } else {
+                let c = self
+                    .push_insn(InsnOut::LinComb(LinComb {
+                        terms: c_terms,
+                        constant: c_constant,
+                    }))
+                    .unwrap();
+                let m = self.mux_monomial(selectors, &mut monomials, t);
+                terms.push(LinCombTerm {
+                    coef: one,
+                    var: self.push_mul(c, m),
+                });
+            }
+        }
+        self.push_insn(InsnOut::LinComb(LinComb { terms, constant }))
+            .unwrap()
+    }
 }
 
 impl<'a, C: Config> InsnTransformAndExecute<'a, C, IrcIn<C>, IrcOut<C>> for Builder<'a, C> {
@@ -301,16 +402,48 @@ impl<'a, C: Config> InsnTransformAndExecute<'a, C, IrcIn<C>, IrcOut<C>> for Buil
                 self.assert((), sum);
                 return self.copys(&bits);
             }
+            Mux { selectors, values } => {
+                // constant selectors only restrict the values
+                let mut fixed_mask = 0;
+                let mut fixed_bits = 0;
+                let mut var_selectors = Vec::new();
+                for (i, &s) in selectors.iter().enumerate() {
+                    match self.constant_value(s) {
+                        Some(v) if v.is_zero() => fixed_mask |= 1 << i,
+                        Some(v) if v == CircuitField::<C>::one() => {
+                            fixed_mask |= 1 << i;
+                            fixed_bits |= 1 << i;
+                        }
+                        Some(_) => {
+                            return InsnTransformResult::Err(Error::UserError(
+                                "invalid bool value".to_string(),
+                            ))
+                        }
+                        None => var_selectors.push(s),
+                    }
+                }
+                let values: Vec<usize> = values
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| i & fixed_mask == fixed_bits)
+                    .map(|(_, v)| *v)
+                    .collect();
+                for &s in var_selectors.iter() {
+                    self.assert_bool(s);
+                }
+                let res = self.mux(&var_selectors, &values);
+                return self.copy(res);
+            }
         })
     }
 
     fn transform_in_con_to_out(
         &mut self,
         in_con: &ir::source::Constraint,
-    ) -> Result<RawConstraint, Error> {
+    ) -> Result<TracedConstraint, Error> {
         match in_con.typ {
-            ir::source::ConstraintType::Zero => Ok(in_con.var),
-            ir::source::ConstraintType::Bool => Ok(self.bool_cond(in_con.var)),
+            ir::source::ConstraintType::Zero => Ok(in_con.var.into()),
+            ir::source::ConstraintType::Bool => Ok(self.bool_cond(in_con.var).into()),
             ir::source::ConstraintType::NonZero => {
                 let one = self.push_const(CircuitField::<C>::one());
                 let inv = self
@@ -322,7 +455,7 @@ impl<'a, C: Config> InsnTransformAndExecute<'a, C, IrcIn<C>, IrcOut<C>> for Buil
                     .unwrap();
                 let multy = self.push_mul(in_con.var, inv);
                 let sub1 = self.push_sub(multy, one);
-                Ok(sub1)
+                Ok(sub1.into())
             }
         }
     }
@@ -372,17 +505,34 @@ impl<'a, C: Config> InsnTransformAndExecute<'a, C, IrcIn<C>, IrcOut<C>> for Buil
 pub fn process<C: Config>(
     rc: &ir::common::RootCircuit<IrcIn<C>>,
 ) -> Result<ir::common::RootCircuit<IrcOut<C>>, Error> {
-    process_root_circuit(rc)
+    process_with_cost_model(rc, &CostModel::of::<C>())
 }
 
+pub fn process_with_cost_model<C: Config>(
+    rc: &ir::common::RootCircuit<IrcIn<C>>,
+    cost_model: &CostModel,
+) -> Result<ir::common::RootCircuit<IrcOut<C>>, Error> {
+    process_root_circuit(rc, cost_model, None, CACHE_STAGE)
+}
+
+/// Same as `process_with_cost_model`, but reuses the circuits normalized by previous
+/// compilations when neither they nor their sub circuits changed. The constraints of the
+/// reused circuits have no origin.
+pub fn process_with_cache<C: Config>(
+    rc: &ir::common::RootCircuit<IrcIn<C>>,
+    cost_model: &CostModel,
+    cache: &CompileCache,
+) -> Result<ir::common::RootCircuit<IrcOut<C>>, Error> {
+    process_root_circuit(rc, cost_model, Some(cache), CACHE_STAGE)
+}
+
+const CACHE_STAGE: &str = "hint_normalize";
+
 #[cfg(test)]
 mod tests {
+    use crate::circuit::ir::{self, common::rand_gen::*};
     use crate::field::FieldArith;
     use crate::frontend::M31Config as C;
-    use crate::{
-        circuit::ir::{self, common::rand_gen::*},
-        utils::error::Error,
-    };
 
     use mersenne31::M31;
 
@@ -408,10 +558,10 @@ mod tests {
             0,
             ir::common::Circuit::<super::IrcIn<C>> {
                 instructions: vec![ir::source::Instruction::LinComb(lc.clone())],
-                constraints: vec![ir::source::Constraint {
-                    typ: ir::source::ConstraintType::Zero,
-                    var: 3,
-                }],
+                constraints: vec![ir::source::Constraint::new(
+                    3,
+                    ir::source::ConstraintType::Zero,
+                )],
                 outputs: vec![],
                 num_inputs: 2,
             },
@@ -460,12 +610,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -503,12 +652,11 @@ mod tests {
                         }
                     }
                 }
-                Err(e) => match e {
-                    Error::UserError(_) => {}
-                    Error::InternalError(e) => {
+                Err(e) => {
+                    if e.is_internal() {
                         panic!("{:?}", e);
                     }
-                },
+                }
             }
         }
     }
@@ -530,10 +678,10 @@ mod tests {
             0,
             ir::common::Circuit::<super::IrcIn<C>> {
                 instructions: vec![ir::source::Instruction::LinComb(lc.clone())],
-                constraints: vec![ir::source::Constraint {
-                    typ: ir::source::ConstraintType::Zero,
-                    var: 100001,
-                }],
+                constraints: vec![ir::source::Constraint::new(
+                    100001,
+                    ir::source::ConstraintType::Zero,
+                )],
                 outputs: vec![],
                 num_inputs: 100000,
             },
@@ -557,10 +705,10 @@ mod tests {
             0,
             ir::common::Circuit::<super::IrcIn<C>> {
                 instructions: vec![ir::source::Instruction::Mul(terms.clone())],
-                constraints: vec![ir::source::Constraint {
-                    typ: ir::source::ConstraintType::Zero,
-                    var: 100001,
-                }],
+                constraints: vec![ir::source::Constraint::new(
+                    100001,
+                    ir::source::ConstraintType::Zero,
+                )],
                 outputs: vec![],
                 num_inputs: 100000,
             },
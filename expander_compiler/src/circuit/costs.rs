@@ -1,33 +1,108 @@
 use super::config::Config;
 
-pub fn cost_of_compress<C: Config>(deg_cnt: &[usize; 3]) -> usize {
-    C::COST_MUL * deg_cnt[2]
-        + C::COST_ADD * deg_cnt[1]
-        + C::COST_CONST * deg_cnt[0]
-        + C::COST_VARIABLE
+/// Relative costs of the parts of a layered circuit, used by the compiler to choose between
+/// equivalent forms of an expression: the lowering of a mux, and whether an expression is
+/// compressed into a new variable on its own layer. The layout of the variables within a
+/// layer doesn't use it. By default they are the associated constants of the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CostModel {
+    pub input: usize,
+    pub variable: usize,
+    pub mul: usize,
+    pub add: usize,
+    pub constant: usize,
 }
 
-pub fn cost_of_multiply<C: Config>(
+impl CostModel {
+    pub fn of<C: Config>() -> Self {
+        CostModel {
+            input: C::COST_INPUT,
+            variable: C::COST_VARIABLE,
+            mul: C::COST_MUL,
+            add: C::COST_ADD,
+            constant: C::COST_CONST,
+        }
+    }
+}
+
+pub fn cost_of_compress(cm: &CostModel, deg_cnt: &[usize; 3]) -> usize {
+    cm.mul * deg_cnt[2] + cm.add * deg_cnt[1] + cm.constant * deg_cnt[0] + cm.variable
+}
+
+pub fn cost_of_multiply(
+    cm: &CostModel,
     a_deg_0: usize,
     a_deg_1: usize,
     b_deg_0: usize,
     b_deg_1: usize,
 ) -> usize {
-    C::COST_MUL * (a_deg_1 * b_deg_1)
-        + C::COST_ADD * (a_deg_0 * b_deg_1 + a_deg_1 * b_deg_0)
-        + C::COST_CONST * (a_deg_0 * b_deg_0)
+    cm.mul * (a_deg_1 * b_deg_1)
+        + cm.add * (a_deg_0 * b_deg_1 + a_deg_1 * b_deg_0)
+        + cm.constant * (a_deg_0 * b_deg_0)
 }
 
-pub fn cost_of_possible_references<C: Config>(
+pub fn cost_of_possible_references(
+    cm: &CostModel,
     deg_cnt: &[usize; 3],
     ref_add: usize,
     ref_mul: usize,
 ) -> usize {
-    C::COST_CONST * (deg_cnt[0] * ref_add)
-        + C::COST_ADD * (deg_cnt[1] * ref_add + deg_cnt[0] * ref_mul)
-        + C::COST_MUL * (deg_cnt[2] * ref_add + (deg_cnt[1] + deg_cnt[2] * 2) * ref_mul)
+    cm.constant * (deg_cnt[0] * ref_add)
+        + cm.add * (deg_cnt[1] * ref_add + deg_cnt[0] * ref_mul)
+        + cm.mul * (deg_cnt[2] * ref_add + (deg_cnt[1] + deg_cnt[2] * 2) * ref_mul)
+}
+
+pub fn cost_of_relay(cm: &CostModel, v1_layer: usize, v2_layer: usize) -> usize {
+    (v1_layer as isize - v2_layer as isize).unsigned_abs() * (cm.variable + cm.add)
+}
+
+/// Estimated cost of a mux lowered to a tree of selects `lo + s * (hi - lo)`, one selector per level.
+pub fn cost_of_mux_tree(cm: &CostModel, value_is_const: &[bool]) -> usize {
+    let mut cost = 0;
+    let mut cur = value_is_const.to_vec();
+    while cur.len() > 1 {
+        let mut next = Vec::with_capacity(cur.len() / 2);
+        for pair in cur.chunks(2) {
+            let (lo, hi) = (pair[0], pair[1]);
+            let diff_deg_0 = (lo || hi) as usize;
+            let diff_deg_1 = !lo as usize + !hi as usize;
+            cost += cost_of_multiply(cm, 0, 1, diff_deg_0, diff_deg_1);
+            cost += cost_of_compress(cm, &[lo as usize, diff_deg_0 + !lo as usize, diff_deg_1]);
+            next.push(false);
+        }
+        cur = next;
+    }
+    cost
 }
 
-pub fn cost_of_relay<C: Config>(v1_layer: usize, v2_layer: usize) -> usize {
-    (v1_layer as isize - v2_layer as isize).unsigned_abs() * (C::COST_VARIABLE + C::COST_ADD)
+/// Estimated cost of a mux lowered to its multilinear expansion `sum_T c_T * prod_{i in T} s_i`,
+/// where each `c_T` is a signed sum of the values whose index is a subset of `T`.
+pub fn cost_of_mux_multilinear(cm: &CostModel, value_is_const: &[bool]) -> usize {
+    let n = value_is_const.len();
+    // number of variable and constant values with index in each subset, by subset sums
+    let mut num_var: Vec<usize> = value_is_const.iter().map(|c| !c as usize).collect();
+    let mut num_const: Vec<usize> = value_is_const.iter().map(|c| *c as usize).collect();
+    let mut bit = 1;
+    while bit < n {
+        for t in 0..n {
+            if t & bit != 0 {
+                num_var[t] += num_var[t ^ bit];
+                num_const[t] += num_const[t ^ bit];
+            }
+        }
+        bit <<= 1;
+    }
+    let mut cost = 0;
+    let mut deg_cnt = [value_is_const[0] as usize, !value_is_const[0] as usize, 0];
+    for t in 1..n {
+        if t.count_ones() >= 2 {
+            // the product of the selectors in t
+            cost += cost_of_multiply(cm, 0, 1, 0, 1) + cost_of_compress(cm, &[0, 0, 1]);
+        }
+        let c_deg_0 = (num_const[t] > 0) as usize;
+        cost += cost_of_multiply(cm, 0, 1, c_deg_0, num_var[t]);
+        deg_cnt[1] += c_deg_0;
+        deg_cnt[2] += num_var[t];
+    }
+    cost + cost_of_compress(cm, &deg_cnt)
 }
@@ -0,0 +1,99 @@
+use crate::{
+    circuit::{config::Config, ir::expr::LinComb},
+    field::FieldArith,
+    frontend::CircuitField,
+};
+
+use super::{Circuit, Constraint, ConstraintType, HashMap, Instruction, IrConfig, RootCircuit};
+
+pub trait ConstFoldInstruction<C: Config>: Instruction<C> {
+    /// Returns the value of the only output, if the instruction is a constant.
+    fn constant_value(&self) -> Option<CircuitField<C>>;
+    /// Returns a simpler instruction with the same outputs, given the values of the variables
+    /// known to be constant, or None if it can't be simplified.
+    /// The simplified instruction must keep every constraint the original one implies.
+    fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(&self, value_of: F) -> Option<Self>;
+}
+
+pub enum FoldedMul<C: Config> {
+    LinComb(LinComb<C>),
+    Mul(Vec<usize>),
+}
+
+/// Simplifies the product of `inputs`, e.g. `x*0` to `0` and `x*1` to `x`.
+pub fn fold_mul<C: Config, F: Fn(usize) -> Option<CircuitField<C>>>(
+    inputs: &[usize],
+    value_of: F,
+) -> Option<FoldedMul<C>> {
+    let mut product = CircuitField::<C>::one();
+    let mut rest = Vec::with_capacity(inputs.len());
+    for &x in inputs.iter() {
+        match value_of(x) {
+            Some(value) => product *= value,
+            None => rest.push(x),
+        }
+    }
+    if rest.len() == inputs.len() {
+        return None;
+    }
+    if product.is_zero() || rest.is_empty() {
+        return Some(FoldedMul::LinComb(LinComb::from_kx_plus_b(
+            0,
+            CircuitField::<C>::zero(),
+            product,
+        )));
+    }
+    if rest.len() == 1 {
+        return Some(FoldedMul::LinComb(LinComb::from_kx_plus_b(
+            rest[0],
+            product,
+            CircuitField::<C>::zero(),
+        )));
+    }
+    if product == CircuitField::<C>::one() {
+        return Some(FoldedMul::Mul(rest));
+    }
+    None
+}
+
+impl<Irc: IrConfig> RootCircuit<Irc>
+where
+    Irc::Instruction: ConstFoldInstruction<Irc::Config>,
+{
+    /// Simplifies the instructions using the constants in each circuit, and removes the
+    /// constraints on constants that are satisfied.
+    /// Constants aren't propagated through sub circuit calls.
+    pub fn fold_constants(&mut self) {
+        for circuit in self.circuits.values_mut() {
+            circuit.fold_constants();
+        }
+    }
+}
+
+impl<Irc: IrConfig> Circuit<Irc>
+where
+    Irc::Instruction: ConstFoldInstruction<Irc::Config>,
+{
+    /// Returns the variables known to be constant after folding.
+    pub fn fold_constants(&mut self) -> HashMap<usize, CircuitField<Irc::Config>> {
+        let mut values = HashMap::new();
+        let mut cur_var = self.get_num_inputs_all();
+        for insn in self.instructions.iter_mut() {
+            if let Some(folded) = insn.fold_constants(|x| values.get(&x).cloned()) {
+                *insn = folded;
+            }
+            if insn.num_outputs() == 1 {
+                if let Some(value) = insn.constant_value() {
+                    values.insert(cur_var + 1, value);
+                }
+            }
+            cur_var += insn.num_outputs();
+        }
+        self.constraints
+            .retain(|cons| match values.get(&cons.var()) {
+                Some(value) => !cons.typ().verify(value),
+                None => true,
+            });
+        values
+    }
+}
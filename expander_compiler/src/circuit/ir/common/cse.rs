@@ -0,0 +1,92 @@
+use crate::circuit::config::Config;
+
+use super::{Circuit, Constraint, HashMap, HashSet, Instruction, IrConfig, RootCircuit};
+
+pub trait CseInstruction<C: Config>: Instruction<C> {
+    /// Returns the canonical form of the instruction, or None if two copies of it may produce
+    /// different values (e.g. hints and random constants).
+    /// Instructions with equal keys and the same variables always have the same outputs.
+    /// Sub circuit calls are handled by the pass itself, and should return None.
+    fn cse_key(&self) -> Option<Self>;
+}
+
+impl<Irc: IrConfig> RootCircuit<Irc>
+where
+    Irc::Instruction: CseInstruction<Irc::Config>,
+{
+    /// Merges instructions that always compute the same values, and rewrites their uses.
+    /// Calls to sub circuits are merged only if the sub circuit, and everything it calls,
+    /// consists of mergeable instructions.
+    pub fn eliminate_common_subexpressions(&mut self) {
+        let order = self.topo_order();
+        let mut pure_circuits: HashSet<usize> = HashSet::new();
+        for circuit_id in order.iter().rev() {
+            let circuit = self.circuits.get_mut(circuit_id).unwrap();
+            if circuit.eliminate_common_subexpressions(&pure_circuits) {
+                pure_circuits.insert(*circuit_id);
+            }
+        }
+    }
+}
+
+impl<Irc: IrConfig> Circuit<Irc>
+where
+    Irc::Instruction: CseInstruction<Irc::Config>,
+{
+    // returns whether the circuit is pure
+    fn eliminate_common_subexpressions(&mut self, pure_circuits: &HashSet<usize>) -> bool {
+        let mut is_pure = true;
+        let mut var_map = Vec::with_capacity(self.get_num_variables() + 1);
+        var_map.push(0);
+        for i in 1..=self.get_num_inputs_all() {
+            var_map.push(i);
+        }
+        let mut seen: HashMap<Irc::Instruction, usize> = HashMap::new();
+        let mut new_instructions = Vec::with_capacity(self.instructions.len());
+        let mut num_vars = var_map.len();
+        for insn in self.instructions.iter() {
+            let insn = insn.replace_vars(|x| var_map[x]);
+            let key = match insn.as_sub_circuit_call() {
+                Some((sub_circuit_id, _, _)) => {
+                    if pure_circuits.contains(&sub_circuit_id) {
+                        Some(insn.clone())
+                    } else {
+                        None
+                    }
+                }
+                None => insn.cse_key(),
+            };
+            if key.is_none() {
+                is_pure = false;
+            }
+            let num_outputs = insn.num_outputs();
+            if let Some(first) = key.as_ref().and_then(|k| seen.get(k)) {
+                for i in 0..num_outputs {
+                    var_map.push(first + i);
+                }
+                continue;
+            }
+            if let Some(key) = key {
+                seen.insert(key, num_vars);
+            }
+            for i in 0..num_outputs {
+                var_map.push(num_vars + i);
+            }
+            num_vars += num_outputs;
+            new_instructions.push(insn);
+        }
+        self.instructions = new_instructions;
+        // merged variables may now have the same constraint several times
+        let mut seen_constraints: HashSet<Irc::Constraint> = HashSet::new();
+        self.constraints = self
+            .constraints
+            .iter()
+            .map(|cons| cons.replace_var(|x| var_map[x]))
+            .filter(|cons| seen_constraints.insert(cons.clone()))
+            .collect();
+        for out in self.outputs.iter_mut() {
+            *out = var_map[*out];
+        }
+        is_pure
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use super::{Circuit, Instruction, IrConfig, RootCircuit};
+use super::{Circuit, Instruction, IrConfig, RootCircuit, TracedConstraint};
 
 impl<Irc: IrConfig> fmt::Display for Circuit<Irc>
 where
@@ -60,3 +60,9 @@ where
         Ok(())
     }
 }
+
+impl fmt::Display for TracedConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.var)
+    }
+}
@@ -0,0 +1,51 @@
+use std::{collections::HashMap, fmt::Write};
+
+use super::{Instruction, IrConfig, RootCircuit};
+
+impl<Irc: IrConfig> RootCircuit<Irc> {
+    /// Renders the sub circuit call graph in Graphviz DOT format. Edges are labelled with
+    /// the number of call sites.
+    pub fn to_dot(&self) -> String {
+        let mut ids: Vec<usize> = self.circuits.keys().copied().collect();
+        ids.sort();
+        let mut res = String::new();
+        writeln!(res, "digraph circuits {{").unwrap();
+        writeln!(res, "  node [shape=box];").unwrap();
+        for id in ids.iter() {
+            let c = &self.circuits[id];
+            writeln!(
+                res,
+                "  c{id} [label=\"circuit {id}\\ninputs: {}  outputs: {}\\ninstructions: {}  constraints: {}\"];",
+                c.num_inputs,
+                c.outputs.len(),
+                c.instructions.len(),
+                c.constraints.len()
+            )
+            .unwrap();
+        }
+        let edges = self.sub_circuit_graph_edges();
+        for id in ids.iter() {
+            let Some(subs) = edges.get(id) else {
+                continue;
+            };
+            let mut num_calls: HashMap<usize, usize> = HashMap::new();
+            for insn in self.circuits[id].instructions.iter() {
+                if let Some((sub_id, _, _)) = insn.as_sub_circuit_call() {
+                    *num_calls.entry(sub_id).or_default() += 1;
+                }
+            }
+            let mut subs: Vec<usize> = subs.iter().copied().collect();
+            subs.sort();
+            for sub_id in subs {
+                writeln!(
+                    res,
+                    "  c{id} -> c{sub_id} [label=\"x{}\"];",
+                    num_calls[&sub_id]
+                )
+                .unwrap();
+            }
+        }
+        writeln!(res, "}}").unwrap();
+        res
+    }
+}
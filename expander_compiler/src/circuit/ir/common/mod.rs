@@ -1,7 +1,8 @@
 use std::{
     collections::{HashMap, HashSet},
     fmt::Debug,
-    hash::Hash,
+    hash::{Hash, Hasher},
+    panic::Location,
 };
 
 use crate::{
@@ -10,11 +11,14 @@ use crate::{
     frontend::CircuitField,
     utils::{
         error::Error,
-        misc::{topo_order, topo_order_and_is_dag},
+        misc::{topo_levels, topo_order, topo_order_and_is_dag},
     },
 };
 
+pub mod const_fold;
+pub mod cse;
 pub mod display;
+pub mod dot;
 pub mod opt;
 pub mod serde;
 pub mod stats;
@@ -35,6 +39,10 @@ pub trait Instruction<C: Config>: Debug + Clone + Hash + PartialEq + Eq {
     fn inputs(&self) -> Vec<usize>;
     fn num_outputs(&self) -> usize;
     fn as_sub_circuit_call(&self) -> Option<(usize, &Vec<usize>, usize)>;
+    /// Returns the hint id if the instruction calls a hint.
+    fn as_hint(&self) -> Option<usize> {
+        None
+    }
     fn sub_circuit_call(sub_circuit_id: usize, inputs: Vec<usize>, num_outputs: usize) -> Self;
     fn replace_vars<F: Fn(usize) -> usize>(&self, f: F) -> Self;
     fn from_kx_plus_b(x: usize, k: CircuitField<C>, b: CircuitField<C>) -> Self;
@@ -55,6 +63,13 @@ pub trait Constraint<C: Config>: Debug + Clone + Hash + PartialEq + Eq {
     fn typ(&self) -> Self::Type;
     fn replace_var<F: Fn(usize) -> usize>(&self, f: F) -> Self;
     fn new(var: usize, typ: Self::Type) -> Self;
+    /// Where the constraint was asserted in the frontend, if it's known.
+    fn origin(&self) -> Option<SourceLocation> {
+        None
+    }
+    fn with_origin(self, _origin: Option<SourceLocation>) -> Self {
+        self
+    }
 }
 
 pub trait ConstraintType<C: Config>: Debug + Copy + Clone + Hash + PartialEq + Eq {
@@ -78,6 +93,61 @@ impl<C: Config> Constraint<C> for RawConstraint {
     }
 }
 
+/// The frontend call site of an assertion.
+pub type SourceLocation = &'static Location<'static>;
+
+/// A zero constraint that remembers the frontend assertion it comes from, so that failed
+/// constraints can be reported at the user's code. The origin is ignored by comparison,
+/// hashing and serialization, which are the ones of the plain variable.
+#[derive(Debug, Clone, Copy)]
+pub struct TracedConstraint {
+    pub var: usize,
+    pub origin: Option<SourceLocation>,
+}
+
+impl From<usize> for TracedConstraint {
+    fn from(var: usize) -> Self {
+        TracedConstraint { var, origin: None }
+    }
+}
+
+impl PartialEq for TracedConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.var == other.var
+    }
+}
+
+impl Eq for TracedConstraint {}
+
+impl Hash for TracedConstraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.var.hash(state);
+    }
+}
+
+impl<C: Config> Constraint<C> for TracedConstraint {
+    type Type = RawConstraintType;
+    fn var(&self) -> usize {
+        self.var
+    }
+    fn typ(&self) -> Self::Type {}
+    fn replace_var<F: Fn(usize) -> usize>(&self, f: F) -> Self {
+        TracedConstraint {
+            var: f(self.var),
+            origin: self.origin,
+        }
+    }
+    fn new(var: usize, _: Self::Type) -> Self {
+        var.into()
+    }
+    fn origin(&self) -> Option<SourceLocation> {
+        self.origin
+    }
+    fn with_origin(self, origin: Option<SourceLocation>) -> Self {
+        TracedConstraint { origin, ..self }
+    }
+}
+
 impl<C: Config> ConstraintType<C> for RawConstraintType {
     fn verify(&self, x: &CircuitField<C>) -> bool {
         x.is_zero()
@@ -195,6 +265,10 @@ impl<Irc: IrConfig> RootCircuit<Irc> {
     }
 
     pub fn validate(&self) -> Result<(), Error> {
+        self.validate_inner().map_err(Error::ir_validation_failed)
+    }
+
+    fn validate_inner(&self) -> Result<(), Error> {
         // tests of this function are in for_layering
         // check if 0 circuit exists
         if !self.circuits.contains_key(&0) {
@@ -281,6 +355,14 @@ impl<Irc: IrConfig> RootCircuit<Irc> {
         )
     }
 
+    // circuits grouped so that each group only calls circuits in previous groups, in order to
+    // process the circuits of each group in parallel
+    pub fn topo_levels(&self) -> Vec<Vec<usize>> {
+        let mut order = self.topo_order();
+        order.reverse();
+        topo_levels(&order, &self.sub_circuit_graph_edges())
+    }
+
     // eval the circuit. This function should be used for testing only
     pub fn eval_unsafe_with_errors(
         &self,
@@ -5,7 +5,7 @@ use std::{
 
 use serdes::{ExpSerde, SerdeResult};
 
-use super::{Circuit, IrConfig, RootCircuit};
+use super::{Circuit, IrConfig, RootCircuit, TracedConstraint};
 use crate::circuit::config::Config;
 
 impl<Irc: IrConfig> ExpSerde for Circuit<Irc>
@@ -66,3 +66,12 @@ where
         })
     }
 }
+
+impl ExpSerde for TracedConstraint {
+    fn serialize_into<W: Write>(&self, writer: W) -> SerdeResult<()> {
+        self.var.serialize_into(writer)
+    }
+    fn deserialize_from<R: Read>(reader: R) -> SerdeResult<Self> {
+        Ok(usize::deserialize_from(reader)?.into())
+    }
+}
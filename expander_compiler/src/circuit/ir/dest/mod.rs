@@ -9,7 +9,7 @@ use crate::utils::error::Error;
 use super::common::EvalResult;
 use super::expr::{Term, VarSpec};
 use super::{
-    common::{self, Instruction as _, IrConfig, RawConstraint},
+    common::{self, const_fold::ConstFoldInstruction, Instruction as _, IrConfig, RawConstraint},
     expr::Expression,
 };
 
@@ -18,6 +18,8 @@ pub mod tests;
 
 pub mod display;
 pub mod mul_fanout_limit;
+pub mod profile;
+pub mod serde;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Instruction<C: Config> {
@@ -157,6 +159,26 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
     }
 }
 
+impl<C: Config> ConstFoldInstruction<C> for Instruction<C> {
+    fn constant_value(&self) -> Option<CircuitField<C>> {
+        match self {
+            Instruction::InternalVariable { expr } => expr.constant_value(),
+            Instruction::ConstantLike {
+                value: Coef::Constant(value),
+            } => Some(*value),
+            _ => None,
+        }
+    }
+    fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(&self, value_of: F) -> Option<Self> {
+        match self {
+            Instruction::InternalVariable { expr } => expr
+                .fold_constants(value_of)
+                .map(|expr| Instruction::InternalVariable { expr }),
+            _ => None,
+        }
+    }
+}
+
 pub type Circuit<C> = common::Circuit<Irc<C>>;
 pub type RootCircuit<C> = common::RootCircuit<Irc<C>>;
 pub type CircuitRelaxed<C> = common::Circuit<IrcRelaxed<C>>;
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use crate::circuit::{
+    config::Config,
+    costs::{cost_of_relay, CostModel},
+};
+
+use super::super::common::{IrConfig, RootCircuit};
+use super::super::expr::VarSpec;
+use super::Instruction;
+
+/// Gate counts of one call of a circuit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GateCounts {
+    pub num_mul: usize,
+    pub num_add: usize,
+    pub num_cst: usize,
+    pub num_custom: usize,
+    // gates copying a variable to the next layer, until it's used
+    pub num_relay: usize,
+}
+
+impl GateCounts {
+    // saturating, since shared sub circuits can be instantiated exponentially many times
+    fn add_scaled(&mut self, other: &GateCounts, k: usize) {
+        let add = |x: &mut usize, y: usize| *x = x.saturating_add(y.saturating_mul(k));
+        add(&mut self.num_mul, other.num_mul);
+        add(&mut self.num_add, other.num_add);
+        add(&mut self.num_cst, other.num_cst);
+        add(&mut self.num_custom, other.num_custom);
+        add(&mut self.num_relay, other.num_relay);
+    }
+
+    pub fn total(&self) -> usize {
+        [
+            self.num_mul,
+            self.num_add,
+            self.num_cst,
+            self.num_custom,
+            self.num_relay,
+        ]
+        .iter()
+        .fold(0, |acc, x| acc.saturating_add(*x))
+    }
+
+    /// Cost according to the same formula as `layered::stats::Stats::total_cost`. A relay is
+    /// charged like the compiler does when placing variables (`costs::cost_of_relay`): it's a
+    /// new variable on the next layer and the add gate copying into it.
+    pub fn cost(&self, cost_model: &CostModel) -> usize {
+        self.num_mul * cost_model.mul
+            + self.num_add * cost_model.add
+            + self.num_cst * cost_model.constant
+            + self.num_relay * cost_of_relay(cost_model, 0, 1)
+    }
+}
+
+/// Estimated gates of one circuit, shared by all its calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitProfile {
+    pub circuit_id: usize,
+    pub name: String,
+    // number of layers of one call
+    pub num_layers: usize,
+    // gates of one call, excluding those of sub circuits
+    pub self_gates: GateCounts,
+    // (sub circuit id, number of calls by one call of this circuit), in order of the first call
+    pub calls: Vec<(usize, usize)>,
+    // number of instances of this circuit in the whole circuit, 1 for the root
+    pub multiplicity: usize,
+}
+
+/// Estimated gate counts of each sub circuit, computed on the dest IR before it's split into
+/// single layers. Sub circuits are named by their id (the hash prefix from
+/// `hash_to_sub_circuit_id`), and the root circuit is named `define`.
+///
+/// Each circuit appears once with the number of times it's instantiated, so the profile stays
+/// linear in the size of the IR when sub circuits are shared by many callers.
+///
+/// The counts estimate the layered circuit: a relay is counted for each layer a variable is
+/// kept alive, which the layered circuit implements with add gates, and the layered
+/// optimizations may still merge or remove gates. So `expanded_gates` is close to the expanded
+/// gates of `layered::Stats`, but isn't an exact match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostProfile {
+    // in topological order, callers first, starting with the root
+    pub circuits: Vec<CircuitProfile>,
+}
+
+impl CostProfile {
+    pub fn root(&self) -> &CircuitProfile {
+        &self.circuits[0]
+    }
+
+    pub fn get(&self, circuit_id: usize) -> Option<&CircuitProfile> {
+        self.circuits.iter().find(|c| c.circuit_id == circuit_id)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&CircuitProfile> {
+        self.circuits.iter().find(|c| c.name == name)
+    }
+
+    /// Replaces the names of the sub circuits found in `names`.
+    pub fn set_names(&mut self, names: &HashMap<usize, String>) {
+        for c in self.circuits.iter_mut() {
+            if let Some(name) = names.get(&c.circuit_id) {
+                c.name = name.clone();
+            }
+        }
+    }
+
+    /// Gates of one call of the circuit, including those of sub circuits.
+    pub fn total_gates(&self, circuit_id: usize) -> GateCounts {
+        let mut totals: HashMap<usize, GateCounts> = HashMap::new();
+        for c in self.circuits.iter().rev() {
+            let mut res = c.self_gates;
+            for (sub_id, n) in c.calls.iter() {
+                res.add_scaled(&totals[sub_id], *n);
+            }
+            totals.insert(c.circuit_id, res);
+        }
+        totals[&circuit_id]
+    }
+
+    /// Gates of the whole circuit, i.e. the gates of each circuit times its multiplicity.
+    pub fn expanded_gates(&self) -> GateCounts {
+        let mut res = GateCounts::default();
+        for c in self.circuits.iter() {
+            res.add_scaled(&c.self_gates, c.multiplicity);
+        }
+        res
+    }
+
+    /// Folded stacks, one `define;caller;callee gates` line per call path of each circuit,
+    /// weighted by the expanded gates (relays included) of the instances on that path. This is
+    /// the input format of flame graph tools. Unlike `circuits`, the number of lines is the
+    /// number of distinct call paths, which can be much larger when sub circuits are shared.
+    pub fn to_folded_stacks(&self) -> String {
+        let names: HashMap<usize, String> = self
+            .circuits
+            .iter()
+            .map(|c| (c.circuit_id, c.name.replace(';', ":")))
+            .collect();
+        // call paths of each circuit with their number of instances, in order of discovery
+        let mut paths: HashMap<usize, Vec<(String, usize)>> = HashMap::new();
+        paths.insert(
+            self.root().circuit_id,
+            vec![(names[&self.root().circuit_id].clone(), 1)],
+        );
+        let mut res = String::new();
+        for c in self.circuits.iter() {
+            let c_paths = paths.remove(&c.circuit_id).unwrap_or_default();
+            for (path, multiplicity) in c_paths.iter() {
+                let value = c.self_gates.total().saturating_mul(*multiplicity);
+                if value > 0 {
+                    res.push_str(&format!("{path} {value}\n"));
+                }
+                for (sub_id, n) in c.calls.iter() {
+                    let sub_path = format!("{path};{}", names[sub_id]);
+                    let sub_multiplicity = multiplicity.saturating_mul(*n);
+                    let sub_paths = paths.entry(*sub_id).or_default();
+                    // distinct circuits may share a name
+                    match sub_paths.iter_mut().find(|(p, _)| *p == sub_path) {
+                        Some((_, m)) => *m = m.saturating_add(sub_multiplicity),
+                        None => sub_paths.push((sub_path, sub_multiplicity)),
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl<C: Config, Irc: IrConfig<Instruction = Instruction<C>, Config = C>> RootCircuit<Irc> {
+    /// Computes the cost profile. Without `cross_layer_relay`, a variable used several layers
+    /// after being defined needs a relay gate on each layer in between.
+    pub fn get_profile(&self, cross_layer_relay: bool) -> CostProfile {
+        let order = self.topo_order();
+        let mut m: HashMap<usize, CircuitProfile> = HashMap::new();
+        for id in order.iter().rev() {
+            let p = self.circuit_profile(*id, &m, cross_layer_relay);
+            m.insert(*id, p);
+        }
+        // callers come first, so the multiplicity of a circuit is final when it's reached
+        let mut circuits: Vec<CircuitProfile> = Vec::with_capacity(order.len());
+        for id in order.iter() {
+            let mut p = m.remove(id).unwrap();
+            if *id == 0 {
+                p.name = "define".to_string();
+                p.multiplicity = 1;
+            }
+            for (sub_id, n) in p.calls.iter() {
+                let sub = m.get_mut(sub_id).unwrap();
+                sub.multiplicity = sub
+                    .multiplicity
+                    .saturating_add(p.multiplicity.saturating_mul(*n));
+            }
+            circuits.push(p);
+        }
+        CostProfile { circuits }
+    }
+
+    fn circuit_profile(
+        &self,
+        id: usize,
+        m: &HashMap<usize, CircuitProfile>,
+        cross_layer_relay: bool,
+    ) -> CircuitProfile {
+        let circuit = &self.circuits[&id];
+        let mut gates = GateCounts::default();
+        let mut calls: Vec<(usize, usize)> = Vec::new();
+        // layer where each variable is computed, and the last layer where it's needed
+        let mut layer = vec![0; circuit.num_inputs + 1];
+        let mut needed = vec![0; circuit.num_inputs + 1];
+        let mut is_constant = vec![false; circuit.num_inputs + 1];
+        for insn in circuit.instructions.iter() {
+            match insn {
+                Instruction::InternalVariable { expr } => {
+                    let vars = expr.get_vars();
+                    let l = vars.iter().map(|v| layer[*v]).max().unwrap_or(0) + 1;
+                    for v in vars {
+                        needed[v] = needed[v].max(l - 1);
+                    }
+                    for term in expr.iter() {
+                        match term.vars {
+                            VarSpec::Const => gates.num_cst += 1,
+                            VarSpec::Linear(_) | VarSpec::RandomLinear(_) => gates.num_add += 1,
+                            VarSpec::Quad(_, _) => gates.num_mul += 1,
+                            VarSpec::Custom { .. } => gates.num_custom += 1,
+                        }
+                    }
+                    layer.push(l);
+                    needed.push(l);
+                    is_constant.push(false);
+                }
+                Instruction::SubCircuitCall {
+                    sub_circuit_id,
+                    inputs,
+                    num_outputs,
+                } => {
+                    let start = inputs.iter().map(|v| layer[*v]).max().unwrap_or(0);
+                    for v in inputs.iter() {
+                        needed[*v] = needed[*v].max(start);
+                    }
+                    let l = start + m[sub_circuit_id].num_layers;
+                    for _ in 0..*num_outputs {
+                        layer.push(l);
+                        needed.push(l);
+                        is_constant.push(false);
+                    }
+                    match calls.iter_mut().find(|(x, _)| x == sub_circuit_id) {
+                        Some((_, n)) => *n += 1,
+                        None => calls.push((*sub_circuit_id, 1)),
+                    }
+                }
+                Instruction::ConstantLike { .. } => {
+                    layer.push(0);
+                    needed.push(0);
+                    is_constant.push(true);
+                }
+            }
+        }
+        let num_layers = circuit
+            .outputs
+            .iter()
+            .chain(circuit.constraints.iter())
+            .map(|v| layer[*v])
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for v in circuit.outputs.iter() {
+            needed[*v] = num_layers;
+        }
+        if !cross_layer_relay {
+            for v in 1..layer.len() {
+                if !is_constant[v] && needed[v] > layer[v] {
+                    gates.num_relay += needed[v] - layer[v];
+                }
+            }
+        }
+        CircuitProfile {
+            circuit_id: id,
+            name: format!("{id:016x}"),
+            num_layers,
+            self_gates: gates,
+            calls,
+            multiplicity: 0,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::io::{Error as IoError, Read, Write};
+
+use serdes::{ExpSerde, SerdeResult};
+
+use crate::circuit::{config::Config, ir::expr::Expression, layered::Coef};
+
+use super::Instruction;
+
+impl<C: Config> ExpSerde for Instruction<C> {
+    fn serialize_into<W: Write>(&self, mut writer: W) -> SerdeResult<()> {
+        match self {
+            Instruction::InternalVariable { expr } => {
+                1u8.serialize_into(&mut writer)?;
+                expr.serialize_into(&mut writer)?;
+            }
+            Instruction::SubCircuitCall {
+                sub_circuit_id,
+                inputs,
+                num_outputs,
+            } => {
+                2u8.serialize_into(&mut writer)?;
+                sub_circuit_id.serialize_into(&mut writer)?;
+                inputs.serialize_into(&mut writer)?;
+                num_outputs.serialize_into(&mut writer)?;
+            }
+            Instruction::ConstantLike { value } => {
+                3u8.serialize_into(&mut writer)?;
+                value.serialize_into(&mut writer)?;
+            }
+        };
+        Ok(())
+    }
+    fn deserialize_from<R: Read>(mut reader: R) -> SerdeResult<Self> {
+        let instruction_type = u8::deserialize_from(&mut reader)?;
+        Ok(match instruction_type {
+            1 => Instruction::InternalVariable {
+                expr: Expression::deserialize_from(&mut reader)?,
+            },
+            2 => Instruction::SubCircuitCall {
+                sub_circuit_id: usize::deserialize_from(&mut reader)?,
+                inputs: Vec::<usize>::deserialize_from(&mut reader)?,
+                num_outputs: usize::deserialize_from(&mut reader)?,
+            },
+            3 => Instruction::ConstantLike {
+                value: Coef::<C>::deserialize_from(&mut reader)?,
+            },
+            _ => {
+                return Err(IoError::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid InstructionType",
+                ))?
+            }
+        })
+    }
+}
@@ -504,3 +504,62 @@ fn adjust_for_layering_and_reassign_duplicate_sub_circuit_outputs() {
         assert_eq!(cond1, cond4);
     }
 }
+
+#[test]
+fn profile_shared_sub_circuits() {
+    // each circuit calls the next one twice, so the call tree has 2^70 leaves
+    let depth = 70;
+    let mut root = RootCircuit::<C>::default();
+    for i in 0..depth {
+        let call = SubCircuitCall {
+            sub_circuit_id: i + 1,
+            inputs: vec![1],
+            num_outputs: 1,
+        };
+        root.circuits.insert(
+            i,
+            Circuit {
+                instructions: vec![
+                    call.clone(),
+                    call,
+                    InternalVariable {
+                        expr: Expression::from_terms(vec![
+                            Term::new_linear(CField::one(), 2),
+                            Term::new_linear(CField::one(), 3),
+                        ]),
+                    },
+                ],
+                constraints: vec![],
+                outputs: vec![4],
+                num_inputs: 1,
+            },
+        );
+    }
+    root.circuits.insert(
+        depth,
+        Circuit {
+            instructions: vec![InternalVariable {
+                expr: Expression::from_terms(vec![Term::new_quad(CField::one(), 1, 1)]),
+            }],
+            constraints: vec![],
+            outputs: vec![2],
+            num_inputs: 1,
+        },
+    );
+    assert_eq!(root.validate(), Ok(()));
+    let profile = root.get_profile(true);
+    assert_eq!(profile.circuits.len(), depth + 1);
+    assert_eq!(profile.root().name, "define");
+    for i in 0..depth {
+        let p = profile.get(i).unwrap();
+        assert_eq!(p.calls, vec![(i + 1, 2)]);
+        assert_eq!(p.self_gates.num_add, 2);
+        if i < 60 {
+            assert_eq!(p.multiplicity, 1 << i);
+        }
+    }
+    assert_eq!(profile.get(depth).unwrap().multiplicity, usize::MAX);
+    assert_eq!(profile.expanded_gates().num_mul, usize::MAX);
+    assert_eq!(profile.total_gates(60).num_mul, 1 << (depth - 60));
+    assert_eq!(profile.to_folded_stacks().lines().count(), depth + 1);
+}
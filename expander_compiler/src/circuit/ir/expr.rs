@@ -363,6 +363,38 @@ impl<C: Config> Expression<C> {
             None
         }
     }
+    /// Substitutes the variables with known values. Returns None if nothing changes.
+    pub fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(
+        &self,
+        value_of: F,
+    ) -> Option<Self> {
+        let mut changed = false;
+        let mut terms = Vec::with_capacity(self.terms.len());
+        for term in self.iter() {
+            let folded = match &term.vars {
+                VarSpec::Linear(x) => value_of(*x).map(|v| Term::new_const(term.coef * v)),
+                VarSpec::Quad(x, y) => match (value_of(*x), value_of(*y)) {
+                    (Some(vx), Some(vy)) => Some(Term::new_const(term.coef * vx * vy)),
+                    (Some(vx), None) => Some(Term::new_linear(term.coef * vx, *y)),
+                    (None, Some(vy)) => Some(Term::new_linear(term.coef * vy, *x)),
+                    (None, None) => None,
+                },
+                _ => None,
+            };
+            match folded {
+                Some(folded) => {
+                    changed = true;
+                    terms.push(folded);
+                }
+                None => terms.push(term.clone()),
+            }
+        }
+        if changed {
+            Some(Expression::from_terms(terms))
+        } else {
+            None
+        }
+    }
     pub fn mul_constant(&self, value: CircuitField<C>) -> Self {
         if value.is_zero() {
             return Expression::default();
@@ -432,6 +464,39 @@ impl<C: Config> LinComb<C> {
             }
         }
     }
+    pub fn constant_value(&self) -> Option<CircuitField<C>> {
+        if self.terms.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+    /// Substitutes the variables with known values, and removes the terms with zero coefficients.
+    /// Returns None if nothing changes.
+    pub fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(
+        &self,
+        value_of: F,
+    ) -> Option<Self> {
+        if self
+            .terms
+            .iter()
+            .all(|term| !term.coef.is_zero() && value_of(term.var).is_none())
+        {
+            return None;
+        }
+        let mut res = LinComb {
+            terms: Vec::with_capacity(self.terms.len()),
+            constant: self.constant,
+        };
+        for term in self.terms.iter() {
+            match value_of(term.var) {
+                Some(value) => res.constant += value * term.coef,
+                None if !term.coef.is_zero() => res.terms.push(term.clone()),
+                None => {}
+            }
+        }
+        Some(res)
+    }
     pub fn eval(&self, values: &[CircuitField<C>]) -> CircuitField<C> {
         let mut res = self.constant;
         for term in self.terms.iter() {
@@ -497,3 +562,85 @@ impl<C: Config> ExpSerde for LinComb<C> {
         Ok(LinComb { terms, constant })
     }
 }
+
+impl ExpSerde for VarSpec {
+    fn serialize_into<W: Write>(&self, mut writer: W) -> SerdeResult<()> {
+        match self {
+            VarSpec::Const => {
+                1u8.serialize_into(&mut writer)?;
+            }
+            VarSpec::Linear(x) => {
+                2u8.serialize_into(&mut writer)?;
+                x.serialize_into(&mut writer)?;
+            }
+            VarSpec::Quad(x, y) => {
+                3u8.serialize_into(&mut writer)?;
+                x.serialize_into(&mut writer)?;
+                y.serialize_into(&mut writer)?;
+            }
+            VarSpec::Custom { gate_type, inputs } => {
+                4u8.serialize_into(&mut writer)?;
+                gate_type.serialize_into(&mut writer)?;
+                inputs.serialize_into(&mut writer)?;
+            }
+            VarSpec::RandomLinear(x) => {
+                5u8.serialize_into(&mut writer)?;
+                x.serialize_into(&mut writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize_from<R: Read>(mut reader: R) -> SerdeResult<Self> {
+        Ok(match u8::deserialize_from(&mut reader)? {
+            1 => VarSpec::Const,
+            2 => VarSpec::Linear(usize::deserialize_from(&mut reader)?),
+            3 => VarSpec::Quad(
+                usize::deserialize_from(&mut reader)?,
+                usize::deserialize_from(&mut reader)?,
+            ),
+            4 => VarSpec::Custom {
+                gate_type: usize::deserialize_from(&mut reader)?,
+                inputs: Vec::<usize>::deserialize_from(&mut reader)?,
+            },
+            5 => VarSpec::RandomLinear(usize::deserialize_from(&mut reader)?),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid VarSpec",
+                ))?
+            }
+        })
+    }
+}
+
+impl<C: Config> ExpSerde for Expression<C> {
+    fn serialize_into<W: Write>(&self, mut writer: W) -> SerdeResult<()> {
+        self.terms.len().serialize_into(&mut writer)?;
+        for term in self.terms.iter() {
+            term.coef.serialize_into(&mut writer)?;
+            term.vars.serialize_into(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_from<R: Read>(mut reader: R) -> SerdeResult<Self> {
+        let len = usize::deserialize_from(&mut reader)?;
+        let mut terms = Vec::with_capacity(len);
+        for _ in 0..len {
+            let coef = CircuitField::<C>::deserialize_from(&mut reader)?;
+            let vars = VarSpec::deserialize_from(&mut reader)?;
+            terms.push(Term { coef, vars });
+        }
+        if terms.is_empty()
+            || !terms.iter().all(|t| t.is_normalized())
+            || !terms.windows(2).all(|w| w[0].vars < w[1].vars)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid Expression",
+            ))?;
+        }
+        Ok(Expression { terms })
+    }
+}
@@ -5,7 +5,11 @@ use crate::hints;
 use crate::utils::error::Error;
 
 use super::{
-    common::{self, EvalResult, IrConfig, RawConstraint},
+    common::{
+        self,
+        const_fold::{fold_mul, ConstFoldInstruction, FoldedMul},
+        EvalResult, IrConfig, TracedConstraint,
+    },
     expr,
 };
 
@@ -13,6 +17,7 @@ use super::{
 mod tests;
 
 pub mod display;
+pub mod serde;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Instruction<C: Config> {
@@ -43,7 +48,7 @@ pub struct Irc<C: Config> {
 }
 impl<C: Config> IrConfig for Irc<C> {
     type Instruction = Instruction<C>;
-    type Constraint = RawConstraint;
+    type Constraint = TracedConstraint;
     type Config = C;
     const ALLOW_DUPLICATE_SUB_CIRCUIT_INPUTS: bool = true;
     const ALLOW_DUPLICATE_CONSTRAINTS: bool = true;
@@ -149,5 +154,25 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
     }
 }
 
+impl<C: Config> ConstFoldInstruction<C> for Instruction<C> {
+    fn constant_value(&self) -> Option<CircuitField<C>> {
+        match self {
+            Instruction::LinComb(lc) => lc.constant_value(),
+            Instruction::ConstantLike(Coef::Constant(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(&self, value_of: F) -> Option<Self> {
+        match self {
+            Instruction::LinComb(lc) => lc.fold_constants(value_of).map(Instruction::LinComb),
+            Instruction::Mul(inputs) => fold_mul(inputs, value_of).map(|res| match res {
+                FoldedMul::LinComb(lc) => Instruction::LinComb(lc),
+                FoldedMul::Mul(inputs) => Instruction::Mul(inputs),
+            }),
+            _ => None,
+        }
+    }
+}
+
 pub type Circuit<C> = common::Circuit<Irc<C>>;
 pub type RootCircuit<C> = common::RootCircuit<Irc<C>>;
@@ -0,0 +1,46 @@
+use std::fmt;
+
+use super::{Config, Instruction};
+
+fn write_vars(f: &mut fmt::Formatter, vars: &[usize], sep: &str) -> fmt::Result {
+    for (i, var) in vars.iter().enumerate() {
+        write!(f, "v{var}")?;
+        if i < vars.len() - 1 {
+            write!(f, "{sep}")?;
+        }
+    }
+    Ok(())
+}
+
+impl<C: Config> fmt::Display for Instruction<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::LinComb(lc) => write!(f, "{lc}"),
+            Instruction::Mul(inputs) => write_vars(f, inputs, "*"),
+            Instruction::Hint {
+                hint_id, inputs, ..
+            } => {
+                write!(f, "hint{hint_id}(")?;
+                write_vars(f, inputs, ",")?;
+                write!(f, ")")
+            }
+            Instruction::ConstantLike(coef) => write!(f, "{coef}"),
+            Instruction::SubCircuitCall {
+                sub_circuit_id,
+                inputs,
+                ..
+            } => {
+                write!(f, "sub{sub_circuit_id}(")?;
+                write_vars(f, inputs, ",")?;
+                write!(f, ")")
+            }
+            Instruction::CustomGate {
+                gate_type, inputs, ..
+            } => {
+                write!(f, "custom{gate_type}(")?;
+                write_vars(f, inputs, ",")?;
+                write!(f, ")")
+            }
+        }
+    }
+}
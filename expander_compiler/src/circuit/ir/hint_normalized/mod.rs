@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use crate::field::FieldArith;
 use crate::frontend::CircuitField;
-use crate::hints::registry::HintCaller;
+use crate::hints::{info::HintInfos, registry::HintCaller, tables::LookupTables};
 use crate::utils::error::Error;
 use crate::{
     circuit::{
@@ -14,13 +14,19 @@ use crate::{
 };
 
 use super::{
-    common::{self, EvalResult, Instruction as _, IrConfig, RawConstraint},
+    common::{
+        self,
+        const_fold::{fold_mul, ConstFoldInstruction, FoldedMul},
+        cse::CseInstruction,
+        EvalResult, Instruction as _, IrConfig, TracedConstraint,
+    },
     expr,
 };
 
 #[cfg(test)]
 mod tests;
 
+pub mod display;
 pub mod serde;
 pub mod witness_solver;
 
@@ -51,7 +57,7 @@ pub struct Irc<C: Config> {
 }
 impl<C: Config> IrConfig for Irc<C> {
     type Instruction = Instruction<C>;
-    type Constraint = RawConstraint;
+    type Constraint = TracedConstraint;
     type Config = C;
     const ALLOW_DUPLICATE_SUB_CIRCUIT_INPUTS: bool = true;
     const ALLOW_DUPLICATE_CONSTRAINTS: bool = true;
@@ -89,6 +95,12 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
             _ => None,
         }
     }
+    fn as_hint(&self) -> Option<usize> {
+        match self {
+            Instruction::Hint { hint_id, .. } => Some(*hint_id),
+            _ => None,
+        }
+    }
     fn sub_circuit_call(sub_circuit_id: usize, inputs: Vec<usize>, num_outputs: usize) -> Self {
         Instruction::SubCircuitCall {
             sub_circuit_id,
@@ -203,12 +215,55 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
     }
 }
 
+impl<C: Config> CseInstruction<C> for Instruction<C> {
+    fn cse_key(&self) -> Option<Self> {
+        match self {
+            Instruction::LinComb(lc) => {
+                let mut lc = lc.clone();
+                lc.terms.sort_by_key(|term| term.var);
+                Some(Instruction::LinComb(lc))
+            }
+            Instruction::Mul(inputs) => {
+                let mut inputs = inputs.clone();
+                inputs.sort();
+                Some(Instruction::Mul(inputs))
+            }
+            // hints may depend on more than their inputs
+            Instruction::Hint { .. } => None,
+            Instruction::ConstantLike(Coef::Random) => None,
+            Instruction::SubCircuitCall { .. } => None,
+            Instruction::ConstantLike(_) | Instruction::CustomGate { .. } => Some(self.clone()),
+        }
+    }
+}
+
+impl<C: Config> ConstFoldInstruction<C> for Instruction<C> {
+    fn constant_value(&self) -> Option<CircuitField<C>> {
+        match self {
+            Instruction::LinComb(lc) => lc.constant_value(),
+            Instruction::ConstantLike(Coef::Constant(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(&self, value_of: F) -> Option<Self> {
+        match self {
+            Instruction::LinComb(lc) => lc.fold_constants(value_of).map(Instruction::LinComb),
+            Instruction::Mul(inputs) => fold_mul(inputs, value_of).map(|res| match res {
+                FoldedMul::LinComb(lc) => Instruction::LinComb(lc),
+                FoldedMul::Mul(inputs) => Instruction::Mul(inputs),
+            }),
+            _ => None,
+        }
+    }
+}
+
 impl<C: Config> Instruction<C> {
     fn eval_safe(
         &self,
         values: &[CircuitField<C>],
         public_inputs: &[CircuitField<C>],
         hint_caller: &impl HintCaller<CircuitField<C>>,
+        tables: &mut LookupTables<CircuitField<C>>,
     ) -> EvalResult<C> {
         if let Instruction::ConstantLike(coef) = self {
             return match coef {
@@ -226,7 +281,13 @@ impl<C: Config> Instruction<C> {
         } = self
         {
             let inputs: Vec<CircuitField<C>> = inputs.iter().map(|i| values[*i]).collect();
-            return match hints::safe_impl(hint_caller, *hint_id, &inputs, *num_outputs) {
+            return match hints::safe_impl_with_tables(
+                hint_caller,
+                tables,
+                *hint_id,
+                &inputs,
+                *num_outputs,
+            ) {
                 Ok(outputs) => EvalResult::Values(outputs),
                 Err(e) => EvalResult::Error(e),
             };
@@ -338,7 +399,14 @@ impl<C: Config> Circuit<C> {
         super::hint_less::Circuit {
             num_inputs: self.num_inputs + sub_hint_sizes[&self_id].0,
             instructions,
-            constraints: self.constraints.iter().map(|x| new_id[*x]).collect(),
+            constraints: self
+                .constraints
+                .iter()
+                .map(|x| TracedConstraint {
+                    var: new_id[x.var],
+                    ..*x
+                })
+                .collect(),
             outputs: self.outputs.iter().map(|x| new_id[*x]).collect(),
         }
     }
@@ -412,7 +480,7 @@ impl<C: Config> Circuit<C> {
         Circuit {
             num_inputs: self.num_inputs,
             instructions,
-            constraints: if is_root { vec![1] } else { vec![] },
+            constraints: if is_root { vec![1.into()] } else { vec![] },
             outputs,
         }
     }
@@ -436,7 +504,14 @@ impl<C: Config> Circuit<C> {
         Circuit {
             num_inputs: im.cur_size(),
             instructions,
-            constraints: self.constraints.iter().map(|x| new_id[*x]).collect(),
+            constraints: self
+                .constraints
+                .iter()
+                .map(|x| TracedConstraint {
+                    var: new_id[x.var],
+                    ..*x
+                })
+                .collect(),
             outputs: self.outputs.iter().map(|x| new_id[*x]).collect(),
         }
     }
@@ -487,22 +562,45 @@ impl<C: Config> RootCircuit<C> {
         inputs: Vec<CircuitField<C>>,
         public_inputs: &[CircuitField<C>],
         hint_caller: &impl HintCaller<CircuitField<C>>,
+    ) -> Result<Vec<CircuitField<C>>, Error> {
+        self.eval_safe_with_hint_infos(inputs, public_inputs, hint_caller, &HintInfos::new())
+    }
+
+    /// Same as `eval_safe`, but hint failures are reported with the hint key and call sites from `hint_infos`.
+    pub fn eval_safe_with_hint_infos(
+        &self,
+        inputs: Vec<CircuitField<C>>,
+        public_inputs: &[CircuitField<C>],
+        hint_caller: &impl HintCaller<CircuitField<C>>,
+        hint_infos: &HintInfos,
     ) -> Result<Vec<CircuitField<C>>, Error> {
         assert_eq!(inputs.len(), self.input_size());
-        self.eval_sub_safe(&self.circuits[&0], inputs, public_inputs, hint_caller)
+        self.eval_sub_safe(
+            &self.circuits[&0],
+            inputs,
+            public_inputs,
+            hint_caller,
+            hint_infos,
+            &mut LookupTables::new(),
+            &mut vec![0],
+        )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn eval_sub_safe(
         &self,
         circuit: &Circuit<C>,
         inputs: Vec<CircuitField<C>>,
         public_inputs: &[CircuitField<C>],
         hint_caller: &impl HintCaller<CircuitField<C>>,
+        hint_infos: &HintInfos,
+        tables: &mut LookupTables<CircuitField<C>>,
+        path: &mut Vec<usize>,
     ) -> Result<Vec<CircuitField<C>>, Error> {
         let mut values = vec![CircuitField::<C>::zero(); 1];
         values.extend(inputs);
-        for insn in circuit.instructions.iter() {
-            match insn.eval_safe(&values, public_inputs, hint_caller) {
+        for (index, insn) in circuit.instructions.iter().enumerate() {
+            match insn.eval_safe(&values, public_inputs, hint_caller, tables) {
                 EvalResult::Value(v) => {
                     values.push(v);
                 }
@@ -510,15 +608,23 @@ impl<C: Config> RootCircuit<C> {
                     values.append(&mut vs);
                 }
                 EvalResult::SubCircuitCall(sub_circuit_id, inputs) => {
+                    path.push(sub_circuit_id);
                     let res = self.eval_sub_safe(
                         &self.circuits[&sub_circuit_id],
                         inputs.iter().map(|&i| values[i]).collect(),
                         public_inputs,
                         hint_caller,
+                        hint_infos,
+                        tables,
+                        path,
                     )?;
+                    path.pop();
                     values.extend(res);
                 }
                 EvalResult::Error(e) => {
+                    if let Instruction::Hint { hint_id, .. } = insn {
+                        return Err(hint_infos.hint_error(*hint_id, path, index, e));
+                    }
                     return Err(e);
                 }
             }
@@ -535,30 +641,49 @@ impl<C: Config> RootCircuit<C> {
         inputs: Vec<SF>,
         public_inputs: &[SF],
         hint_caller: &impl HintCaller<CircuitField<C>>,
+    ) -> Result<Vec<SF>, Error> {
+        self.eval_safe_simd_with_hint_infos(inputs, public_inputs, hint_caller, &HintInfos::new())
+    }
+
+    pub fn eval_safe_simd_with_hint_infos<SF: arith::SimdField<Scalar = CircuitField<C>>>(
+        &self,
+        inputs: Vec<SF>,
+        public_inputs: &[SF],
+        hint_caller: &impl HintCaller<CircuitField<C>>,
+        hint_infos: &HintInfos,
     ) -> Result<Vec<SF>, Error> {
         assert_eq!(inputs.len(), self.input_size());
         let mut result_values = Vec::new();
+        // each lane is a separate witness, with its own tables
+        let mut tables: Vec<_> = (0..SF::PACK_SIZE).map(|_| LookupTables::new()).collect();
         self.eval_sub_safe_simd(
             &self.circuits[&0],
             inputs,
             public_inputs,
             hint_caller,
+            hint_infos,
+            &mut tables,
+            &mut vec![0],
             &mut result_values,
         )?;
         Ok(result_values)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn eval_sub_safe_simd<SF: arith::SimdField<Scalar = CircuitField<C>>>(
         &self,
         circuit: &Circuit<C>,
         inputs: Vec<SF>,
         public_inputs: &[SF],
         hint_caller: &impl HintCaller<CircuitField<C>>,
+        hint_infos: &HintInfos,
+        tables: &mut [LookupTables<CircuitField<C>>],
+        path: &mut Vec<usize>,
         result_values: &mut Vec<SF>,
     ) -> Result<(), Error> {
         let mut values = vec![SF::zero(); 1];
         values.extend(inputs);
-        for insn in circuit.instructions.iter() {
+        for (index, insn) in circuit.instructions.iter().enumerate() {
             match insn {
                 Instruction::LinComb(lc) => {
                     let res = lc.eval_simd(&values);
@@ -585,9 +710,17 @@ impl<C: Config> RootCircuit<C> {
                     }
                     let mut outputs_tmp =
                         vec![CircuitField::<C>::zero(); num_outputs * SF::PACK_SIZE];
-                    for (i, inputs) in inputs_scalar.iter().enumerate() {
-                        let outputs =
-                            hints::safe_impl(hint_caller, *hint_id, inputs, *num_outputs)?;
+                    for (i, (inputs, tables)) in
+                        inputs_scalar.iter().zip(tables.iter_mut()).enumerate()
+                    {
+                        let outputs = hints::safe_impl_with_tables(
+                            hint_caller,
+                            tables,
+                            *hint_id,
+                            inputs,
+                            *num_outputs,
+                        )
+                        .map_err(|e| hint_infos.hint_error(*hint_id, path, index, e))?;
                         for (j, x) in outputs.iter().enumerate() {
                             outputs_tmp[j * SF::PACK_SIZE + i] = *x;
                         }
@@ -615,13 +748,18 @@ impl<C: Config> RootCircuit<C> {
                     inputs,
                     ..
                 } => {
+                    path.push(*sub_circuit_id);
                     self.eval_sub_safe_simd(
                         &self.circuits[sub_circuit_id],
                         inputs.iter().map(|&i| values[i]).collect(),
                         public_inputs,
                         hint_caller,
+                        hint_infos,
+                        tables,
+                        path,
                         &mut values,
                     )?;
+                    path.pop();
                 }
                 Instruction::CustomGate { .. } => {
                     panic!("CustomGate currently unsupported");
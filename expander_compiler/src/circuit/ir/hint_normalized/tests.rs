@@ -48,7 +48,7 @@ fn remove_hints_simple() {
                 },
                 super::Instruction::Mul(vec![10, 9, 8, 7, 6, 5, 4, 3, 2, 1]),
             ],
-            constraints: vec![1],
+            constraints: vec![1.into()],
             outputs: vec![1],
             num_inputs: 2,
         },
@@ -82,7 +82,7 @@ fn export_hints_simple() {
                     num_outputs: 2,
                 },
             ],
-            constraints: vec![1],
+            constraints: vec![1.into()],
             outputs: vec![1],
             num_inputs: 2,
         },
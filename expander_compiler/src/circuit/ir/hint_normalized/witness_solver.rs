@@ -1,16 +1,35 @@
-use crate::circuit::{
-    config::{CircuitField, SIMDField},
-    layered::witness::{Witness, WitnessValues},
+use std::io::{Read, Write};
+
+use crate::{
+    circuit::{
+        config::{CircuitField, SIMDField},
+        layered::witness::{Witness, WitnessValues},
+    },
+    hints::info::HintInfos,
 };
 
 use arith::SimdField;
-use serdes::ExpSerde;
+use serdes::{ExpSerde, SerdeResult};
 
 use super::{Config, Error, FieldArith, HintCaller, RootCircuit};
 
-#[derive(ExpSerde)]
 pub struct WitnessSolver<C: Config> {
     pub circuit: RootCircuit<C>,
+    // debug information only, it isn't serialized to keep the format of existing witness solvers
+    pub hint_infos: HintInfos,
+}
+
+impl<C: Config> ExpSerde for WitnessSolver<C> {
+    fn serialize_into<W: Write>(&self, writer: W) -> SerdeResult<()> {
+        self.circuit.serialize_into(writer)
+    }
+
+    fn deserialize_from<R: Read>(reader: R) -> SerdeResult<Self> {
+        Ok(WitnessSolver {
+            circuit: RootCircuit::deserialize_from(reader)?,
+            hint_infos: HintInfos::new(),
+        })
+    }
 }
 
 impl<C: Config> WitnessSolver<C> {
@@ -22,7 +41,12 @@ impl<C: Config> WitnessSolver<C> {
     ) -> Result<(Vec<CircuitField<C>>, usize), Error> {
         assert_eq!(vars.len(), self.circuit.input_size());
         assert_eq!(public_vars.len(), self.circuit.num_public_inputs);
-        let mut a = self.circuit.eval_safe(vars, &public_vars, hint_caller)?;
+        let mut a = self.circuit.eval_safe_with_hint_infos(
+            vars,
+            &public_vars,
+            hint_caller,
+            &self.hint_infos,
+        )?;
         let res_len = a.len();
         a.extend(public_vars);
         Ok((a, res_len))
@@ -90,9 +114,12 @@ impl<C: Config> WitnessSolver<C> {
                 }
                 simd_public_inputs.push(SIMDField::<C>::pack(&tmp));
             }
-            let simd_result =
-                self.circuit
-                    .eval_safe_simd(simd_inputs, &simd_public_inputs, hint_caller)?;
+            let simd_result = self.circuit.eval_safe_simd_with_hint_infos(
+                simd_inputs,
+                &simd_public_inputs,
+                hint_caller,
+                &self.hint_infos,
+            )?;
             num_inputs_per_witness = simd_result.len();
             values.extend(simd_result);
             values.extend(simd_public_inputs);
@@ -1,3 +1,5 @@
+use std::hash::{Hash, Hasher};
+
 use ethnum::U256;
 
 use crate::{
@@ -9,7 +11,12 @@ use crate::{
 };
 
 use super::{
-    common::{self, EvalResult, IrConfig},
+    common::{
+        self,
+        const_fold::{fold_mul, ConstFoldInstruction, FoldedMul},
+        cse::CseInstruction,
+        EvalResult, IrConfig, SourceLocation,
+    },
     expr,
 };
 
@@ -18,6 +25,11 @@ mod tests;
 
 pub mod chains;
 pub mod serde;
+pub mod specialize;
+pub mod text;
+
+/// The largest number of selectors of a `Mux` instruction.
+pub const MAX_MUX_SELECTORS: usize = 16;
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Instruction<C: Config> {
@@ -64,6 +76,12 @@ pub enum Instruction<C: Config> {
         x: usize,
         num_bits: usize,
     },
+    /// Returns `values[i]`, where `i` has the little-endian bits `selectors`.
+    /// The selectors must be boolean, and `values.len()` must be `2^selectors.len()`.
+    Mux {
+        selectors: Vec<usize>,
+        values: Vec<usize>,
+    },
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -94,10 +112,46 @@ pub enum UnconstrainedBinOpType {
     BitXor,
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+/// The origin is only debug information, it's ignored by comparison, hashing and serialization.
+#[derive(Debug, Clone)]
 pub struct Constraint {
     pub typ: ConstraintType,
     pub var: usize,
+    origin: Option<SourceLocation>,
+}
+
+impl Constraint {
+    pub fn new(var: usize, typ: ConstraintType) -> Self {
+        Constraint {
+            typ,
+            var,
+            origin: None,
+        }
+    }
+
+    /// Where the constraint was asserted, if known.
+    pub fn origin(&self) -> Option<SourceLocation> {
+        self.origin
+    }
+
+    pub fn with_origin(self, origin: Option<SourceLocation>) -> Self {
+        Constraint { origin, ..self }
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.typ == other.typ && self.var == other.var
+    }
+}
+
+impl Eq for Constraint {}
+
+impl Hash for Constraint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.typ.hash(state);
+        self.var.hash(state);
+    }
 }
 
 #[derive(Debug, Clone, Hash, Copy, PartialEq, Eq)]
@@ -119,10 +173,17 @@ impl<C: Config> common::Constraint<C> for Constraint {
         Constraint {
             typ: self.typ,
             var: f(self.var),
+            origin: self.origin,
         }
     }
     fn new(var: usize, typ: ConstraintType) -> Self {
-        Constraint { var, typ }
+        Constraint::new(var, typ)
+    }
+    fn origin(&self) -> Option<SourceLocation> {
+        self.origin
+    }
+    fn with_origin(self, origin: Option<SourceLocation>) -> Self {
+        Constraint::with_origin(self, origin)
     }
 }
 
@@ -169,6 +230,7 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
             } => vec![*cond, *if_true, *if_false],
             Instruction::CustomGate { inputs, .. } => inputs.clone(),
             Instruction::ToBinary { x, .. } => vec![*x],
+            Instruction::Mux { selectors, values } => [selectors.as_slice(), values].concat(),
         }
     }
     fn num_outputs(&self) -> usize {
@@ -186,6 +248,7 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
             Instruction::UnconstrainedSelect { .. } => 1,
             Instruction::CustomGate { .. } => 1,
             Instruction::ToBinary { num_bits, .. } => *num_bits,
+            Instruction::Mux { .. } => 1,
         }
     }
     fn as_sub_circuit_call(&self) -> Option<(usize, &Vec<usize>, usize)> {
@@ -198,6 +261,12 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
             _ => None,
         }
     }
+    fn as_hint(&self) -> Option<usize> {
+        match self {
+            Instruction::Hint { hint_id, .. } => Some(*hint_id),
+            _ => None,
+        }
+    }
     fn sub_circuit_call(sub_circuit_id: usize, inputs: Vec<usize>, num_outputs: usize) -> Self {
         Instruction::SubCircuitCall {
             sub_circuit_id,
@@ -264,6 +333,10 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
                 x: f(*x),
                 num_bits: *num_bits,
             },
+            Instruction::Mux { selectors, values } => Instruction::Mux {
+                selectors: selectors.iter().map(|i| f(*i)).collect(),
+                values: values.iter().map(|i| f(*i)).collect(),
+            },
         }
     }
     fn from_kx_plus_b(x: usize, k: CircuitField<C>, b: CircuitField<C>) -> Self {
@@ -313,6 +386,19 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
                     ))
                 }
             }
+            Instruction::Mux { selectors, values } => {
+                if selectors.is_empty() || selectors.len() > MAX_MUX_SELECTORS {
+                    Err(Error::InternalError(format!(
+                        "mux instruction must have between 1 and {MAX_MUX_SELECTORS} selectors"
+                    )))
+                } else if values.len() != 1 << selectors.len() {
+                    Err(Error::InternalError(
+                        "mux instruction must have 2^selectors values".to_string(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
             _ => Ok(()),
         }
     }
@@ -406,6 +492,120 @@ impl<C: Config> common::Instruction<C> for Instruction<C> {
                 Ok(outputs) => EvalResult::Values(outputs),
                 Err(e) => EvalResult::Error(e),
             },
+            Instruction::Mux {
+                selectors,
+                values: mux_values,
+            } => {
+                let mut index = 0;
+                for (i, s) in selectors.iter().enumerate() {
+                    let s = values[*s];
+                    if s == CircuitField::<C>::one() {
+                        index |= 1 << i;
+                    } else if !s.is_zero() {
+                        return EvalResult::Error(Error::UserError(
+                            "invalid bool value".to_string(),
+                        ));
+                    }
+                }
+                EvalResult::Value(values[mux_values[index]])
+            }
+        }
+    }
+}
+
+impl<C: Config> CseInstruction<C> for Instruction<C> {
+    fn cse_key(&self) -> Option<Self> {
+        match self {
+            Instruction::LinComb(lc) => {
+                let mut lc = lc.clone();
+                lc.terms.sort_by_key(|term| term.var);
+                Some(Instruction::LinComb(lc))
+            }
+            Instruction::Mul(inputs) => {
+                let mut inputs = inputs.clone();
+                inputs.sort();
+                Some(Instruction::Mul(inputs))
+            }
+            Instruction::BoolBinOp { x, y, op } => Some(Instruction::BoolBinOp {
+                x: *x.min(y),
+                y: *x.max(y),
+                op: op.clone(),
+            }),
+            // commits and hints may depend on more than their inputs
+            Instruction::Commit(_) | Instruction::Hint { .. } => None,
+            Instruction::ConstantLike(Coef::Random) => None,
+            Instruction::SubCircuitCall { .. } => None,
+            _ => Some(self.clone()),
+        }
+    }
+}
+
+impl<C: Config> ConstFoldInstruction<C> for Instruction<C> {
+    fn constant_value(&self) -> Option<CircuitField<C>> {
+        match self {
+            Instruction::LinComb(lc) => lc.constant_value(),
+            Instruction::ConstantLike(Coef::Constant(value)) => Some(*value),
+            _ => None,
+        }
+    }
+    fn fold_constants<F: Fn(usize) -> Option<CircuitField<C>>>(&self, value_of: F) -> Option<Self> {
+        let zero = CircuitField::<C>::zero();
+        let one = CircuitField::<C>::one();
+        let kx = |x: usize, k: CircuitField<C>| {
+            Instruction::LinComb(expr::LinComb::from_kx_plus_b(x, k, zero))
+        };
+        let constant = |value: CircuitField<C>| {
+            Instruction::LinComb(expr::LinComb::from_kx_plus_b(0, zero, value))
+        };
+        let is_bool = |value: &CircuitField<C>| value.is_zero() || *value == one;
+        match self {
+            Instruction::LinComb(lc) => lc.fold_constants(value_of).map(Instruction::LinComb),
+            Instruction::Mul(inputs) => fold_mul(inputs, value_of).map(|res| match res {
+                FoldedMul::LinComb(lc) => Instruction::LinComb(lc),
+                FoldedMul::Mul(inputs) => Instruction::Mul(inputs),
+            }),
+            Instruction::Div { x, y, .. } => {
+                let y = value_of(*y)?;
+                // division by zero must still fail at runtime
+                let inv = y.inv()?;
+                Some(match value_of(*x) {
+                    Some(x) => constant(x * inv),
+                    None => kx(*x, inv),
+                })
+            }
+            Instruction::BoolBinOp { x, y, op } => {
+                let x = value_of(*x)?;
+                let y = value_of(*y)?;
+                if !is_bool(&x) || !is_bool(&y) {
+                    return None;
+                }
+                Some(constant(match op {
+                    BoolBinOpType::Xor => x + y - CircuitField::<C>::from(2_u32) * x * y,
+                    BoolBinOpType::Or => x + y - x * y,
+                    BoolBinOpType::And => x * y,
+                }))
+            }
+            Instruction::IsZero(x) => {
+                let x = value_of(*x)?;
+                Some(constant(CircuitField::<C>::from(x.is_zero() as u32)))
+            }
+            Instruction::Mux { selectors, values } => {
+                let mut index = 0;
+                for (i, s) in selectors.iter().enumerate() {
+                    let s = value_of(*s)?;
+                    if s == one {
+                        index |= 1 << i;
+                    } else if !s.is_zero() {
+                        return None;
+                    }
+                }
+                let x = values[index];
+                Some(match value_of(x) {
+                    Some(value) => constant(value),
+                    None => kx(x, one),
+                })
+            }
+            _ => None,
         }
     }
 }
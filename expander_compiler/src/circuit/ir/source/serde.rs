@@ -87,6 +87,11 @@ impl<C: Config> ExpSerde for Instruction<C> {
                 x.serialize_into(&mut writer)?;
                 num_bits.serialize_into(&mut writer)?;
             }
+            Instruction::Mux { selectors, values } => {
+                14u8.serialize_into(&mut writer)?;
+                selectors.serialize_into(&mut writer)?;
+                values.serialize_into(&mut writer)?;
+            }
         };
         Ok(())
     }
@@ -174,6 +179,10 @@ impl<C: Config> ExpSerde for Instruction<C> {
                 x: usize::deserialize_from(&mut reader)?,
                 num_bits: usize::deserialize_from(&mut reader)?,
             },
+            14 => Instruction::Mux {
+                selectors: Vec::<usize>::deserialize_from(&mut reader)?,
+                values: Vec::<usize>::deserialize_from(&mut reader)?,
+            },
             _ => {
                 return Err(IoError::new(
                     std::io::ErrorKind::InvalidData,
@@ -192,19 +201,18 @@ impl ExpSerde for Constraint {
     }
 
     fn deserialize_from<R: Read>(mut reader: R) -> SerdeResult<Self> {
-        Ok(Constraint {
-            typ: match u8::deserialize_from(&mut reader)? {
-                1 => ConstraintType::Zero,
-                2 => ConstraintType::NonZero,
-                3 => ConstraintType::Bool,
-                _ => {
-                    return Err(IoError::new(
-                        std::io::ErrorKind::InvalidData,
-                        "invalid ConstraintType",
-                    ))?
-                }
-            },
-            var: usize::deserialize_from(&mut reader)?,
-        })
+        let typ = match u8::deserialize_from(&mut reader)? {
+            1 => ConstraintType::Zero,
+            2 => ConstraintType::NonZero,
+            3 => ConstraintType::Bool,
+            _ => {
+                return Err(IoError::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid ConstraintType",
+                ))?
+            }
+        };
+        let var = usize::deserialize_from(&mut reader)?;
+        Ok(Constraint::new(var, typ))
     }
 }
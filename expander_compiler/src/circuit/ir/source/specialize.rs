@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    circuit::{
+        ir::common::{Constraint as _, Instruction as _},
+        layered::Coef,
+    },
+    frontend::CircuitField,
+};
+
+use super::{Circuit, Config, Instruction, RootCircuit};
+
+type SpecializationKey<C> = (usize, Vec<Option<CircuitField<C>>>);
+
+impl<C: Config> RootCircuit<C> {
+    /// Folds the constants in every circuit, and replaces each call to a sub circuit with some
+    /// constant inputs by a call to a copy of the sub circuit specialized to these constants.
+    /// Copies are shared by the calls with the same constants, and `budget` bounds the total
+    /// number of instructions in them.
+    pub fn specialize_constant_calls(&mut self, mut budget: usize) {
+        let mut queue: VecDeque<usize> = self.topo_order().into_iter().collect();
+        let mut specialized: HashMap<SpecializationKey<C>, usize> = HashMap::new();
+        let mut next_id = self.circuits.keys().max().unwrap() + 1;
+        while let Some(circuit_id) = queue.pop_front() {
+            let mut circuit = self.circuits.remove(&circuit_id).unwrap();
+            let values = circuit.fold_constants();
+            for insn in circuit.instructions.iter_mut() {
+                let (sub_circuit_id, inputs, num_outputs) = match insn.as_sub_circuit_call() {
+                    Some((id, inputs, num_outputs)) => (id, inputs.clone(), num_outputs),
+                    None => continue,
+                };
+                let mut pattern: Vec<Option<CircuitField<C>>> =
+                    inputs.iter().map(|x| values.get(x).cloned()).collect();
+                // sub circuits must have at least one input
+                if pattern.iter().all(|v| v.is_some()) {
+                    pattern[0] = None;
+                }
+                if pattern.iter().all(|v| v.is_none()) {
+                    continue;
+                }
+                let key = (sub_circuit_id, pattern);
+                let new_id = if let Some(id) = specialized.get(&key) {
+                    *id
+                } else {
+                    let callee = &self.circuits[&sub_circuit_id];
+                    // the copy also has one constant instruction per constant input
+                    let cost =
+                        callee.instructions.len() + key.1.iter().filter(|v| v.is_some()).count();
+                    if cost > budget {
+                        continue;
+                    }
+                    budget -= cost;
+                    let copy = callee.specialize(&key.1);
+                    self.circuits.insert(next_id, copy);
+                    queue.push_back(next_id);
+                    specialized.insert(key.clone(), next_id);
+                    next_id += 1;
+                    next_id - 1
+                };
+                *insn = Instruction::SubCircuitCall {
+                    sub_circuit_id: new_id,
+                    inputs: inputs
+                        .iter()
+                        .zip(key.1.iter())
+                        .filter(|(_, v)| v.is_none())
+                        .map(|(x, _)| *x)
+                        .collect(),
+                    num_outputs,
+                };
+            }
+            self.circuits.insert(circuit_id, circuit);
+        }
+    }
+}
+
+impl<C: Config> Circuit<C> {
+    // a copy of the circuit, where the inputs with a value in the pattern are constants
+    fn specialize(&self, pattern: &[Option<CircuitField<C>>]) -> Self {
+        let num_inputs = pattern.iter().filter(|v| v.is_none()).count();
+        // the constants are defined right after the remaining inputs, so the variables
+        // defined by the instructions keep their ids
+        let mut var_map: Vec<usize> = (0..=self.get_num_variables()).collect();
+        let mut instructions =
+            Vec::with_capacity(pattern.len() - num_inputs + self.instructions.len());
+        let mut next_input = 1;
+        for (i, v) in pattern.iter().enumerate() {
+            match v {
+                None => {
+                    var_map[i + 1] = next_input;
+                    next_input += 1;
+                }
+                Some(value) => {
+                    var_map[i + 1] = num_inputs + instructions.len() + 1;
+                    instructions.push(Instruction::ConstantLike(Coef::Constant(*value)));
+                }
+            }
+        }
+        instructions.extend(
+            self.instructions
+                .iter()
+                .map(|insn| insn.replace_vars(|x| var_map[x])),
+        );
+        Circuit {
+            num_inputs,
+            instructions,
+            constraints: self
+                .constraints
+                .iter()
+                .map(|cons| cons.replace_var(|x| var_map[x]))
+                .collect(),
+            outputs: self.outputs.iter().map(|x| var_map[*x]).collect(),
+        }
+    }
+}
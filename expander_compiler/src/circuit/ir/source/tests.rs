@@ -26,7 +26,8 @@ impl<C: Config> RandomInstruction for Instruction<C> {
         num_vars: usize,
         num_public_inputs: usize,
     ) -> Self {
-        let prob1 = rnd.gen::<f64>();
+        // the mux branch is drawn from the extra 1%, leaving the other branches as they were
+        let prob1 = rnd.gen::<f64>() * 1.01;
         if prob1 < 0.1 {
             ConstantLike(Coef::random_no_random(&mut rnd, num_public_inputs))
         } else if prob1 < 0.35 {
@@ -114,12 +115,22 @@ impl<C: Config> RandomInstruction for Instruction<C> {
                 y: rnd.next_u64() as usize % num_vars + 1,
                 op,
             }
-        } else {
+        } else if prob1 < 1.0 {
             super::Instruction::ToBinary {
                 x: rnd.next_u64() as usize % num_vars + 1,
                 num_bits: [1, 3, 66, 266, 267, 268, 270, 300, 300, 300]
                     [rnd.next_u64() as usize % 10],
             }
+        } else {
+            let num_selectors = rnd.next_u64() as usize % 3 + 1;
+            super::Instruction::Mux {
+                selectors: (0..num_selectors)
+                    .map(|_| rnd.next_u64() as usize % num_vars + 1)
+                    .collect(),
+                values: (0..1 << num_selectors)
+                    .map(|_| rnd.next_u64() as usize % num_vars + 1)
+                    .collect(),
+            }
         }
     }
 }
@@ -199,6 +210,222 @@ fn opt_remove_unreachable_2() {
     }
 }
 
+#[test]
+fn opt_eliminate_common_subexpressions() {
+    let mut config = RandomCircuitConfig {
+        seed: 0,
+        num_circuits: RandomRange { min: 1, max: 10 },
+        num_inputs: RandomRange { min: 1, max: 3 },
+        num_instructions: RandomRange { min: 10, max: 30 },
+        num_constraints: RandomRange { min: 0, max: 5 },
+        num_outputs: RandomRange { min: 1, max: 5 },
+        num_terms: RandomRange { min: 1, max: 3 },
+        sub_circuit_prob: 0.2,
+    };
+    for i in 0..3000 {
+        config.seed = i;
+        let root = RootCircuit::<C>::random(&config);
+        assert_eq!(root.validate(), Ok(()));
+        let mut optroot = root.clone();
+        optroot.eliminate_common_subexpressions();
+        assert_eq!(optroot.validate(), Ok(()));
+        assert_eq!(optroot.input_size(), root.input_size());
+        let inputs: Vec<CField> = (0..root.input_size())
+            .map(|_| CField::random_unsafe(&mut rand::thread_rng()))
+            .collect();
+        let e1 = root.eval_unsafe_with_errors(inputs.clone());
+        let e2 = optroot.eval_unsafe_with_errors(inputs);
+        if e1.is_ok() {
+            assert_eq!(e2, e1);
+        } else if e1.as_ref().err().unwrap().is_internal() {
+            panic!("{:?}", e1);
+        }
+    }
+}
+
+#[test]
+fn opt_eliminate_common_subexpressions_simple() {
+    let mut root = RootCircuit::<C>::default();
+    // 3 = x*y, 4 = y*x, 5 = hint(x), 6 = hint(x), 7 = sub(3), 8 = sub(4)
+    root.circuits.insert(
+        0,
+        Circuit::<C> {
+            num_inputs: 2,
+            instructions: vec![
+                Mul(vec![1, 2]),
+                Mul(vec![2, 1]),
+                Instruction::Hint {
+                    hint_id: hints::BuiltinHintIds::Identity as u64 as usize,
+                    inputs: vec![1],
+                    num_outputs: 1,
+                },
+                Instruction::Hint {
+                    hint_id: hints::BuiltinHintIds::Identity as u64 as usize,
+                    inputs: vec![1],
+                    num_outputs: 1,
+                },
+                Instruction::SubCircuitCall {
+                    sub_circuit_id: 1,
+                    inputs: vec![3],
+                    num_outputs: 1,
+                },
+                Instruction::SubCircuitCall {
+                    sub_circuit_id: 1,
+                    inputs: vec![4],
+                    num_outputs: 1,
+                },
+            ],
+            constraints: vec![
+                super::Constraint::new(7, ConstraintType::Zero),
+                super::Constraint::new(8, ConstraintType::Zero),
+            ],
+            outputs: vec![3, 4, 5, 6, 7, 8],
+        },
+    );
+    root.circuits.insert(
+        1,
+        Circuit::<C> {
+            num_inputs: 1,
+            instructions: vec![Mul(vec![1, 1]), Mul(vec![1, 1])],
+            constraints: vec![],
+            outputs: vec![2, 3],
+        },
+    );
+    assert_eq!(root.validate(), Ok(()));
+    root.eliminate_common_subexpressions();
+    assert_eq!(root.validate(), Ok(()));
+    let c1 = &root.circuits[&1];
+    assert_eq!(c1.instructions.len(), 1);
+    assert_eq!(c1.outputs, vec![2, 2]);
+    let c0 = &root.circuits[&0];
+    // the hints are kept, the muls and the calls to the pure sub circuit are merged
+    assert_eq!(c0.instructions.len(), 4);
+    assert_eq!(c0.outputs, vec![3, 3, 4, 5, 6, 6]);
+    assert_eq!(c0.constraints.len(), 1);
+}
+
+#[test]
+fn opt_fold_constants_and_specialize() {
+    let mut config = RandomCircuitConfig {
+        seed: 0,
+        num_circuits: RandomRange { min: 1, max: 10 },
+        num_inputs: RandomRange { min: 1, max: 3 },
+        num_instructions: RandomRange { min: 10, max: 30 },
+        num_constraints: RandomRange { min: 0, max: 5 },
+        num_outputs: RandomRange { min: 1, max: 5 },
+        num_terms: RandomRange { min: 1, max: 3 },
+        sub_circuit_prob: 0.2,
+    };
+    for i in 0..3000 {
+        config.seed = i;
+        let root = RootCircuit::<C>::random(&config);
+        assert_eq!(root.validate(), Ok(()));
+        let mut folded = root.clone();
+        folded.fold_constants();
+        assert_eq!(folded.validate(), Ok(()));
+        let mut specialized = root.clone();
+        specialized.specialize_constant_calls(if i % 2 == 0 { 20 } else { 1000 });
+        assert_eq!(specialized.validate(), Ok(()));
+        let inputs: Vec<CField> = (0..root.input_size())
+            .map(|_| CField::random_unsafe(&mut rand::thread_rng()))
+            .collect();
+        let e1 = root.eval_unsafe_with_errors(inputs.clone());
+        let e2 = folded.eval_unsafe_with_errors(inputs.clone());
+        let e3 = specialized.eval_unsafe_with_errors(inputs);
+        if e1.is_ok() {
+            assert_eq!(e2, e1);
+            assert_eq!(e3, e1);
+        } else if e1.as_ref().err().unwrap().is_internal() {
+            panic!("{:?}", e1);
+        }
+    }
+}
+
+#[test]
+fn specialize_constant_calls_simple() {
+    let mut root = RootCircuit::<C>::default();
+    // 2 = 0, 3 = 3, 4 = sub(x, 0), 5 = sub(x, 3), 6 = sub(x, 0)
+    root.circuits.insert(
+        0,
+        Circuit::<C> {
+            num_inputs: 1,
+            instructions: vec![
+                ConstantLike(Coef::Constant(CField::zero())),
+                LinComb(expr::LinComb::from_kx_plus_b(
+                    0,
+                    CField::zero(),
+                    CField::from(3u32),
+                )),
+                Instruction::SubCircuitCall {
+                    sub_circuit_id: 1,
+                    inputs: vec![1, 2],
+                    num_outputs: 1,
+                },
+                Instruction::SubCircuitCall {
+                    sub_circuit_id: 1,
+                    inputs: vec![1, 3],
+                    num_outputs: 1,
+                },
+                Instruction::SubCircuitCall {
+                    sub_circuit_id: 1,
+                    inputs: vec![1, 2],
+                    num_outputs: 1,
+                },
+            ],
+            constraints: vec![],
+            outputs: vec![4, 5, 6],
+        },
+    );
+    // 3 = x*y, 4 = 3 + y
+    root.circuits.insert(
+        1,
+        Circuit::<C> {
+            num_inputs: 2,
+            instructions: vec![
+                Mul(vec![1, 2]),
+                LinComb(expr::LinComb {
+                    terms: vec![
+                        expr::LinCombTerm {
+                            var: 3,
+                            coef: CField::one(),
+                        },
+                        expr::LinCombTerm {
+                            var: 2,
+                            coef: CField::one(),
+                        },
+                    ],
+                    constant: CField::zero(),
+                }),
+            ],
+            constraints: vec![],
+            outputs: vec![4],
+        },
+    );
+    assert_eq!(root.validate(), Ok(()));
+    let mut specialized = root.clone();
+    specialized.specialize_constant_calls(100);
+    assert_eq!(specialized.validate(), Ok(()));
+    // one copy for each distinct constant
+    assert_eq!(specialized.circuits.len(), 4);
+    for id in [2, 3] {
+        let c = &specialized.circuits[&id];
+        assert_eq!(c.num_inputs, 1);
+        assert!(!c.instructions.iter().any(|insn| matches!(insn, Mul(_))));
+    }
+    let inputs = vec![CField::from(5u32)];
+    assert_eq!(
+        specialized.eval_unsafe_with_errors(inputs.clone()),
+        root.eval_unsafe_with_errors(inputs)
+    );
+
+    // each copy has the 2 instructions of the callee and 1 for the constant input
+    for (budget, num_circuits) in [(3, 3), (5, 3), (6, 4)] {
+        let mut specialized = root.clone();
+        specialized.specialize_constant_calls(budget);
+        assert_eq!(specialized.circuits.len(), num_circuits, "budget {budget}");
+    }
+}
+
 fn test_detect_chains_inner(is_mul: bool, seq_typ: usize) {
     let n = 1000000;
     let mut root = RootCircuit::<C>::default();
@@ -270,3 +497,129 @@ fn test_detect_chains() {
     test_detect_chains_inner(true, 2);
     test_detect_chains_inner(true, 3);
 }
+
+#[test]
+fn text_round_trip() {
+    let mut config = RandomCircuitConfig {
+        seed: 0,
+        num_circuits: RandomRange { min: 1, max: 10 },
+        num_inputs: RandomRange { min: 1, max: 10 },
+        num_instructions: RandomRange { min: 1, max: 10 },
+        num_constraints: RandomRange { min: 0, max: 10 },
+        num_outputs: RandomRange { min: 1, max: 10 },
+        num_terms: RandomRange { min: 1, max: 5 },
+        sub_circuit_prob: 0.5,
+    };
+    for i in 0..1000 {
+        config.seed = i;
+        let root = RootCircuit::<C>::random(&config);
+        assert_eq!(root.validate(), Ok(()));
+        let text = root.to_text();
+        let root2 = RootCircuit::<C>::from_text(&text).unwrap();
+        assert_eq!(root, root2);
+    }
+}
+
+#[test]
+fn text_hint_keys() {
+    let key = "myhint.key \"quoted\"";
+    let hint_id = hints::registry::hint_key_to_id(key);
+    let mut hint_infos = hints::info::HintInfos::new();
+    hint_infos.record(hint_id, key, 0, 0, std::panic::Location::caller());
+    let mut root = RootCircuit::<C>::default();
+    root.circuits.insert(
+        0,
+        Circuit::<C> {
+            num_inputs: 2,
+            instructions: vec![
+                Instruction::Hint {
+                    hint_id,
+                    inputs: vec![1, 2],
+                    num_outputs: 2,
+                },
+                LinComb(expr::LinComb {
+                    terms: vec![expr::LinCombTerm {
+                        coef: -CircuitField::<C>::from(3u32),
+                        var: 3,
+                    }],
+                    constant: CircuitField::<C>::zero(),
+                }),
+            ],
+            constraints: vec![super::Constraint::new(5, ConstraintType::Zero)],
+            outputs: vec![4],
+        },
+    );
+    let text = root.to_text_with_hint_infos(&hint_infos);
+    assert!(text.contains("v3..v4 = hint \"myhint.key \\\"quoted\\\"\" v1 v2"));
+    assert!(text.contains("v5 = lin -3*v3"));
+    assert_eq!(RootCircuit::<C>::from_text(&text).unwrap(), root);
+
+    let bad = text.replace("v5 = lin", "v6 = lin");
+    assert!(RootCircuit::<C>::from_text(&bad).is_err());
+}
+
+#[test]
+fn text_builtin_hint_names() {
+    // every builtin hint must have its own name, otherwise it's printed as a raw id
+    let mut names = std::collections::HashSet::new();
+    let first = hints::BuiltinHintIds::Identity as u64 as usize;
+    for hint_id in first..=first + 100 {
+        if hints::BuiltinHintIds::from_usize(hint_id).is_none() {
+            continue;
+        }
+        let mut root = RootCircuit::<C>::default();
+        root.circuits.insert(
+            0,
+            Circuit::<C> {
+                num_inputs: 1,
+                instructions: vec![Instruction::Hint {
+                    hint_id,
+                    inputs: vec![1],
+                    num_outputs: 1,
+                }],
+                constraints: vec![],
+                outputs: vec![2],
+            },
+        );
+        let text = root.to_text();
+        let name = text
+            .split("hint builtin.")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .unwrap_or_else(|| panic!("builtin hint {hint_id:#x} has no name"));
+        assert!(names.insert(name.to_string()), "duplicate name {name}");
+    }
+}
+
+#[test]
+fn to_dot_call_graph() {
+    let mut root = RootCircuit::<C>::default();
+    let call = |inputs: Vec<usize>| Instruction::SubCircuitCall {
+        sub_circuit_id: 1,
+        inputs,
+        num_outputs: 1,
+    };
+    root.circuits.insert(
+        0,
+        Circuit::<C> {
+            num_inputs: 2,
+            instructions: vec![call(vec![1, 2]), call(vec![2, 3])],
+            constraints: vec![],
+            outputs: vec![4],
+        },
+    );
+    root.circuits.insert(
+        1,
+        Circuit::<C> {
+            num_inputs: 2,
+            instructions: vec![Mul(vec![1, 2])],
+            constraints: vec![],
+            outputs: vec![3],
+        },
+    );
+    assert_eq!(root.validate(), Ok(()));
+    let dot = root.to_dot();
+    assert!(dot.starts_with("digraph"));
+    assert!(dot.contains("c0 -> c1 [label=\"x2\"];"));
+    assert!(dot.contains("instructions: 1  constraints: 0"));
+}
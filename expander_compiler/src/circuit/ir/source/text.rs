@@ -0,0 +1,683 @@
+//! Textual format of the source IR.
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! public_inputs 1
+//! output_zeroes 0
+//!
+//! circuit 0 inputs 2
+//!   v3 = lin 3*v1 + v2 + 5
+//!   v4..v6 = hint "myhint.foo" v1 v3
+//!   v7 = call 12345 v4 v5
+//!   output v6 v7
+//!   assert_zero v3
+//! end
+//! ```
+//!
+//! Variables are numbered as in the IR: `v1..vN` are the inputs, and every instruction
+//! defines the next ones, written on the left as `vK`, `vK..vL` (inclusive) or `_` for none.
+//! Field elements are decimal, and may be negative. Circuits are written in increasing id order.
+//!
+//! Instructions:
+//! - `lin c*vK + vL + c`: linear combination, `lin 0` if empty
+//! - `mul vK vL ...`
+//! - `div vK vL checked|unchecked`
+//! - `xor|or|and vK vL`: boolean operations
+//! - `is_zero vK`, `commit vK ...`
+//! - `hint NAME vK ...`, where NAME is a quoted hint key, `builtin.NAME` or `#ID`
+//! - `const c`, `random`, `public I`
+//! - `call ID vK ...`
+//! - `unconstrained OP vK vL`, OP being the snake case name of an `UnconstrainedBinOpType`
+//! - `unconstrained_select vCond vTrue vFalse`
+//! - `custom GATE_TYPE vK ...`
+//! - `to_binary vK`
+//! - `mux [vK ...] [vL ...]`: selectors, then values
+//!
+//! Constraints are `assert_zero vK`, `assert_nonzero vK` and `assert_bool vK`, and outputs
+//! are `output vK ...` (several lines are concatenated).
+
+use std::fmt::Write;
+
+use ethnum::U256;
+
+use crate::{
+    circuit::{
+        config::Config,
+        ir::expr::{LinComb, LinCombTerm},
+        layered::Coef,
+    },
+    field::{Field, FieldArith},
+    frontend::CircuitField,
+    hints::{info::HintInfos, registry::hint_key_to_id, BuiltinHintIds},
+    utils::error::Error,
+};
+
+use super::{
+    super::common::Instruction as _, BoolBinOpType, Circuit, Constraint, ConstraintType,
+    Instruction, RootCircuit, UnconstrainedBinOpType,
+};
+
+const BUILTIN_HINT_NAMES: [(u64, &str); 24] = [
+    (BuiltinHintIds::Identity as u64, "identity"),
+    (BuiltinHintIds::Div as u64, "div"),
+    (BuiltinHintIds::Eq as u64, "eq"),
+    (BuiltinHintIds::NotEq as u64, "not_eq"),
+    (BuiltinHintIds::BoolOr as u64, "bool_or"),
+    (BuiltinHintIds::BoolAnd as u64, "bool_and"),
+    (BuiltinHintIds::BitOr as u64, "bit_or"),
+    (BuiltinHintIds::BitAnd as u64, "bit_and"),
+    (BuiltinHintIds::BitXor as u64, "bit_xor"),
+    (BuiltinHintIds::Select as u64, "select"),
+    (BuiltinHintIds::Pow as u64, "pow"),
+    (BuiltinHintIds::IntDiv as u64, "int_div"),
+    (BuiltinHintIds::Mod as u64, "mod"),
+    (BuiltinHintIds::ShiftL as u64, "shift_l"),
+    (BuiltinHintIds::ShiftR as u64, "shift_r"),
+    (BuiltinHintIds::LesserEq as u64, "lesser_eq"),
+    (BuiltinHintIds::GreaterEq as u64, "greater_eq"),
+    (BuiltinHintIds::Lesser as u64, "lesser"),
+    (BuiltinHintIds::Greater as u64, "greater"),
+    (BuiltinHintIds::ToBinary as u64, "to_binary"),
+    (BuiltinHintIds::RangeCount as u64, "range_count"),
+    (BuiltinHintIds::TableLookup as u64, "table_lookup"),
+    (BuiltinHintIds::TableCount as u64, "table_count"),
+    (BuiltinHintIds::TableDefine as u64, "table_define"),
+];
+
+const UNCONSTRAINED_OP_NAMES: [(UnconstrainedBinOpType, &str); 17] = [
+    (UnconstrainedBinOpType::Div, "div"),
+    (UnconstrainedBinOpType::Pow, "pow"),
+    (UnconstrainedBinOpType::IntDiv, "int_div"),
+    (UnconstrainedBinOpType::Mod, "mod"),
+    (UnconstrainedBinOpType::ShiftL, "shift_l"),
+    (UnconstrainedBinOpType::ShiftR, "shift_r"),
+    (UnconstrainedBinOpType::LesserEq, "lesser_eq"),
+    (UnconstrainedBinOpType::GreaterEq, "greater_eq"),
+    (UnconstrainedBinOpType::Lesser, "lesser"),
+    (UnconstrainedBinOpType::Greater, "greater"),
+    (UnconstrainedBinOpType::Eq, "eq"),
+    (UnconstrainedBinOpType::NotEq, "not_eq"),
+    (UnconstrainedBinOpType::BoolOr, "bool_or"),
+    (UnconstrainedBinOpType::BoolAnd, "bool_and"),
+    (UnconstrainedBinOpType::BitOr, "bit_or"),
+    (UnconstrainedBinOpType::BitAnd, "bit_and"),
+    (UnconstrainedBinOpType::BitXor, "bit_xor"),
+];
+
+fn field_to_text<C: Config>(x: &CircuitField<C>) -> String {
+    let x = x.to_u256();
+    let modulus = CircuitField::<C>::MODULUS;
+    if x > modulus / 2 {
+        format!("-{}", modulus - x)
+    } else {
+        x.to_string()
+    }
+}
+
+fn vars_to_text(vars: &[usize]) -> String {
+    vars.iter()
+        .map(|v| format!("v{v}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn hint_name_to_text(hint_id: usize, hint_infos: Option<&HintInfos>) -> String {
+    for (id, name) in BUILTIN_HINT_NAMES.iter() {
+        if hint_id == *id as usize {
+            return format!("builtin.{name}");
+        }
+    }
+    if let Some(key) = hint_infos.and_then(|infos| infos.key(hint_id)) {
+        if hint_key_to_id(key) == hint_id {
+            return quote(key);
+        }
+    }
+    format!("#{hint_id}")
+}
+
+fn lin_comb_to_text<C: Config>(lc: &LinComb<C>) -> String {
+    let mut parts: Vec<String> = lc
+        .terms
+        .iter()
+        .map(|term| {
+            if term.coef == CircuitField::<C>::one() {
+                format!("v{}", term.var)
+            } else {
+                format!("{}*v{}", field_to_text::<C>(&term.coef), term.var)
+            }
+        })
+        .collect();
+    if !lc.constant.is_zero() || parts.is_empty() {
+        parts.push(field_to_text::<C>(&lc.constant));
+    }
+    parts.join(" + ")
+}
+
+fn instruction_to_text<C: Config>(insn: &Instruction<C>, hint_infos: Option<&HintInfos>) -> String {
+    match insn {
+        Instruction::LinComb(lc) => format!("lin {}", lin_comb_to_text(lc)),
+        Instruction::Mul(inputs) => format!("mul {}", vars_to_text(inputs)),
+        Instruction::Div { x, y, checked } => format!(
+            "div v{x} v{y} {}",
+            if *checked { "checked" } else { "unchecked" }
+        ),
+        Instruction::BoolBinOp { x, y, op } => {
+            let op = match op {
+                BoolBinOpType::Xor => "xor",
+                BoolBinOpType::Or => "or",
+                BoolBinOpType::And => "and",
+            };
+            format!("{op} v{x} v{y}")
+        }
+        Instruction::IsZero(x) => format!("is_zero v{x}"),
+        Instruction::Commit(inputs) => format!("commit {}", vars_to_text(inputs)),
+        Instruction::Hint {
+            hint_id, inputs, ..
+        } => format!(
+            "hint {} {}",
+            hint_name_to_text(*hint_id, hint_infos),
+            vars_to_text(inputs)
+        ),
+        Instruction::ConstantLike(coef) => match coef {
+            Coef::Constant(c) => format!("const {}", field_to_text::<C>(c)),
+            Coef::Random => "random".to_string(),
+            Coef::PublicInput(i) => format!("public {i}"),
+        },
+        Instruction::SubCircuitCall {
+            sub_circuit_id,
+            inputs,
+            ..
+        } => format!("call {sub_circuit_id} {}", vars_to_text(inputs)),
+        Instruction::UnconstrainedBinOp { x, y, op } => {
+            let name = UNCONSTRAINED_OP_NAMES
+                .iter()
+                .find(|(o, _)| o == op)
+                .unwrap()
+                .1;
+            format!("unconstrained {name} v{x} v{y}")
+        }
+        Instruction::UnconstrainedSelect {
+            cond,
+            if_true,
+            if_false,
+        } => format!("unconstrained_select v{cond} v{if_true} v{if_false}"),
+        Instruction::CustomGate { gate_type, inputs } => {
+            format!("custom {gate_type} {}", vars_to_text(inputs))
+        }
+        Instruction::ToBinary { x, .. } => format!("to_binary v{x}"),
+        Instruction::Mux { selectors, values } => format!(
+            "mux [{}] [{}]",
+            vars_to_text(selectors),
+            vars_to_text(values)
+        ),
+    }
+}
+
+fn constraint_to_text(con: &Constraint) -> String {
+    let typ = match con.typ {
+        ConstraintType::Zero => "assert_zero",
+        ConstraintType::NonZero => "assert_nonzero",
+        ConstraintType::Bool => "assert_bool",
+    };
+    format!("{typ} v{}", con.var)
+}
+
+fn circuit_to_text<C: Config>(
+    res: &mut String,
+    circuit_id: usize,
+    circuit: &Circuit<C>,
+    hint_infos: Option<&HintInfos>,
+) {
+    writeln!(res, "circuit {circuit_id} inputs {}", circuit.num_inputs).unwrap();
+    let mut next_var = circuit.num_inputs + 1;
+    for insn in circuit.instructions.iter() {
+        let n = insn.num_outputs();
+        let lhs = match n {
+            0 => "_".to_string(),
+            1 => format!("v{next_var}"),
+            _ => format!("v{next_var}..v{}", next_var + n - 1),
+        };
+        next_var += n;
+        writeln!(res, "  {lhs} = {}", instruction_to_text(insn, hint_infos)).unwrap();
+    }
+    if !circuit.outputs.is_empty() {
+        writeln!(res, "  output {}", vars_to_text(&circuit.outputs)).unwrap();
+    }
+    for con in circuit.constraints.iter() {
+        writeln!(res, "  {}", constraint_to_text(con)).unwrap();
+    }
+    writeln!(res, "end").unwrap();
+}
+
+impl<C: Config> RootCircuit<C> {
+    pub fn to_text(&self) -> String {
+        self.to_text_impl(None)
+    }
+
+    /// Like `to_text`, but writes the keys of the hints recorded in `hint_infos` instead of their ids.
+    pub fn to_text_with_hint_infos(&self, hint_infos: &HintInfos) -> String {
+        self.to_text_impl(Some(hint_infos))
+    }
+
+    fn to_text_impl(&self, hint_infos: Option<&HintInfos>) -> String {
+        let mut res = String::new();
+        writeln!(res, "public_inputs {}", self.num_public_inputs).unwrap();
+        writeln!(res, "output_zeroes {}", self.expected_num_output_zeroes).unwrap();
+        let mut ids: Vec<usize> = self.circuits.keys().copied().collect();
+        ids.sort();
+        for id in ids {
+            writeln!(res).unwrap();
+            circuit_to_text(&mut res, id, &self.circuits[&id], hint_infos);
+        }
+        res
+    }
+
+    /// Parses the format written by `to_text`, and validates the result.
+    pub fn from_text(text: &str) -> Result<Self, Error> {
+        let mut parser = Parser {
+            lines: text.lines().enumerate(),
+        };
+        let root = parser.parse_root()?;
+        root.validate()?;
+        Ok(root)
+    }
+}
+
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut s = String::from('"');
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(e) => s.push(e),
+                        None => return Err("unterminated string".to_string()),
+                    },
+                    Some(e) => s.push(e),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            tokens.push(s);
+        } else if c == '[' || c == ']' {
+            chars.next();
+            tokens.push(c.to_string());
+        } else {
+            let mut s = String::new();
+            while let Some(&e) = chars.peek() {
+                if e.is_whitespace() || e == '[' || e == ']' || e == '"' {
+                    break;
+                }
+                s.push(e);
+                chars.next();
+            }
+            tokens.push(s);
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_usize(s: &str) -> Result<usize, String> {
+    s.parse::<usize>()
+        .map_err(|_| format!("expected a number, got `{s}`"))
+}
+
+fn parse_var(s: &str) -> Result<usize, String> {
+    match s.strip_prefix('v') {
+        Some(id) => parse_usize(id),
+        None => Err(format!("expected a variable, got `{s}`")),
+    }
+}
+
+fn parse_vars(tokens: &[String]) -> Result<Vec<usize>, String> {
+    tokens.iter().map(|t| parse_var(t)).collect()
+}
+
+fn parse_field<C: Config>(s: &str) -> Result<CircuitField<C>, String> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, s),
+    };
+    let x = U256::from_str_radix(digits, 10)
+        .map_err(|_| format!("expected a field element, got `{s}`"))?;
+    if x >= CircuitField::<C>::MODULUS {
+        return Err(format!("`{s}` is not below the field modulus"));
+    }
+    let x = CircuitField::<C>::from_u256(x);
+    Ok(if negative { -x } else { x })
+}
+
+fn parse_lin_comb<C: Config>(tokens: &[String]) -> Result<LinComb<C>, String> {
+    let mut lc = LinComb {
+        terms: vec![],
+        constant: CircuitField::<C>::zero(),
+    };
+    for (i, t) in tokens.iter().enumerate() {
+        if i % 2 == 1 {
+            if t != "+" {
+                return Err(format!("expected `+`, got `{t}`"));
+            }
+            continue;
+        }
+        if let Some((coef, var)) = t.split_once('*') {
+            lc.terms.push(LinCombTerm {
+                coef: parse_field::<C>(coef)?,
+                var: parse_var(var)?,
+            });
+        } else if t.starts_with('v') {
+            lc.terms.push(LinCombTerm {
+                coef: CircuitField::<C>::one(),
+                var: parse_var(t)?,
+            });
+        } else {
+            lc.constant += parse_field::<C>(t)?;
+        }
+    }
+    Ok(lc)
+}
+
+fn parse_hint_id(s: &str) -> Result<usize, String> {
+    if let Some(key) = s.strip_prefix('"') {
+        return Ok(hint_key_to_id(key));
+    }
+    if let Some(id) = s.strip_prefix('#') {
+        return parse_usize(id);
+    }
+    if let Some(name) = s.strip_prefix("builtin.") {
+        for (id, n) in BUILTIN_HINT_NAMES.iter() {
+            if *n == name {
+                return Ok(*id as usize);
+            }
+        }
+    }
+    Err(format!("unknown hint `{s}`"))
+}
+
+fn expect_len(tokens: &[String], n: usize) -> Result<(), String> {
+    if tokens.len() != n {
+        return Err(format!("expected {} operands, got {}", n, tokens.len()));
+    }
+    Ok(())
+}
+
+fn parse_instruction<C: Config>(
+    tokens: &[String],
+    num_outputs: usize,
+) -> Result<Instruction<C>, String> {
+    let (op, args) = match tokens.split_first() {
+        Some(x) => x,
+        None => return Err("missing instruction".to_string()),
+    };
+    Ok(match op.as_str() {
+        "lin" => Instruction::LinComb(parse_lin_comb(args)?),
+        "mul" => Instruction::Mul(parse_vars(args)?),
+        "div" => {
+            expect_len(args, 3)?;
+            let checked = match args[2].as_str() {
+                "checked" => true,
+                "unchecked" => false,
+                s => return Err(format!("expected checked or unchecked, got `{s}`")),
+            };
+            Instruction::Div {
+                x: parse_var(&args[0])?,
+                y: parse_var(&args[1])?,
+                checked,
+            }
+        }
+        "xor" | "or" | "and" => {
+            expect_len(args, 2)?;
+            Instruction::BoolBinOp {
+                x: parse_var(&args[0])?,
+                y: parse_var(&args[1])?,
+                op: match op.as_str() {
+                    "xor" => BoolBinOpType::Xor,
+                    "or" => BoolBinOpType::Or,
+                    _ => BoolBinOpType::And,
+                },
+            }
+        }
+        "is_zero" => {
+            expect_len(args, 1)?;
+            Instruction::IsZero(parse_var(&args[0])?)
+        }
+        "commit" => Instruction::Commit(parse_vars(args)?),
+        "hint" => {
+            let (name, inputs) = match args.split_first() {
+                Some(x) => x,
+                None => return Err("missing hint name".to_string()),
+            };
+            Instruction::Hint {
+                hint_id: parse_hint_id(name)?,
+                inputs: parse_vars(inputs)?,
+                num_outputs,
+            }
+        }
+        "const" => {
+            expect_len(args, 1)?;
+            Instruction::ConstantLike(Coef::Constant(parse_field::<C>(&args[0])?))
+        }
+        "random" => {
+            expect_len(args, 0)?;
+            Instruction::ConstantLike(Coef::Random)
+        }
+        "public" => {
+            expect_len(args, 1)?;
+            Instruction::ConstantLike(Coef::PublicInput(parse_usize(&args[0])?))
+        }
+        "call" => {
+            let (id, inputs) = match args.split_first() {
+                Some(x) => x,
+                None => return Err("missing sub circuit id".to_string()),
+            };
+            Instruction::SubCircuitCall {
+                sub_circuit_id: parse_usize(id)?,
+                inputs: parse_vars(inputs)?,
+                num_outputs,
+            }
+        }
+        "unconstrained" => {
+            expect_len(args, 3)?;
+            let op = match UNCONSTRAINED_OP_NAMES.iter().find(|(_, n)| *n == args[0]) {
+                Some((op, _)) => op.clone(),
+                None => return Err(format!("unknown unconstrained op `{}`", args[0])),
+            };
+            Instruction::UnconstrainedBinOp {
+                x: parse_var(&args[1])?,
+                y: parse_var(&args[2])?,
+                op,
+            }
+        }
+        "unconstrained_select" => {
+            expect_len(args, 3)?;
+            Instruction::UnconstrainedSelect {
+                cond: parse_var(&args[0])?,
+                if_true: parse_var(&args[1])?,
+                if_false: parse_var(&args[2])?,
+            }
+        }
+        "custom" => {
+            let (gate_type, inputs) = match args.split_first() {
+                Some(x) => x,
+                None => return Err("missing gate type".to_string()),
+            };
+            Instruction::CustomGate {
+                gate_type: parse_usize(gate_type)?,
+                inputs: parse_vars(inputs)?,
+            }
+        }
+        "to_binary" => {
+            expect_len(args, 1)?;
+            Instruction::ToBinary {
+                x: parse_var(&args[0])?,
+                num_bits: num_outputs,
+            }
+        }
+        "mux" => {
+            let mut groups = Vec::new();
+            let mut rest = args;
+            while let Some((open, tail)) = rest.split_first() {
+                if open != "[" {
+                    return Err(format!("expected `[`, got `{open}`"));
+                }
+                let close = match tail.iter().position(|t| t == "]") {
+                    Some(i) => i,
+                    None => return Err("expected `]`".to_string()),
+                };
+                groups.push(parse_vars(&tail[..close])?);
+                rest = &tail[close + 1..];
+            }
+            if groups.len() != 2 {
+                return Err("mux expects selectors and values in brackets".to_string());
+            }
+            let values = groups.pop().unwrap();
+            let selectors = groups.pop().unwrap();
+            Instruction::Mux { selectors, values }
+        }
+        s => return Err(format!("unknown instruction `{s}`")),
+    })
+}
+
+struct Parser<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+impl Parser<'_> {
+    // next non-empty line, tokenized, with its 1-based line number
+    fn next_line(&mut self) -> Result<Option<(usize, Vec<String>)>, Error> {
+        for (i, line) in self.lines.by_ref() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return match tokenize(line) {
+                Ok(tokens) => Ok(Some((i + 1, tokens))),
+                Err(e) => Err(parse_error(i + 1, e)),
+            };
+        }
+        Ok(None)
+    }
+
+    fn header(&mut self, key: &str) -> Result<usize, Error> {
+        match self.next_line()? {
+            Some((n, tokens)) => {
+                if tokens.len() != 2 || tokens[0] != key {
+                    return Err(parse_error(n, format!("expected `{key} N`")));
+                }
+                parse_usize(&tokens[1]).map_err(|e| parse_error(n, e))
+            }
+            None => Err(parse_error(0, format!("missing `{key}`"))),
+        }
+    }
+
+    fn parse_root<C: Config>(&mut self) -> Result<RootCircuit<C>, Error> {
+        let mut root = RootCircuit::<C> {
+            num_public_inputs: self.header("public_inputs")?,
+            expected_num_output_zeroes: self.header("output_zeroes")?,
+            ..Default::default()
+        };
+        while let Some((n, tokens)) = self.next_line()? {
+            if tokens.len() != 4 || tokens[0] != "circuit" || tokens[2] != "inputs" {
+                return Err(parse_error(n, "expected `circuit ID inputs N`".to_string()));
+            }
+            let id = parse_usize(&tokens[1]).map_err(|e| parse_error(n, e))?;
+            let num_inputs = parse_usize(&tokens[3]).map_err(|e| parse_error(n, e))?;
+            let circuit = self.parse_circuit(num_inputs)?;
+            if root.circuits.insert(id, circuit).is_some() {
+                return Err(parse_error(n, format!("duplicate circuit {id}")));
+            }
+        }
+        Ok(root)
+    }
+
+    fn parse_circuit<C: Config>(&mut self, num_inputs: usize) -> Result<Circuit<C>, Error> {
+        let mut circuit = Circuit::<C> {
+            instructions: vec![],
+            constraints: vec![],
+            outputs: vec![],
+            num_inputs,
+        };
+        let mut next_var = num_inputs + 1;
+        loop {
+            let (n, tokens) = match self.next_line()? {
+                Some(x) => x,
+                None => return Err(parse_error(0, "missing `end`".to_string())),
+            };
+            if tokens[0] == "end" {
+                return Ok(circuit);
+            }
+            parse_circuit_line(&mut circuit, &mut next_var, &tokens)
+                .map_err(|e| parse_error(n, e))?;
+        }
+    }
+}
+
+fn parse_circuit_line<C: Config>(
+    circuit: &mut Circuit<C>,
+    next_var: &mut usize,
+    tokens: &[String],
+) -> Result<(), String> {
+    match tokens[0].as_str() {
+        "output" => circuit.outputs.extend(parse_vars(&tokens[1..])?),
+        "assert_zero" | "assert_nonzero" | "assert_bool" => {
+            expect_len(&tokens[1..], 1)?;
+            let typ = match tokens[0].as_str() {
+                "assert_zero" => ConstraintType::Zero,
+                "assert_nonzero" => ConstraintType::NonZero,
+                _ => ConstraintType::Bool,
+            };
+            circuit
+                .constraints
+                .push(Constraint::new(parse_var(&tokens[1])?, typ));
+        }
+        _ => {
+            if tokens.len() < 3 || tokens[1] != "=" {
+                return Err("expected `LHS = INSTRUCTION`".to_string());
+            }
+            let num_outputs = parse_lhs(&tokens[0], *next_var)?;
+            let insn = parse_instruction::<C>(&tokens[2..], num_outputs)?;
+            if insn.num_outputs() != num_outputs {
+                return Err(format!(
+                    "instruction has {} outputs, but {} are defined",
+                    insn.num_outputs(),
+                    num_outputs
+                ));
+            }
+            *next_var += num_outputs;
+            circuit.instructions.push(insn);
+        }
+    }
+    Ok(())
+}
+
+// number of variables defined by the left hand side, which must start at next_var
+fn parse_lhs(lhs: &str, next_var: usize) -> Result<usize, String> {
+    if lhs == "_" {
+        return Ok(0);
+    }
+    let (first, last) = match lhs.split_once("..") {
+        Some((a, b)) => (parse_var(a)?, parse_var(b)?),
+        None => {
+            let v = parse_var(lhs)?;
+            (v, v)
+        }
+    };
+    if first != next_var || last < first {
+        return Err(format!(
+            "`{lhs}` doesn't define the next variables, starting at v{next_var}"
+        ));
+    }
+    Ok(last - first + 1)
+}
+
+fn parse_error(line: usize, msg: String) -> Error {
+    Error::DeserializationFailed(format!("source IR text, line {line}: {msg}"))
+}
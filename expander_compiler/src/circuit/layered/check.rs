@@ -0,0 +1,277 @@
+use std::fmt;
+
+use crate::{
+    circuit::{
+        config::{CircuitField, Config},
+        input_mapping::{InputMapping, EMPTY},
+        ir::{
+            self,
+            common::SourceLocation,
+            hint_less::{Instruction, Irc},
+        },
+    },
+    field::FieldArith,
+    hints,
+    utils::error::Error,
+};
+
+use super::{witness::Witness, Circuit, InputType};
+
+/// Maps the outputs of a layered circuit back to the constraints they check.
+///
+/// The zero outputs of the layered circuit are random linear combinations (or plain copies,
+/// when the field doesn't allow random combination) of the constraints, so a failed output
+/// can't be blamed on a single constraint by itself. Instead, the debug map keeps the
+/// hint-less IR, where hint outputs are plain inputs and every constraint is still
+/// separate, together with the mapping from its inputs to the layered circuit inputs.
+/// Each constraint of the hint-less IR keeps the frontend location of the assertion it comes
+/// from, which is what failed constraints are reported with.
+#[derive(Debug, Clone)]
+pub struct ConstraintDebugMap<C: Config> {
+    circuit: ir::hint_less::RootCircuit<C>,
+    input_mapping: InputMapping,
+}
+
+/// Identifies a constraint of the hint-less IR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintOrigin {
+    /// Where the constraint was asserted in the frontend. It's `None` for the constraints added
+    /// by the compiler, e.g. for checked divisions. When the same constraint is asserted at
+    /// several places, only the first one is kept.
+    pub location: Option<SourceLocation>,
+    /// Ids of the circuits from the root (always 0) to the one holding the constraint.
+    /// These are the ids of the optimized hint-less IR, not the ones of the frontend.
+    pub sub_circuit_path: Vec<usize>,
+    /// Index of the constraint in `constraints` of that circuit.
+    pub constraint_index: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FailedConstraint<C: Config> {
+    pub witness_index: usize,
+    pub origin: ConstraintOrigin,
+    /// The value of the constrained variable, which should have been zero.
+    pub value: CircuitField<C>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WitnessCheckReport<C: Config> {
+    /// Pairs of (witness index, layered output index) for the outputs which should be zero but aren't.
+    pub failed_outputs: Vec<(usize, usize)>,
+    pub failed_constraints: Vec<FailedConstraint<C>>,
+}
+
+impl<C: Config> WitnessCheckReport<C> {
+    pub fn is_satisfied(&self) -> bool {
+        self.failed_outputs.is_empty() && self.failed_constraints.is_empty()
+    }
+}
+
+impl fmt::Display for ConstraintOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self
+            .sub_circuit_path
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        match self.location {
+            Some(location) => write!(
+                f,
+                "constraint asserted at {} (constraint {} in sub-circuit path {})",
+                location, self.constraint_index, path
+            ),
+            None => write!(
+                f,
+                "compiler constraint {} in sub-circuit path {}",
+                self.constraint_index, path
+            ),
+        }
+    }
+}
+
+impl<C: Config> fmt::Display for WitnessCheckReport<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} output(s) and {} constraint(s) failed",
+            self.failed_outputs.len(),
+            self.failed_constraints.len()
+        )?;
+        for (witness_index, output_index) in self.failed_outputs.iter() {
+            write!(
+                f,
+                "\n  witness {witness_index}: output {output_index} is not zero"
+            )?;
+        }
+        for c in self.failed_constraints.iter() {
+            write!(
+                f,
+                "\n  witness {}: {} has value {:?}",
+                c.witness_index, c.origin, c.value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Config> ConstraintDebugMap<C> {
+    /// `input_mapping` maps the inputs of `circuit` to the inputs of the layered circuit.
+    pub fn new(circuit: ir::hint_less::RootCircuit<C>, input_mapping: InputMapping) -> Self {
+        ConstraintDebugMap {
+            circuit,
+            input_mapping,
+        }
+    }
+
+    pub fn circuit(&self) -> &ir::hint_less::RootCircuit<C> {
+        &self.circuit
+    }
+
+    fn failed_constraints(
+        &self,
+        witness_index: usize,
+        layered_inputs: &[CircuitField<C>],
+        public_inputs: &[CircuitField<C>],
+        res: &mut Vec<FailedConstraint<C>>,
+    ) {
+        let inputs = self
+            .input_mapping
+            .mapping()
+            .iter()
+            .map(|&i| {
+                if i == EMPTY {
+                    // removed inputs don't affect any constraint
+                    CircuitField::<C>::zero()
+                } else {
+                    layered_inputs[i]
+                }
+            })
+            .collect();
+        let mut path = vec![0];
+        self.eval_sub(
+            &self.circuit.circuits[&0],
+            inputs,
+            public_inputs,
+            &mut path,
+            witness_index,
+            res,
+        );
+    }
+
+    fn eval_sub(
+        &self,
+        circuit: &ir::common::Circuit<Irc<C>>,
+        inputs: Vec<CircuitField<C>>,
+        public_inputs: &[CircuitField<C>],
+        path: &mut Vec<usize>,
+        witness_index: usize,
+        res: &mut Vec<FailedConstraint<C>>,
+    ) -> Vec<CircuitField<C>> {
+        let mut values = vec![CircuitField::<C>::zero(); 1];
+        values.extend(inputs);
+        for insn in circuit.instructions.iter() {
+            match insn {
+                Instruction::LinComb(lc) => values.push(lc.eval(&values)),
+                Instruction::Mul(inputs) => {
+                    let mut r = CircuitField::<C>::one();
+                    for &i in inputs.iter() {
+                        r *= values[i];
+                    }
+                    values.push(r);
+                }
+                Instruction::ConstantLike(coef) => {
+                    values.push(coef.get_value_with_public_inputs(public_inputs))
+                }
+                Instruction::SubCircuitCall {
+                    sub_circuit_id,
+                    inputs,
+                    ..
+                } => {
+                    path.push(*sub_circuit_id);
+                    let outputs = self.eval_sub(
+                        &self.circuit.circuits[sub_circuit_id],
+                        inputs.iter().map(|&i| values[i]).collect(),
+                        public_inputs,
+                        path,
+                        witness_index,
+                        res,
+                    );
+                    path.pop();
+                    values.extend(outputs);
+                }
+                Instruction::CustomGate { gate_type, inputs } => {
+                    let inputs = inputs.iter().map(|&i| values[i]).collect();
+                    values.extend(hints::stub_impl(*gate_type, &inputs, 1));
+                }
+            }
+        }
+        for (i, c) in circuit.constraints.iter().enumerate() {
+            let value = values[c.var];
+            if !value.is_zero() {
+                res.push(FailedConstraint {
+                    witness_index,
+                    origin: ConstraintOrigin {
+                        location: c.origin,
+                        sub_circuit_path: path.clone(),
+                        constraint_index: i,
+                    },
+                    value,
+                });
+            }
+        }
+        circuit.outputs.iter().map(|&o| values[o]).collect()
+    }
+}
+
+impl<C: Config, I: InputType> Circuit<C, I> {
+    /// Evaluates the circuit on every witness and reports the outputs and the constraints that failed.
+    pub fn check_witness(
+        &self,
+        witness: &Witness<C>,
+        debug_map: &ConstraintDebugMap<C>,
+    ) -> Result<WitnessCheckReport<C>, Error> {
+        if witness.num_inputs_per_witness != self.input_size()
+            || witness.num_public_inputs_per_witness != self.num_public_inputs
+        {
+            return Err(Error::InvalidShape(format!(
+                "witness has {} inputs and {} public inputs, circuit expects {} and {}",
+                witness.num_inputs_per_witness,
+                witness.num_public_inputs_per_witness,
+                self.input_size(),
+                self.num_public_inputs
+            )));
+        }
+        if debug_map.input_mapping.next_size() != self.input_size() {
+            return Err(Error::InvalidShape(
+                "debug map doesn't belong to this circuit".to_string(),
+            ));
+        }
+        let mut report = WitnessCheckReport {
+            failed_outputs: Vec::new(),
+            failed_constraints: Vec::new(),
+        };
+        for (witness_index, (inputs, public_inputs)) in witness.iter_scalar().enumerate() {
+            let outputs = self.eval_all_outputs_with_public_inputs(inputs.clone(), &public_inputs);
+            let n = report.failed_outputs.len();
+            for (i, out) in outputs
+                .iter()
+                .take(self.expected_num_output_zeroes)
+                .enumerate()
+            {
+                if !out.is_zero() {
+                    report.failed_outputs.push((witness_index, i));
+                }
+            }
+            if report.failed_outputs.len() > n {
+                debug_map.failed_constraints(
+                    witness_index,
+                    &inputs,
+                    &public_inputs,
+                    &mut report.failed_constraints,
+                );
+            }
+        }
+        Ok(report)
+    }
+}
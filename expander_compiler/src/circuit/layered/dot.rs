@@ -0,0 +1,40 @@
+use std::fmt::Write;
+
+use super::{Circuit, Config, InputType, InputUsize};
+
+impl<C: Config, I: InputType> Circuit<C, I> {
+    /// Renders the segments in Graphviz DOT format, with their gate counts, the child segments
+    /// they allocate (labelled with the number of allocations), and the segment of each layer.
+    pub fn to_dot(&self) -> String {
+        let mut res = String::new();
+        writeln!(res, "digraph layered {{").unwrap();
+        writeln!(res, "  node [shape=box];").unwrap();
+        for (i, seg) in self.segments.iter().enumerate() {
+            writeln!(
+                res,
+                "  s{i} [label=\"segment {i}\\ninputs: {:?}  outputs: {}\\nmul: {}  add: {}  const: {}  custom: {}\"];",
+                seg.num_inputs.to_vec(),
+                seg.num_outputs,
+                seg.gate_muls.len(),
+                seg.gate_adds.len(),
+                seg.gate_consts.len(),
+                seg.gate_customs.len()
+            )
+            .unwrap();
+            for (sub_id, allocs) in seg.child_segs.iter() {
+                writeln!(res, "  s{i} -> s{sub_id} [label=\"x{}\"];", allocs.len()).unwrap();
+            }
+        }
+        writeln!(res, "  subgraph layers {{").unwrap();
+        writeln!(res, "    rank=same;").unwrap();
+        for i in 0..self.layer_ids.len() {
+            writeln!(res, "    l{i} [shape=ellipse, label=\"layer {i}\"];").unwrap();
+        }
+        writeln!(res, "  }}").unwrap();
+        for (i, id) in self.layer_ids.iter().enumerate() {
+            writeln!(res, "  l{i} -> s{id} [style=dashed];").unwrap();
+        }
+        writeln!(res, "}}").unwrap();
+        res
+    }
+}
@@ -0,0 +1,303 @@
+//! JSON representation of layered circuits, for inspection by external tools.
+//!
+//! ```text
+//! {
+//!   "num_public_inputs": 0,
+//!   "num_actual_outputs": 2,
+//!   "expected_num_output_zeroes": 0,
+//!   "layer_ids": [1, 0],
+//!   "segments": [
+//!     {
+//!       "num_inputs": 4,
+//!       "num_outputs": 2,
+//!       "child_segs": [{ "segment": 0, "allocations": [{ "input_offset": 0, "output_offset": 0 }] }],
+//!       "gate_muls": [{ "inputs": [0, 1], "output": 0, "coef": "2" }],
+//!       "gate_adds": [{ "inputs": [2], "output": 1, "coef": { "public_input": 0 } }],
+//!       "gate_consts": [{ "inputs": [], "output": 1, "coef": "random" }],
+//!       "gate_customs": [{ "gate_type": 12345, "inputs": [0, 1], "output": 1, "coef": "1" }]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Field elements are decimal strings. For circuits with cross layer inputs, an input is
+//! `{ "layer": l, "offset": o }` instead of a number, and `num_inputs` and `input_offset`
+//! are arrays with one entry per input layer.
+
+use ethnum::U256;
+use serde_json::{json, Map, Value};
+
+use crate::{field::Field, utils::error::Error};
+
+use super::{
+    Allocation, ChildSpec, Circuit, CircuitField, Coef, Config, Gate, GateCustom, Input, InputType,
+    InputUsize, Segment,
+};
+
+fn coef_to_json<C: Config>(coef: &Coef<C>) -> Value {
+    match coef {
+        Coef::Constant(c) => Value::String(c.to_u256().to_string()),
+        Coef::Random => Value::String("random".to_string()),
+        Coef::PublicInput(i) => json!({ "public_input": i }),
+    }
+}
+
+fn input_to_json<I: InputType>(input: &I::Input) -> Value {
+    if I::CROSS_LAYER_RELAY {
+        json!({ "layer": input.layer(), "offset": input.offset() })
+    } else {
+        json!(input.offset())
+    }
+}
+
+fn input_usize_to_json<I: InputType>(x: &I::InputUsize) -> Value {
+    if I::CROSS_LAYER_RELAY {
+        json!(x.to_vec())
+    } else {
+        json!(x.get(0))
+    }
+}
+
+fn gate_to_json<C: Config, I: InputType, const INPUT_NUM: usize>(
+    gate: &Gate<C, I, INPUT_NUM>,
+) -> Value {
+    json!({
+        "inputs": gate.inputs.iter().map(input_to_json::<I>).collect::<Vec<_>>(),
+        "output": gate.output,
+        "coef": coef_to_json(&gate.coef),
+    })
+}
+
+fn segment_to_json<C: Config, I: InputType>(seg: &Segment<C, I>) -> Value {
+    json!({
+        "num_inputs": input_usize_to_json::<I>(&seg.num_inputs),
+        "num_outputs": seg.num_outputs,
+        "child_segs": seg.child_segs.iter().map(|(id, allocs)| json!({
+            "segment": id,
+            "allocations": allocs.iter().map(|a| json!({
+                "input_offset": input_usize_to_json::<I>(&a.input_offset),
+                "output_offset": a.output_offset,
+            })).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "gate_muls": seg.gate_muls.iter().map(gate_to_json).collect::<Vec<_>>(),
+        "gate_adds": seg.gate_adds.iter().map(gate_to_json).collect::<Vec<_>>(),
+        "gate_consts": seg.gate_consts.iter().map(gate_to_json).collect::<Vec<_>>(),
+        "gate_customs": seg.gate_customs.iter().map(|g| json!({
+            "gate_type": g.gate_type,
+            "inputs": g.inputs.iter().map(input_to_json::<I>).collect::<Vec<_>>(),
+            "output": g.output,
+            "coef": coef_to_json(&g.coef),
+        })).collect::<Vec<_>>(),
+    })
+}
+
+fn json_error(path: &str, msg: &str) -> Error {
+    Error::DeserializationFailed(format!("layered circuit json: {path}: {msg}"))
+}
+
+fn get<'a>(obj: &'a Value, key: &str, path: &str) -> Result<&'a Value, Error> {
+    obj.get(key)
+        .ok_or_else(|| json_error(path, &format!("missing field `{key}`")))
+}
+
+fn to_usize(v: &Value, path: &str) -> Result<usize, Error> {
+    v.as_u64()
+        .map(|x| x as usize)
+        .ok_or_else(|| json_error(path, "expected a non-negative integer"))
+}
+
+fn get_usize(obj: &Value, key: &str, path: &str) -> Result<usize, Error> {
+    to_usize(get(obj, key, path)?, &format!("{path}.{key}"))
+}
+
+fn get_array<'a>(obj: &'a Value, key: &str, path: &str) -> Result<&'a Vec<Value>, Error> {
+    get(obj, key, path)?
+        .as_array()
+        .ok_or_else(|| json_error(&format!("{path}.{key}"), "expected an array"))
+}
+
+fn coef_from_json<C: Config>(v: &Value, path: &str) -> Result<Coef<C>, Error> {
+    match v {
+        Value::String(s) if s == "random" => Ok(Coef::Random),
+        Value::String(s) => {
+            let x = U256::from_str_radix(s, 10)
+                .map_err(|_| json_error(path, "expected a decimal field element"))?;
+            if x >= CircuitField::<C>::MODULUS {
+                return Err(json_error(path, "constant is not below the field modulus"));
+            }
+            Ok(Coef::Constant(CircuitField::<C>::from_u256(x)))
+        }
+        Value::Object(_) => Ok(Coef::PublicInput(get_usize(v, "public_input", path)?)),
+        _ => Err(json_error(path, "invalid coef")),
+    }
+}
+
+fn input_from_json<I: InputType>(v: &Value, path: &str) -> Result<I::Input, Error> {
+    if I::CROSS_LAYER_RELAY {
+        Ok(I::Input::new(
+            get_usize(v, "layer", path)?,
+            get_usize(v, "offset", path)?,
+        ))
+    } else {
+        Ok(I::Input::new(0, to_usize(v, path)?))
+    }
+}
+
+fn input_usize_from_json<I: InputType>(v: &Value, path: &str) -> Result<I::InputUsize, Error> {
+    if I::CROSS_LAYER_RELAY {
+        let xs = v
+            .as_array()
+            .ok_or_else(|| json_error(path, "expected an array"))?;
+        let xs = xs
+            .iter()
+            .enumerate()
+            .map(|(i, x)| to_usize(x, &format!("{path}[{i}]")))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(I::InputUsize::from_vec(xs))
+    } else {
+        Ok(I::InputUsize::from_vec(vec![to_usize(v, path)?]))
+    }
+}
+
+fn inputs_from_json<I: InputType>(obj: &Value, path: &str) -> Result<Vec<I::Input>, Error> {
+    get_array(obj, "inputs", path)?
+        .iter()
+        .enumerate()
+        .map(|(i, x)| input_from_json::<I>(x, &format!("{path}.inputs[{i}]")))
+        .collect()
+}
+
+fn gates_from_json<C: Config, I: InputType, const INPUT_NUM: usize>(
+    seg: &Value,
+    key: &str,
+    path: &str,
+) -> Result<Vec<Gate<C, I, INPUT_NUM>>, Error> {
+    let mut res = Vec::new();
+    for (i, g) in get_array(seg, key, path)?.iter().enumerate() {
+        let path = format!("{path}.{key}[{i}]");
+        let inputs: [I::Input; INPUT_NUM] = inputs_from_json::<I>(g, &path)?
+            .try_into()
+            .map_err(|_| json_error(&path, &format!("expected {INPUT_NUM} inputs")))?;
+        res.push(Gate {
+            inputs,
+            output: get_usize(g, "output", &path)?,
+            coef: coef_from_json(get(g, "coef", &path)?, &format!("{path}.coef"))?,
+        });
+    }
+    Ok(res)
+}
+
+fn segment_from_json<C: Config, I: InputType>(
+    v: &Value,
+    path: &str,
+) -> Result<Segment<C, I>, Error> {
+    let mut child_segs: Vec<ChildSpec<I>> = Vec::new();
+    for (i, c) in get_array(v, "child_segs", path)?.iter().enumerate() {
+        let path = format!("{path}.child_segs[{i}]");
+        let mut allocs = Vec::new();
+        for (j, a) in get_array(c, "allocations", &path)?.iter().enumerate() {
+            let path = format!("{path}.allocations[{j}]");
+            allocs.push(Allocation {
+                input_offset: input_usize_from_json::<I>(
+                    get(a, "input_offset", &path)?,
+                    &format!("{path}.input_offset"),
+                )?,
+                output_offset: get_usize(a, "output_offset", &path)?,
+            });
+        }
+        child_segs.push((get_usize(c, "segment", &path)?, allocs));
+    }
+    let mut gate_customs = Vec::new();
+    for (i, g) in get_array(v, "gate_customs", path)?.iter().enumerate() {
+        let path = format!("{path}.gate_customs[{i}]");
+        gate_customs.push(GateCustom {
+            gate_type: get_usize(g, "gate_type", &path)?,
+            inputs: inputs_from_json::<I>(g, &path)?,
+            output: get_usize(g, "output", &path)?,
+            coef: coef_from_json(get(g, "coef", &path)?, &format!("{path}.coef"))?,
+        });
+    }
+    let seg = Segment {
+        num_inputs: input_usize_from_json::<I>(
+            get(v, "num_inputs", path)?,
+            &format!("{path}.num_inputs"),
+        )?,
+        num_outputs: get_usize(v, "num_outputs", path)?,
+        child_segs,
+        gate_muls: gates_from_json(v, "gate_muls", path)?,
+        gate_adds: gates_from_json(v, "gate_adds", path)?,
+        gate_consts: gates_from_json(v, "gate_consts", path)?,
+        gate_customs,
+    };
+    // `Circuit::validate` looks up the input layer sizes before checking the layers
+    let num_layers = seg.num_inputs.len();
+    let max_layer = seg
+        .gate_muls
+        .iter()
+        .flat_map(|g| g.inputs.iter())
+        .chain(seg.gate_adds.iter().flat_map(|g| g.inputs.iter()))
+        .chain(seg.gate_customs.iter().flat_map(|g| g.inputs.iter()))
+        .map(|x| x.layer())
+        .max();
+    if max_layer.is_some_and(|l| l >= num_layers) {
+        return Err(json_error(path, "gate input layer out of range"));
+    }
+    Ok(seg)
+}
+
+impl<C: Config, I: InputType> Circuit<C, I> {
+    pub fn to_json(&self) -> Value {
+        let mut res = Map::new();
+        res.insert(
+            "num_public_inputs".to_string(),
+            json!(self.num_public_inputs),
+        );
+        res.insert(
+            "num_actual_outputs".to_string(),
+            json!(self.num_actual_outputs),
+        );
+        res.insert(
+            "expected_num_output_zeroes".to_string(),
+            json!(self.expected_num_output_zeroes),
+        );
+        res.insert("layer_ids".to_string(), json!(self.layer_ids));
+        res.insert(
+            "segments".to_string(),
+            Value::Array(self.segments.iter().map(segment_to_json).collect()),
+        );
+        Value::Object(res)
+    }
+
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json()).unwrap()
+    }
+
+    /// Builds a circuit from the output of `to_json`, and validates it.
+    pub fn from_json(v: &Value) -> Result<Self, Error> {
+        let path = "$";
+        let mut segments = Vec::new();
+        for (i, seg) in get_array(v, "segments", path)?.iter().enumerate() {
+            segments.push(segment_from_json(seg, &format!("$.segments[{i}]"))?);
+        }
+        let layer_ids = get_array(v, "layer_ids", path)?
+            .iter()
+            .enumerate()
+            .map(|(i, x)| to_usize(x, &format!("$.layer_ids[{i}]")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let circuit = Circuit {
+            num_public_inputs: get_usize(v, "num_public_inputs", path)?,
+            num_actual_outputs: get_usize(v, "num_actual_outputs", path)?,
+            expected_num_output_zeroes: get_usize(v, "expected_num_output_zeroes", path)?,
+            segments,
+            layer_ids,
+        };
+        circuit.validate().map_err(Error::ir_validation_failed)?;
+        Ok(circuit)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, Error> {
+        let v: Value = serde_json::from_str(s)
+            .map_err(|e| Error::DeserializationFailed(format!("layered circuit json: {e}")))?;
+        Self::from_json(&v)
+    }
+}
@@ -9,7 +9,10 @@ use super::config::{CircuitField, Config};
 #[cfg(test)]
 mod tests;
 
+pub mod check;
+pub mod dot;
 pub mod export;
+pub mod json;
 pub mod opt;
 pub mod serde;
 pub mod stats;
@@ -734,6 +737,26 @@ impl<C: Config, I: InputType> Circuit<C, I> {
         inputs: Vec<CircuitField<C>>,
         public_inputs: &[CircuitField<C>],
     ) -> (Vec<CircuitField<C>>, bool) {
+        let cur = self.eval_all_outputs_with_public_inputs(inputs, public_inputs);
+        let mut constraints_satisfied = true;
+        for out in cur.iter().take(self.expected_num_output_zeroes) {
+            if !out.is_zero() {
+                constraints_satisfied = false;
+                break;
+            }
+        }
+        (
+            cur[self.expected_num_output_zeroes..self.num_actual_outputs].to_vec(),
+            constraints_satisfied,
+        )
+    }
+
+    // returns the whole output layer, including the outputs expected to be zero
+    fn eval_all_outputs_with_public_inputs(
+        &self,
+        inputs: Vec<CircuitField<C>>,
+        public_inputs: &[CircuitField<C>],
+    ) -> Vec<CircuitField<C>> {
         if inputs.len() != self.input_size() {
             panic!("input length mismatch");
         }
@@ -752,18 +775,7 @@ impl<C: Config, I: InputType> Circuit<C, I> {
             );
             cur.push(next);
         }
-        let cur = cur.last().unwrap();
-        let mut constraints_satisfied = true;
-        for out in cur.iter().take(self.expected_num_output_zeroes) {
-            if !out.is_zero() {
-                constraints_satisfied = false;
-                break;
-            }
-        }
-        (
-            cur[self.expected_num_output_zeroes..self.num_actual_outputs].to_vec(),
-            constraints_satisfied,
-        )
+        cur.pop().unwrap()
     }
 
     fn apply_segment_with_public_inputs(
@@ -709,18 +709,15 @@ mod tests {
     use mersenne31::M31;
 
     use crate::circuit::layered;
+    use crate::circuit::{
+        ir::{self, common::rand_gen::*},
+        layered::{CrossLayerInputType, NormalInputType},
+    };
     use crate::field::FieldArith;
     use crate::frontend::CircuitField;
     use crate::frontend::M31Config as C;
     use crate::layering::compile;
     use crate::layering::CompileOptions;
-    use crate::{
-        circuit::{
-            ir::{self, common::rand_gen::*},
-            layered::{CrossLayerInputType, NormalInputType},
-        },
-        utils::error::Error,
-    };
 
     use super::InputType;
 
@@ -737,14 +734,12 @@ mod tests {
         assert_eq!(root.validate(), Ok(()));
         match root.validate_circuit_has_inputs() {
             Ok(_) => {}
-            Err(e) => match e {
-                Error::InternalError(s) => {
-                    panic!("{}", s);
-                }
-                Error::UserError(_) => {
-                    return None;
+            Err(e) => {
+                if e.is_internal() {
+                    panic!("{}", e);
                 }
-            },
+                return None;
+            }
         }
         let (lc, _) = compile(
             &root,
@@ -1,4 +1,4 @@
-use crate::circuit::config::Config;
+use crate::circuit::{config::Config, costs::CostModel};
 
 use super::{Circuit, InputType, InputUsize};
 
@@ -33,6 +33,10 @@ struct CircuitStats {
 
 impl<C: Config, I: InputType> Circuit<C, I> {
     pub fn get_stats(&self) -> Stats {
+        self.get_stats_with_cost_model(&CostModel::of::<C>())
+    }
+
+    pub fn get_stats_with_cost_model(&self, cost_model: &CostModel) -> Stats {
         let mut m: Vec<CircuitStats> = Vec::with_capacity(self.segments.len());
         let mut ar = Stats {
             num_layers: 0,
@@ -96,11 +100,11 @@ impl<C: Config, I: InputType> Circuit<C, I> {
                 ar.num_inputs += 1;
             }
         }
-        ar.total_cost = self.input_size() * C::COST_INPUT;
-        ar.total_cost += ar.num_total_gates * C::COST_VARIABLE;
-        ar.total_cost += ar.num_expanded_mul * C::COST_MUL;
-        ar.total_cost += ar.num_expanded_add * C::COST_ADD;
-        ar.total_cost += ar.num_expanded_cst * C::COST_CONST;
+        ar.total_cost = self.input_size() * cost_model.input;
+        ar.total_cost += ar.num_total_gates * cost_model.variable;
+        ar.total_cost += ar.num_expanded_mul * cost_model.mul;
+        ar.total_cost += ar.num_expanded_add * cost_model.add;
+        ar.total_cost += ar.num_expanded_cst * cost_model.constant;
         ar
     }
 }
@@ -0,0 +1,141 @@
+//! On-disk cache of compilation results.
+//!
+//! Entries are keyed by a hash of everything the cached stage reads: the version of the
+//! compiler and of the cache format, the config, the options used by the stage, and the
+//! content of the circuit. So entries written by another version of the compiler are never
+//! reused.
+//!
+//! Hint normalization and the final build are cached per sub circuit. Sub circuits are keyed
+//! by their content and the keys of the sub circuits they call, so a change in one sub circuit
+//! only invalidates it and its callers. Hint normalization also keys them by their id from
+//! `hash_to_sub_circuit_id`, which alone isn't enough since it doesn't cover the body of the
+//! function building the sub circuit. The layer layouts of the sub circuits depend on the
+//! layers their callers need, so the layered circuit is cached as a whole.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serdes::ExpSerde;
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::{
+    circuit::{config::Config, costs::CostModel},
+    utils::error::Error,
+};
+
+pub type CacheKey = [u8; 32];
+
+#[derive(Debug, Clone)]
+pub struct CompileCache {
+    dir: PathBuf,
+}
+
+impl CompileCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(|e| {
+            Error::UserError(format!(
+                "failed to create compile cache directory {}: {e}",
+                dir.display()
+            ))
+        })?;
+        Ok(CompileCache { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, stage: &str, key: &CacheKey) -> PathBuf {
+        let hex: String = key.iter().map(|b| format!("{b:02x}")).collect();
+        self.dir.join(format!("{stage}-{hex}.bin"))
+    }
+
+    /// Returns the cached value, or None if it's missing or can't be deserialized.
+    pub fn get<T: ExpSerde>(&self, stage: &str, key: &CacheKey) -> Option<T> {
+        let bytes = fs::read(self.path(stage, key)).ok()?;
+        let mut reader = bytes.as_slice();
+        let value = T::deserialize_from(&mut reader).ok()?;
+        if !reader.is_empty() {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Stores the value. Failures are ignored, since the cache is only an optimization.
+    pub fn put<T: ExpSerde>(&self, stage: &str, key: &CacheKey, value: &T) {
+        let mut bytes = Vec::new();
+        if value.serialize_into(&mut bytes).is_err() {
+            return;
+        }
+        // write to a temporary file first, so that concurrent compilations never read a
+        // partially written entry
+        static NEXT_TMP_ID: AtomicUsize = AtomicUsize::new(0);
+        let path = self.path(stage, key);
+        let tmp = path.with_extension(format!(
+            "tmp{}-{}",
+            std::process::id(),
+            NEXT_TMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        if fs::write(&tmp, bytes).is_err() || fs::rename(&tmp, &path).is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+    }
+}
+
+pub struct CacheKeyHasher {
+    hasher: Keccak,
+}
+
+// bumped when the layout of the cached entries changes within a version of the compiler
+const CACHE_FORMAT_VERSION: u64 = 1;
+
+impl CacheKeyHasher {
+    pub fn new<C: Config>(stage: &str) -> Self {
+        let mut hasher = CacheKeyHasher {
+            hasher: Keccak::v256(),
+        };
+        hasher.update_str(env!("CARGO_PKG_VERSION"));
+        hasher.update(&CACHE_FORMAT_VERSION.to_le_bytes());
+        hasher.update_str(stage);
+        hasher.update_usize(C::CONFIG_ID);
+        hasher
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+    }
+
+    // length prefixed, so that consecutive strings can't be confused
+    pub fn update_str(&mut self, s: &str) {
+        self.update_usize(s.len());
+        self.hasher.update(s.as_bytes());
+    }
+
+    pub fn update_usize(&mut self, x: usize) {
+        self.hasher.update(&(x as u64).to_le_bytes());
+    }
+
+    pub fn update_cost_model(&mut self, cost_model: &CostModel) {
+        self.update_usize(cost_model.input);
+        self.update_usize(cost_model.variable);
+        self.update_usize(cost_model.mul);
+        self.update_usize(cost_model.add);
+        self.update_usize(cost_model.constant);
+    }
+
+    pub fn update_serde<T: ExpSerde>(&mut self, value: &T) {
+        let mut bytes = Vec::new();
+        value.serialize_into(&mut bytes).unwrap();
+        self.hasher.update(&bytes);
+    }
+
+    pub fn finalize(self) -> CacheKey {
+        let mut res = [0u8; 32];
+        self.hasher.finalize(&mut res);
+        res
+    }
+}
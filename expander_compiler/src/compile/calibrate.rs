@@ -0,0 +1,120 @@
+use std::time::Instant;
+
+use arith::Field;
+use expander_binary::executor;
+use gkr_engine::MPIConfig;
+
+use crate::circuit::{
+    config::{CircuitField, Config, SIMDField},
+    costs::CostModel,
+    layered::{
+        Circuit, Coef, GateAdd, GateConst, GateMul, NormalInput, NormalInputType, NormalInputUsize,
+        Segment,
+    },
+};
+
+// a single layer circuit with n_in inputs and n_out outputs, where output i is input i % n_in
+fn base_circuit<C: Config>(n_in: usize, n_out: usize) -> Circuit<C, NormalInputType> {
+    let one = Coef::Constant(CircuitField::<C>::one());
+    Circuit {
+        num_public_inputs: 0,
+        num_actual_outputs: n_out,
+        expected_num_output_zeroes: 0,
+        segments: vec![Segment {
+            num_inputs: NormalInputUsize { v: n_in },
+            num_outputs: n_out,
+            child_segs: vec![],
+            gate_muls: vec![],
+            gate_adds: (0..n_out)
+                .map(|i| GateAdd {
+                    inputs: [NormalInput { offset: i % n_in }],
+                    output: i,
+                    coef: one,
+                })
+                .collect(),
+            gate_consts: vec![],
+            gate_customs: vec![],
+        }],
+        layer_ids: vec![0],
+    }
+}
+
+// the minimum proving time in seconds over `repeat` runs
+fn prove_time<C: Config>(lc: &Circuit<C, NormalInputType>, repeat: usize) -> f64 {
+    let mut expander_circuit = lc.export_to_expander_flatten();
+    let mpi_config = MPIConfig::prover_new(None, None);
+    let mut rng = rand::thread_rng();
+    expander_circuit.layers[0].input_vals = (0..lc.input_size())
+        .map(|_| SIMDField::<C>::random_unsafe(&mut rng))
+        .collect();
+    expander_circuit.public_input = vec![];
+    expander_circuit.evaluate();
+    let mut best = f64::INFINITY;
+    for _ in 0..repeat.max(1) {
+        let start = Instant::now();
+        executor::prove::<C>(&mut expander_circuit, mpi_config.clone());
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    best
+}
+
+/// Measures the Expander proving time of each kind of gate on this machine, using circuits
+/// with `2^log_size` gates of each kind, and returns a cost model proportional to it.
+/// The costs are scaled so that an add gate costs as much as in the model of the config.
+pub fn calibrate_cost_model<C: Config>(log_size: usize, repeat: usize) -> CostModel {
+    let n = 1 << log_size;
+    let one = Coef::Constant(CircuitField::<C>::one());
+    let base = base_circuit::<C>(n, n);
+    let t_base = prove_time(&base, repeat);
+
+    let mut with_mul = base.clone();
+    with_mul.segments[0].gate_muls = (0..n)
+        .map(|i| GateMul {
+            inputs: [
+                NormalInput { offset: i },
+                NormalInput {
+                    offset: (i + 1) % n,
+                },
+            ],
+            output: i,
+            coef: one,
+        })
+        .collect();
+    let mut with_add = base.clone();
+    with_add.segments[0]
+        .gate_adds
+        .extend((0..n).map(|i| GateAdd {
+            inputs: [NormalInput {
+                offset: (i + 1) % n,
+            }],
+            output: i,
+            coef: one,
+        }));
+    let mut with_const = base.clone();
+    with_const.segments[0].gate_consts = (0..n)
+        .map(|i| GateConst {
+            inputs: [],
+            output: i,
+            coef: one,
+        })
+        .collect();
+
+    let per_gate = |lc: &Circuit<C, NormalInputType>| (prove_time(lc, repeat) - t_base) / n as f64;
+    let mul = per_gate(&with_mul);
+    let add = per_gate(&with_add);
+    let constant = per_gate(&with_const);
+    // n more outputs, each with an add gate
+    let variable = per_gate(&base_circuit::<C>(n, 2 * n)) - add;
+    let input = per_gate(&base_circuit::<C>(2 * n, n));
+
+    let unit = if add > 0.0 { add } else { t_base / n as f64 };
+    let scale = CostModel::of::<C>().add as f64 / unit;
+    let to_cost = |t: f64| ((t * scale).round() as usize).max(1);
+    CostModel {
+        input: to_cost(input),
+        variable: to_cost(variable),
+        mul: to_cost(mul),
+        add: to_cost(add),
+        constant: to_cost(constant),
+    }
+}
@@ -1,15 +1,25 @@
+use std::path::PathBuf;
+
 use crate::{
     builder,
     circuit::{
         config::Config,
+        costs::CostModel,
         input_mapping::InputMapping,
-        ir,
-        layered::{self, InputType},
+        ir::{self, dest::profile::CostProfile},
+        layered::{self, check::ConstraintDebugMap, InputType},
     },
     layering,
     utils::error::Error,
 };
 
+pub mod cache;
+pub mod calibrate;
+pub mod passes;
+
+use cache::{CacheKeyHasher, CompileCache};
+use passes::PassManager;
+
 #[cfg(test)]
 mod random_circuit_tests;
 #[cfg(test)]
@@ -20,6 +30,15 @@ pub struct CompileOptions {
     pub mul_fanout_limit: Option<usize>,
     pub allow_input_reorder: bool,
     pub opt_level: usize,
+    // costs used to choose how a mux is lowered and which expressions the final build
+    // compresses into new variables, which decides the number of layers; the placement of
+    // variables within a layer doesn't depend on it. The ones of the config if not set
+    pub cost_model: Option<CostModel>,
+    // directory of the on-disk cache of compiled sub circuits and layered circuits, if any
+    pub cache_dir: Option<PathBuf>,
+    // if set, sub circuits called with constant inputs are specialized to them, adding at most
+    // this many instructions
+    pub const_specialization_budget: Option<usize>,
 }
 
 impl Default for CompileOptions {
@@ -28,6 +47,9 @@ impl Default for CompileOptions {
             mul_fanout_limit: None,
             allow_input_reorder: true,
             opt_level: 3,
+            cost_model: None,
+            cache_dir: None,
+            const_specialization_budget: None,
         }
     }
 }
@@ -45,6 +67,24 @@ impl CompileOptions {
         self.opt_level = opt_level;
         self
     }
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = Some(cost_model);
+        self
+    }
+    pub fn cost_model<C: Config>(&self) -> CostModel {
+        self.cost_model.unwrap_or_else(CostModel::of::<C>)
+    }
+    pub fn with_cache_dir<P: Into<PathBuf>>(mut self, cache_dir: P) -> Self {
+        self.cache_dir = Some(cache_dir.into());
+        self
+    }
+    pub fn with_const_specialization_budget(mut self, budget: usize) -> Self {
+        self.const_specialization_budget = Some(budget);
+        self
+    }
+    pub fn cache(&self) -> Result<Option<CompileCache>, Error> {
+        self.cache_dir.as_ref().map(CompileCache::new).transpose()
+    }
     pub fn validate(&self) -> Result<(), Error> {
         if self.mul_fanout_limit.is_some() && self.mul_fanout_limit.unwrap() <= 1 {
             return Err(Error::UserError("mul_fanout_limit must be > 1".to_string()));
@@ -96,21 +136,50 @@ fn print_stat(stat_name: &str, stat: usize, is_last: bool) {
 pub fn compile_step_1<C: Config>(
     r_source: &ir::source::RootCircuit<C>,
     options: CompileOptions,
+) -> Result<(ir::hint_normalized::RootCircuit<C>, InputMapping), Error> {
+    compile_step_1_with_passes::<C, layered::NormalInputType>(
+        r_source,
+        options,
+        &PassManager::new(),
+        false,
+    )
+}
+
+// the cache doesn't keep the origins of the constraints, so it isn't used if they're needed
+fn compile_step_1_with_passes<C: Config, I: InputType>(
+    r_source: &ir::source::RootCircuit<C>,
+    options: CompileOptions,
+    passes: &PassManager<C, I>,
+    keep_constraint_origins: bool,
 ) -> Result<(ir::hint_normalized::RootCircuit<C>, InputMapping), Error> {
     r_source.validate()?;
 
     let mut src_im = InputMapping::new_identity(r_source.input_size());
 
-    let mut r_source = r_source.clone();
+    let mut r_source = passes.run_source(None, r_source.clone())?;
+    if let Some(budget) = options.const_specialization_budget {
+        r_source.specialize_constant_calls(budget);
+    }
 
     let r_source_opt = if options.opt_level >= 3 {
+        r_source.fold_constants();
+        r_source.eliminate_common_subexpressions();
         r_source.detect_chains();
         optimize_until_fixed_point(&r_source, &mut src_im, |r| {
             let (mut r, im) = r.remove_unreachable();
             r.reassign_duplicate_sub_circuit_outputs(false);
+            r.fold_constants();
+            r.eliminate_common_subexpressions();
             r.detect_chains();
             (r, im)
         })
+    } else if options.opt_level >= 2 {
+        r_source.fold_constants();
+        r_source.eliminate_common_subexpressions();
+        r_source.detect_chains();
+        let (r, im) = r_source.remove_unreachable();
+        src_im.compose_in_place(&im);
+        r
     } else if options.opt_level >= 1 {
         r_source.detect_chains();
         let (r, im) = r_source.remove_unreachable();
@@ -122,14 +191,33 @@ pub fn compile_step_1<C: Config>(
     r_source_opt
         .validate()
         .map_err(|e| e.prepend("source ir circuit invalid"))?;
+    passes.dump("source", "source_opt", || r_source_opt.to_text())?;
+    let r_source_opt = passes.run_source(Some("source_opt"), r_source_opt)?;
 
-    let r_hint_normalized = builder::hint_normalize::process(&r_source_opt)
-        .map_err(|e| e.prepend("hint normalization failed"))?;
+    let cost_model = options.cost_model::<C>();
+    let cache = if keep_constraint_origins {
+        None
+    } else {
+        options.cache()?
+    };
+    let r_hint_normalized = match cache {
+        Some(cache) => {
+            builder::hint_normalize::process_with_cache(&r_source_opt, &cost_model, &cache)
+        }
+        None => builder::hint_normalize::process_with_cost_model(&r_source_opt, &cost_model),
+    }
+    .map_err(|e| e.prepend("hint normalization failed"))?;
+    passes.dump("hint_normalized", "hint_normalize", || {
+        r_hint_normalized.to_string()
+    })?;
+    let r_hint_normalized = passes.run_hint_normalized("hint_normalize", r_hint_normalized)?;
 
     let r_hint_normalized_opt = if options.opt_level >= 2 {
         optimize_until_fixed_point(&r_hint_normalized, &mut src_im, |r| {
             let (mut r, im) = r.remove_unreachable();
             r.reassign_duplicate_sub_circuit_outputs(false);
+            r.fold_constants();
+            r.eliminate_common_subexpressions();
             (r, im)
         })
     } else if options.opt_level >= 1 {
@@ -142,6 +230,11 @@ pub fn compile_step_1<C: Config>(
     r_hint_normalized_opt
         .validate()
         .map_err(|e| e.prepend("hint normalized ir circuit invalid"))?;
+    passes.dump("hint_normalized", "hint_normalized_opt", || {
+        r_hint_normalized_opt.to_string()
+    })?;
+    let r_hint_normalized_opt =
+        passes.run_hint_normalized("hint_normalized_opt", r_hint_normalized_opt)?;
     Ok((r_hint_normalized_opt, src_im))
 }
 
@@ -149,12 +242,25 @@ pub fn compile_step_2<C: Config, I: InputType>(
     r_hint_less: ir::hint_less::RootCircuit<C>,
     options: CompileOptions,
 ) -> Result<(ir::dest::RootCircuit<C>, InputMapping), Error> {
+    let (r_dest, hl_im, _) =
+        compile_step_2_with_passes::<C, I>(r_hint_less, options, &PassManager::new(), false)?;
+    Ok((r_dest, hl_im))
+}
+
+// also returns the cost profile if `keep_profile` is set
+fn compile_step_2_with_passes<C: Config, I: InputType>(
+    r_hint_less: ir::hint_less::RootCircuit<C>,
+    options: CompileOptions,
+    passes: &PassManager<C, I>,
+    keep_profile: bool,
+) -> Result<(ir::dest::RootCircuit<C>, InputMapping, Option<CostProfile>), Error> {
     let mut hl_im = InputMapping::new_identity(r_hint_less.input_size());
 
     let r_hint_less_opt = if options.opt_level >= 2 {
         optimize_until_fixed_point(&r_hint_less, &mut hl_im, |r| {
             let (mut r, im) = r.remove_unreachable();
             r.reassign_duplicate_sub_circuit_outputs(false);
+            r.fold_constants();
             (r, im)
         })
     } else {
@@ -163,14 +269,25 @@ pub fn compile_step_2<C: Config, I: InputType>(
     r_hint_less_opt
         .validate()
         .map_err(|e| e.prepend("hint less ir circuit invalid"))?;
+    passes.dump("hint_less", "hint_less_opt", || r_hint_less_opt.to_string())?;
+    let r_hint_less_opt = passes.run_hint_less("hint_less_opt", r_hint_less_opt)?;
 
-    let r_dest_relaxed = builder::final_build_opt::process(&r_hint_less_opt)
-        .map_err(|e| e.prepend("final build failed"))?;
+    let cost_model = options.cost_model::<C>();
+    let r_dest_relaxed = match options.cache()? {
+        Some(cache) => {
+            builder::final_build_opt::process_with_cache(&r_hint_less_opt, &cost_model, &cache)
+        }
+        None => builder::final_build_opt::process_with_cost_model(&r_hint_less_opt, &cost_model),
+    }
+    .map_err(|e| e.prepend("final build failed"))?;
+    passes.dump("dest_relaxed", "final_build", || r_dest_relaxed.to_string())?;
+    let r_dest_relaxed = passes.run_dest_relaxed("final_build", r_dest_relaxed)?;
 
     let r_dest_relaxed_opt = if options.opt_level >= 2 {
         optimize_until_fixed_point(&r_dest_relaxed, &mut hl_im, |r| {
             let (mut r, im) = r.remove_unreachable();
             r.reassign_duplicate_sub_circuit_outputs(false);
+            r.fold_constants();
             (r, im)
         })
     } else if options.opt_level >= 1 {
@@ -183,15 +300,21 @@ pub fn compile_step_2<C: Config, I: InputType>(
     r_dest_relaxed_opt
         .validate()
         .map_err(|e| e.prepend("dest relaxed ir circuit invalid"))?;
+    passes.dump("dest_relaxed", "dest_relaxed_opt", || {
+        r_dest_relaxed_opt.to_string()
+    })?;
+    let r_dest_relaxed_opt = passes.run_dest_relaxed("dest_relaxed_opt", r_dest_relaxed_opt)?;
 
     let r_dest_relaxed_opt = if let Some(limit) = options.mul_fanout_limit {
         let r = r_dest_relaxed_opt.solve_mul_fanout_limit(limit);
         r.validate()
             .map_err(|e| e.prepend("dest relaxed ir circuit invalid"))?;
+        passes.dump("dest_relaxed", "mul_fanout_limit", || r.to_string())?;
         r
     } else {
         r_dest_relaxed_opt
     };
+    let r_dest_relaxed_opt = passes.run_dest_relaxed("mul_fanout_limit", r_dest_relaxed_opt)?;
 
     let r_dest_relaxed_p2 = if C::ENABLE_RANDOM_COMBINATION {
         r_dest_relaxed_opt
@@ -202,15 +325,26 @@ pub fn compile_step_2<C: Config, I: InputType>(
         hl_im.compose_in_place(&im);
         r2.validate()
             .map_err(|e| e.prepend("dest relaxed ir circuit invalid"))?;
+        passes.dump("dest_relaxed", "export_constraints", || r2.to_string())?;
         r2
     };
+    let r_dest_relaxed_p2 = passes.run_dest_relaxed("export_constraints", r_dest_relaxed_p2)?;
 
-    let mut r_dest_relaxed_p3 = if I::CROSS_LAYER_RELAY {
+    // sub circuit ids are kept until the dest ir is split into single layers, so the profile
+    // is computed here
+    let profile = if keep_profile {
+        Some(r_dest_relaxed_p2.get_profile(I::CROSS_LAYER_RELAY))
+    } else {
+        None
+    };
+
+    let r_dest_relaxed_p3 = if I::CROSS_LAYER_RELAY {
         r_dest_relaxed_p2
     } else {
         let r = layering::ir_split::split_to_single_layer(&r_dest_relaxed_p2);
         r.validate()
             .map_err(|e| e.prepend("dest relaxed ir circuit invalid"))?;
+        passes.dump("dest_relaxed", "split_to_single_layer", || r.to_string())?;
 
         if options.opt_level >= 2 {
             optimize_until_fixed_point(&r, &mut hl_im, |r| {
@@ -226,12 +360,16 @@ pub fn compile_step_2<C: Config, I: InputType>(
             r
         }
     };
+    let mut r_dest_relaxed_p3 =
+        passes.run_dest_relaxed("split_to_single_layer", r_dest_relaxed_p3)?;
 
     if options.opt_level == 1 {
         r_dest_relaxed_p3.reassign_duplicate_sub_circuit_outputs(true);
     }
 
     let r_dest = r_dest_relaxed_p3.solve_duplicates();
+    passes.dump("dest", "solve_duplicates", || r_dest.to_string())?;
+    let r_dest = passes.run_dest("solve_duplicates", r_dest)?;
 
     let r_dest_opt = if options.opt_level >= 2 {
         optimize_until_fixed_point(&r_dest, &mut hl_im, |r| {
@@ -252,7 +390,9 @@ pub fn compile_step_2<C: Config, I: InputType>(
     r_dest_opt
         .validate_circuit_has_inputs()
         .map_err(|e| e.prepend("dest ir circuit invalid"))?;
-    Ok((r_dest_opt, hl_im))
+    passes.dump("dest", "dest_opt", || r_dest_opt.to_string())?;
+    let r_dest_opt = passes.run_dest("dest_opt", r_dest_opt)?;
+    Ok((r_dest_opt, hl_im, profile))
 }
 
 pub fn compile_step_3<C: Config, I: InputType>(
@@ -260,7 +400,7 @@ pub fn compile_step_3<C: Config, I: InputType>(
     options: CompileOptions,
 ) -> Result<layered::Circuit<C, I>, Error> {
     lc.validate()
-        .map_err(|e| e.prepend("layered circuit invalid"))?;
+        .map_err(|e| Error::ir_validation_failed(e).prepend("layered circuit invalid"))?;
 
     if options.opt_level >= 1 {
         lc.dedup_gates();
@@ -280,7 +420,7 @@ pub fn compile_step_3<C: Config, I: InputType>(
         }
     }
     lc.validate()
-        .map_err(|e| e.prepend("layered circuit invalid1"))?;
+        .map_err(|e| Error::ir_validation_failed(e).prepend("layered circuit invalid1"))?;
     lc.sort_everything(); // for deterministic output
     Ok(lc)
 }
@@ -338,9 +478,139 @@ pub fn compile_with_options<C: Config, I: InputType>(
     r_source: &ir::source::RootCircuit<C>,
     options: CompileOptions,
 ) -> Result<(ir::hint_normalized::RootCircuit<C>, layered::Circuit<C, I>), Error> {
+    compile_with_passes(r_source, options, &PassManager::new())
+}
+
+/// Same as `compile_with_options`, but runs the user passes of `passes`, and dumps the IR
+/// after each pass if requested.
+pub fn compile_with_passes<C: Config, I: InputType>(
+    r_source: &ir::source::RootCircuit<C>,
+    options: CompileOptions,
+    passes: &PassManager<C, I>,
+) -> Result<(ir::hint_normalized::RootCircuit<C>, layered::Circuit<C, I>), Error> {
+    let (r_hint_exported, lc, _, _) =
+        compile_with_options_inner(r_source, options, false, false, passes)?;
+    Ok((r_hint_exported, lc))
+}
+
+/// Same as `compile_with_passes`, but also returns the map used by `layered::Circuit::check_witness`.
+pub fn compile_with_constraint_debug_map<C: Config, I: InputType>(
+    r_source: &ir::source::RootCircuit<C>,
+    options: CompileOptions,
+    passes: &PassManager<C, I>,
+) -> Result<
+    (
+        ir::hint_normalized::RootCircuit<C>,
+        layered::Circuit<C, I>,
+        ConstraintDebugMap<C>,
+    ),
+    Error,
+> {
+    let (r_hint_exported, lc, debug_map, _) =
+        compile_with_options_inner(r_source, options, true, false, passes)?;
+    Ok((r_hint_exported, lc, debug_map.unwrap()))
+}
+
+/// Same as `compile_with_passes`, but also returns the estimated gates of each sub circuit.
+pub fn compile_with_profile<C: Config, I: InputType>(
+    r_source: &ir::source::RootCircuit<C>,
+    options: CompileOptions,
+    passes: &PassManager<C, I>,
+) -> Result<
+    (
+        ir::hint_normalized::RootCircuit<C>,
+        layered::Circuit<C, I>,
+        CostProfile,
+    ),
+    Error,
+> {
+    let (r_hint_exported, lc, _, profile) =
+        compile_with_options_inner(r_source, options, false, true, passes)?;
+    Ok((r_hint_exported, lc, profile.unwrap()))
+}
+
+type CompileOutput<C, I> = (
+    ir::hint_normalized::RootCircuit<C>,
+    layered::Circuit<C, I>,
+    Option<ConstraintDebugMap<C>>,
+    Option<CostProfile>,
+);
+
+const LAYERED_CACHE_STAGE: &str = "layered";
+const LAYERED_INPUT_MAPPING_CACHE_STAGE: &str = "layered_input_mapping";
+
+fn layering_cache_key<C: Config, I: InputType>(
+    r_dest: &ir::dest::RootCircuit<C>,
+    options: &CompileOptions,
+) -> cache::CacheKey {
+    let mut hasher = CacheKeyHasher::new::<C>(LAYERED_CACHE_STAGE);
+    hasher.update_usize(I::CROSS_LAYER_RELAY as usize);
+    hasher.update_usize(options.allow_input_reorder as usize);
+    hasher.update_usize(options.opt_level);
+    hasher.update_usize(r_dest.num_public_inputs);
+    hasher.update_usize(r_dest.expected_num_output_zeroes);
+    let mut ids: Vec<usize> = r_dest.circuits.keys().cloned().collect();
+    ids.sort();
+    for id in ids {
+        hasher.update_usize(id);
+        hasher.update_serde(&r_dest.circuits[&id]);
+    }
+    hasher.finalize()
+}
+
+// layering and step 3, reusing the result of a previous compilation of the same dest ir
+fn compile_layering<C: Config, I: InputType>(
+    r_dest: &ir::dest::RootCircuit<C>,
+    options: &CompileOptions,
+    passes: &PassManager<C, I>,
+) -> Result<(layered::Circuit<C, I>, InputMapping), Error> {
+    // the cached layered circuit doesn't include the changes of user passes before step 3
+    let cache = if passes.has_layered_passes_after_layering() {
+        None
+    } else {
+        options.cache()?
+    };
+    let key = cache
+        .as_ref()
+        .map(|_| layering_cache_key::<C, I>(r_dest, options));
+    if let (Some(cache), Some(key)) = (&cache, &key) {
+        let lc = cache.get::<layered::Circuit<C, I>>(LAYERED_CACHE_STAGE, key);
+        let im = cache.get::<InputMapping>(LAYERED_INPUT_MAPPING_CACHE_STAGE, key);
+        if let (Some(lc), Some(im)) = (lc, im) {
+            if lc.validate().is_ok() {
+                return Ok((lc, im));
+            }
+        }
+    }
+
+    let (lc, dest_im) = layering::compile(
+        r_dest,
+        layering::CompileOptions {
+            allow_input_reorder: options.allow_input_reorder,
+        },
+    );
+    passes.dump("layered", "layering", || lc.to_string())?;
+    let lc = passes.run_layered("layering", lc)?;
+    let lc = compile_step_3(lc, options.clone())?;
+
+    if let (Some(cache), Some(key)) = (&cache, &key) {
+        cache.put(LAYERED_CACHE_STAGE, key, &lc);
+        cache.put(LAYERED_INPUT_MAPPING_CACHE_STAGE, key, &dest_im);
+    }
+    Ok((lc, dest_im))
+}
+
+fn compile_with_options_inner<C: Config, I: InputType>(
+    r_source: &ir::source::RootCircuit<C>,
+    options: CompileOptions,
+    keep_debug_map: bool,
+    keep_profile: bool,
+    passes: &PassManager<C, I>,
+) -> Result<CompileOutput<C, I>, Error> {
     options.validate()?;
 
-    let (r_hint_normalized_opt, mut src_im) = compile_step_1(r_source, options.clone())?;
+    let (r_hint_normalized_opt, mut src_im) =
+        compile_step_1_with_passes(r_source, options.clone(), passes, keep_debug_map)?;
 
     print_ir_stats(&r_hint_normalized_opt);
 
@@ -348,22 +618,28 @@ pub fn compile_with_options<C: Config, I: InputType>(
     r_hint_exported
         .validate()
         .map_err(|e| e.prepend("hint exported circuit invalid"))?;
+    passes.dump("hint_less", "export_hints", || r_hint_less.to_string())?;
+    let r_hint_less = passes.run_hint_less("export_hints", r_hint_less)?;
 
-    let (r_dest_opt, mut hl_im) = compile_step_2::<C, I>(r_hint_less, options.clone())?;
+    let r_hint_less_debug = if keep_debug_map {
+        Some(r_hint_less.clone())
+    } else {
+        None
+    };
 
-    let (lc, dest_im) = layering::compile(
-        &r_dest_opt,
-        layering::CompileOptions {
-            allow_input_reorder: options.allow_input_reorder,
-        },
-    );
+    let (r_dest_opt, mut hl_im, profile) =
+        compile_step_2_with_passes::<C, I>(r_hint_less, options.clone(), passes, keep_profile)?;
 
-    let lc = compile_step_3(lc, options.clone())?;
+    let (lc, dest_im) = compile_layering::<C, I>(&r_dest_opt, &options, passes)?;
+    passes.dump("layered", "layered_opt", || lc.to_string())?;
+    let lc = passes.run_layered("layered_opt", lc)?;
 
     print_layered_circuit_stats(&lc);
 
     hl_im.compose_in_place(&dest_im);
 
+    let debug_map = r_hint_less_debug.map(|r| ConstraintDebugMap::new(r, hl_im.clone()));
+
     let rhe_c0 = r_hint_exported.circuits.get_mut(&0).unwrap();
     rhe_c0.outputs = hl_im
         .map_inputs(&rhe_c0.outputs)
@@ -377,5 +653,5 @@ pub fn compile_with_options<C: Config, I: InputType>(
         .validate()
         .map_err(|e| e.prepend("final hint exported circuit invalid"))?;
 
-    Ok((r_hint_exported_opt, lc))
+    Ok((r_hint_exported_opt, lc, debug_map, profile))
 }
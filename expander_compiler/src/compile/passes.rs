@@ -0,0 +1,496 @@
+//! User passes and IR dumps for the compilation pipeline.
+//!
+//! The built-in passes run in this order, each one on the IR named before the colon:
+//!
+//! ```text
+//! source: source_opt
+//! hint_normalized: hint_normalize, hint_normalized_opt
+//! hint_less: export_hints, hint_less_opt
+//! dest_relaxed: final_build, dest_relaxed_opt, mul_fanout_limit, export_constraints,
+//!               split_to_single_layer
+//! dest: solve_duplicates, dest_opt
+//! layered: layering, layered_opt
+//! ```
+//!
+//! The first built-in pass of an IR builds it from the previous IR, so a user pass of an IR can
+//! run after any of its built-in passes, or before any of them except the first one. Only source
+//! passes can run before `source_opt`, on the circuit given to the compiler. Some built-in passes
+//! are skipped depending on the options and the config, e.g. `mul_fanout_limit` without a limit;
+//! the user passes placed around them still run at the same point. By default, user passes run
+//! after the last built-in pass of their IR. Passes at the same point run in the order they were
+//! added.
+//!
+//! User passes must keep the inputs of the circuit, and their output is validated. With a dump
+//! directory, the output of every pass that runs is written to `NN-ir-pass.txt` in that
+//! directory.
+
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    circuit::{
+        config::Config,
+        ir,
+        layered::{self, InputType, NormalInputType},
+    },
+    utils::error::Error,
+};
+
+type PassFn<T> = Box<dyn Fn(T) -> Result<T, Error> + Send + Sync>;
+
+const BUILTIN_PASSES: [(&str, &[&str]); 6] = [
+    ("source", &["source_opt"]),
+    (
+        "hint_normalized",
+        &["hint_normalize", "hint_normalized_opt"],
+    ),
+    ("hint_less", &["export_hints", "hint_less_opt"]),
+    (
+        "dest_relaxed",
+        &[
+            "final_build",
+            "dest_relaxed_opt",
+            "mul_fanout_limit",
+            "export_constraints",
+            "split_to_single_layer",
+        ],
+    ),
+    ("dest", &["solve_duplicates", "dest_opt"]),
+    ("layered", &["layering", "layered_opt"]),
+];
+
+fn builtin_passes(ir: &str) -> &'static [&'static str] {
+    BUILTIN_PASSES.iter().find(|(x, _)| *x == ir).unwrap().1
+}
+
+/// Where a user pass runs, relative to the built-in passes of its IR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassPosition<'a> {
+    Before(&'a str),
+    After(&'a str),
+    /// After the last built-in pass of the IR.
+    End,
+}
+
+struct UserPass<T> {
+    name: String,
+    // the number of built-in passes of the IR that run before it
+    point: usize,
+    f: PassFn<T>,
+}
+
+pub struct PassManager<C: Config, I: InputType = NormalInputType> {
+    source: Vec<UserPass<ir::source::RootCircuit<C>>>,
+    hint_normalized: Vec<UserPass<ir::hint_normalized::RootCircuit<C>>>,
+    hint_less: Vec<UserPass<ir::hint_less::RootCircuit<C>>>,
+    dest_relaxed: Vec<UserPass<ir::dest::RootCircuit<C>>>,
+    dest: Vec<UserPass<ir::dest::RootCircuit<C>>>,
+    layered: Vec<UserPass<layered::Circuit<C, I>>>,
+    dump_dir: Option<PathBuf>,
+    num_dumps: AtomicUsize,
+}
+
+impl<C: Config, I: InputType> Default for PassManager<C, I> {
+    fn default() -> Self {
+        PassManager {
+            source: Vec::new(),
+            hint_normalized: Vec::new(),
+            hint_less: Vec::new(),
+            dest_relaxed: Vec::new(),
+            dest: Vec::new(),
+            layered: Vec::new(),
+            dump_dir: None,
+            num_dumps: AtomicUsize::new(0),
+        }
+    }
+}
+
+fn insert_pass<T, F>(
+    passes: &mut Vec<UserPass<T>>,
+    ir: &str,
+    position: PassPosition,
+    name: &str,
+    f: F,
+) -> Result<(), Error>
+where
+    F: Fn(T) -> Result<T, Error> + Send + Sync + 'static,
+{
+    let builtins = builtin_passes(ir);
+    let find = |anchor: &str| {
+        builtins.iter().position(|x| *x == anchor).ok_or_else(|| {
+            Error::UserError(format!(
+                "unknown built-in pass {anchor} of the {ir} ir, expected one of {builtins:?}"
+            ))
+        })
+    };
+    let point = match position {
+        PassPosition::Before(anchor) => find(anchor)?,
+        PassPosition::After(anchor) => find(anchor)? + 1,
+        PassPosition::End => builtins.len(),
+    };
+    if point == 0 && ir != "source" {
+        return Err(Error::UserError(format!(
+            "pass {name} can't run before {}, which builds the {ir} ir",
+            builtins[0]
+        )));
+    }
+    passes.push(UserPass {
+        name: name.to_string(),
+        point,
+        f: Box::new(f),
+    });
+    Ok(())
+}
+
+impl<C: Config, I: InputType> PassManager<C, I> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dump_dir<P: Into<PathBuf>>(mut self, dump_dir: P) -> Self {
+        self.dump_dir = Some(dump_dir.into());
+        self
+    }
+
+    pub fn add_source_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(ir::source::RootCircuit<C>) -> Result<ir::source::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_source_pass(PassPosition::End, name, f).unwrap()
+    }
+
+    pub fn insert_source_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(ir::source::RootCircuit<C>) -> Result<ir::source::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(&mut self.source, "source", position, name, f)?;
+        Ok(self)
+    }
+
+    pub fn add_hint_normalized_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(
+                ir::hint_normalized::RootCircuit<C>,
+            ) -> Result<ir::hint_normalized::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_hint_normalized_pass(PassPosition::End, name, f)
+            .unwrap()
+    }
+
+    pub fn insert_hint_normalized_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(
+                ir::hint_normalized::RootCircuit<C>,
+            ) -> Result<ir::hint_normalized::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(
+            &mut self.hint_normalized,
+            "hint_normalized",
+            position,
+            name,
+            f,
+        )?;
+        Ok(self)
+    }
+
+    pub fn add_hint_less_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(ir::hint_less::RootCircuit<C>) -> Result<ir::hint_less::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_hint_less_pass(PassPosition::End, name, f)
+            .unwrap()
+    }
+
+    pub fn insert_hint_less_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(ir::hint_less::RootCircuit<C>) -> Result<ir::hint_less::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(&mut self.hint_less, "hint_less", position, name, f)?;
+        Ok(self)
+    }
+
+    /// Adds a pass on the dest ir before its duplicate sub circuit outputs are solved. After
+    /// `split_to_single_layer`, which runs without cross layer relay, every sub circuit is a
+    /// single layer, and the pass must keep it that way.
+    pub fn add_dest_relaxed_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(ir::dest::RootCircuit<C>) -> Result<ir::dest::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_dest_relaxed_pass(PassPosition::End, name, f)
+            .unwrap()
+    }
+
+    pub fn insert_dest_relaxed_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(ir::dest::RootCircuit<C>) -> Result<ir::dest::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(&mut self.dest_relaxed, "dest_relaxed", position, name, f)?;
+        Ok(self)
+    }
+
+    pub fn add_dest_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(ir::dest::RootCircuit<C>) -> Result<ir::dest::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_dest_pass(PassPosition::End, name, f).unwrap()
+    }
+
+    pub fn insert_dest_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(ir::dest::RootCircuit<C>) -> Result<ir::dest::RootCircuit<C>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(&mut self.dest, "dest", position, name, f)?;
+        Ok(self)
+    }
+
+    pub fn add_layered_pass<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: Fn(layered::Circuit<C, I>) -> Result<layered::Circuit<C, I>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.insert_layered_pass(PassPosition::End, name, f)
+            .unwrap()
+    }
+
+    pub fn insert_layered_pass<F>(
+        &mut self,
+        position: PassPosition,
+        name: &str,
+        f: F,
+    ) -> Result<&mut Self, Error>
+    where
+        F: Fn(layered::Circuit<C, I>) -> Result<layered::Circuit<C, I>, Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        insert_pass(&mut self.layered, "layered", position, name, f)?;
+        Ok(self)
+    }
+
+    /// Whether some layered passes run between `layering` and `layered_opt`.
+    pub(crate) fn has_layered_passes_after_layering(&self) -> bool {
+        self.layered.iter().any(|p| p.point == 1)
+    }
+
+    /// Writes the output of a pass to the dump directory, if any.
+    pub(crate) fn dump<F: FnOnce() -> String>(
+        &self,
+        ir: &str,
+        pass: &str,
+        content: F,
+    ) -> Result<(), Error> {
+        let dir = match &self.dump_dir {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let id = self.num_dumps.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("{id:02}-{ir}-{pass}.txt"));
+        fs::create_dir_all(dir)
+            .and_then(|_| fs::write(&path, content()))
+            .map_err(|e| Error::UserError(format!("failed to dump ir to {}: {e}", path.display())))
+    }
+
+    // runs the user passes placed right after the built-in pass `after`, or before all the
+    // built-in passes if it's `None`
+    #[allow(clippy::too_many_arguments)]
+    fn run<T, S, V, D>(
+        &self,
+        passes: &[UserPass<T>],
+        mut x: T,
+        ir: &str,
+        after: Option<&str>,
+        input_size: S,
+        validate: V,
+        dump: D,
+    ) -> Result<T, Error>
+    where
+        S: Fn(&T) -> usize,
+        V: Fn(&T) -> Result<(), Error>,
+        D: Fn(&T) -> String,
+    {
+        let point = match after {
+            Some(after) => builtin_passes(ir).iter().position(|p| *p == after).unwrap() + 1,
+            None => 0,
+        };
+        for pass in passes.iter().filter(|p| p.point == point) {
+            let n = input_size(&x);
+            x = (pass.f)(x).map_err(|e| e.prepend(&format!("pass {} failed", pass.name)))?;
+            if input_size(&x) != n {
+                return Err(Error::UserError(format!(
+                    "pass {} changed the number of inputs from {} to {}",
+                    pass.name,
+                    n,
+                    input_size(&x)
+                )));
+            }
+            validate(&x)
+                .map_err(|e| e.prepend(&format!("{ir} invalid after pass {}", pass.name)))?;
+            self.dump(ir, &pass.name, || dump(&x))?;
+        }
+        Ok(x)
+    }
+
+    pub(crate) fn run_source(
+        &self,
+        after: Option<&str>,
+        r: ir::source::RootCircuit<C>,
+    ) -> Result<ir::source::RootCircuit<C>, Error> {
+        self.run(
+            &self.source,
+            r,
+            "source",
+            after,
+            |r| r.input_size(),
+            |r| r.validate(),
+            |r| r.to_text(),
+        )
+    }
+
+    pub(crate) fn run_hint_normalized(
+        &self,
+        after: &str,
+        r: ir::hint_normalized::RootCircuit<C>,
+    ) -> Result<ir::hint_normalized::RootCircuit<C>, Error> {
+        self.run(
+            &self.hint_normalized,
+            r,
+            "hint_normalized",
+            Some(after),
+            |r| r.input_size(),
+            |r| r.validate(),
+            |r| r.to_string(),
+        )
+    }
+
+    pub(crate) fn run_hint_less(
+        &self,
+        after: &str,
+        r: ir::hint_less::RootCircuit<C>,
+    ) -> Result<ir::hint_less::RootCircuit<C>, Error> {
+        self.run(
+            &self.hint_less,
+            r,
+            "hint_less",
+            Some(after),
+            |r| r.input_size(),
+            |r| r.validate(),
+            |r| r.to_string(),
+        )
+    }
+
+    pub(crate) fn run_dest_relaxed(
+        &self,
+        after: &str,
+        r: ir::dest::RootCircuit<C>,
+    ) -> Result<ir::dest::RootCircuit<C>, Error> {
+        self.run(
+            &self.dest_relaxed,
+            r,
+            "dest_relaxed",
+            Some(after),
+            |r| r.input_size(),
+            |r| r.validate(),
+            |r| r.to_string(),
+        )
+    }
+
+    pub(crate) fn run_dest(
+        &self,
+        after: &str,
+        r: ir::dest::RootCircuit<C>,
+    ) -> Result<ir::dest::RootCircuit<C>, Error> {
+        self.run(
+            &self.dest,
+            r,
+            "dest",
+            Some(after),
+            |r| r.input_size(),
+            // unreachable sub circuits without inputs are only removed by dest_opt
+            |r| {
+                r.validate()?;
+                if after == "dest_opt" {
+                    r.validate_circuit_has_inputs()?;
+                }
+                Ok(())
+            },
+            |r| r.to_string(),
+        )
+    }
+
+    pub(crate) fn run_layered(
+        &self,
+        after: &str,
+        lc: layered::Circuit<C, I>,
+    ) -> Result<layered::Circuit<C, I>, Error> {
+        self.run(
+            &self.layered,
+            lc,
+            "layered",
+            Some(after),
+            |lc| lc.input_size(),
+            |lc| lc.validate().map_err(Error::ir_validation_failed),
+            |lc| lc.to_string(),
+        )
+    }
+}
@@ -10,7 +10,6 @@ use crate::{
     compile::{compile, compile_with_options},
     field::FieldArith,
     frontend::BabyBearConfig,
-    utils::error::Error,
 };
 
 use super::CompileOptions;
@@ -49,21 +48,19 @@ fn do_test_with_options<C: Config, I: InputType>(
                             assert_eq!(src_cond, lc_cond);
                             assert_eq!(src_output, lc_output);
                         }
-                        Err(e) => match e {
-                            Error::UserError(_) => {}
-                            Error::InternalError(e) => {
+                        Err(e) => {
+                            if e.is_internal() {
                                 panic!("{:?}", e);
                             }
-                        },
+                        }
                     }
                 }
             }
-            Err(e) => match e {
-                Error::UserError(_) => {}
-                Error::InternalError(e) => {
+            Err(e) => {
+                if e.is_internal() {
                     panic!("{:?}", e);
                 }
-            },
+            }
         }
     }
 }
@@ -16,10 +16,10 @@ fn simple_div() {
                 y: 2,
                 checked: true,
             }],
-            constraints: vec![ir::source::Constraint {
-                typ: ir::source::ConstraintType::Zero,
-                var: 3,
-            }],
+            constraints: vec![ir::source::Constraint::new(
+                3,
+                ir::source::ConstraintType::Zero,
+            )],
             outputs: vec![3],
             num_inputs: 2,
         },
@@ -36,3 +36,28 @@ fn simple_div() {
     assert_eq!(o[0], CField::from(10 as u32));
     assert!(!cond);
 }
+
+#[test]
+fn invalid_ir_error_kind() {
+    let mut root = ir::source::RootCircuit::<C>::default();
+    root.circuits.insert(
+        0,
+        ir::source::Circuit {
+            instructions: vec![ir::source::Instruction::SubCircuitCall {
+                sub_circuit_id: 5,
+                inputs: vec![1],
+                num_outputs: 1,
+            }],
+            constraints: vec![],
+            outputs: vec![2],
+            num_inputs: 1,
+        },
+    );
+    let err = super::compile::<_, NormalInputType>(&root).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        crate::utils::error::ErrorKind::IrValidationFailed
+    );
+    assert!(err.is_internal());
+    assert_eq!(err.to_string(), "sub circuit 5 not found");
+}
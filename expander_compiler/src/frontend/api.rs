@@ -1,9 +1,11 @@
 use arith::Field;
+use ethnum::U256;
 
-use crate::circuit::config::Config;
+use crate::{circuit::config::Config, utils::error::Error};
 
 use super::{
     builder::{ToVariableOrValue, Variable},
+    lookup::Table,
     CircuitField,
 };
 
@@ -43,24 +45,96 @@ pub trait BasicAPI<C: Config> {
         num_bits: usize,
     ) -> Vec<Variable>;
     /// return 1 if x > y; 0 otherwise
-    /// this is a very expensive operation -- it uses bit decompositions for comparison
-    /// consider use lookup table for better performance
+    /// this is a very expensive operation -- it uses bit decompositions for comparison,
+    /// unless the builder knows a bound on both values (see `lt_bounded`)
     fn gt(
         &mut self,
         x: impl ToVariableOrValue<CircuitField<C>>,
         y: impl ToVariableOrValue<CircuitField<C>>,
     ) -> Variable;
     /// return 1 if x >= y; 0 otherwise
-    /// this is a very expensive operation -- it uses bit decompositions for comparison
-    /// consider use lookup table for better performance
+    /// this is a very expensive operation -- it uses bit decompositions for comparison,
+    /// unless the builder knows a bound on both values (see `lt_bounded`)
     fn geq(
         &mut self,
         x: impl ToVariableOrValue<CircuitField<C>>,
         y: impl ToVariableOrValue<CircuitField<C>>,
     ) -> Variable;
+    /// Returns 1 if x < y, 0 otherwise, where both x and y must be below `2^bits`.
+    /// Only `bits + 1` bits are decomposed (by a range check), instead of the whole field.
+    /// The bound is asserted for the values the builder doesn't already know to be below
+    /// `2^bits`, so the constraints can't be satisfied if it doesn't hold.
+    ///
+    /// `gt` and `geq` use this automatically for values with a bound known to the builder,
+    /// i.e. the inputs and outputs of `to_binary`, and values checked by `assert_in_range`
+    /// or `assert_is_bool`.
+    fn lt_bounded(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+        bits: usize,
+    ) -> Variable;
+    /// Returns `values[i]`, where the little-endian bits of `i` are `selectors`, which must be boolean.
+    /// `values.len()` must be `2^selectors.len()`.
+    /// The compiler picks the cheapest decomposition into additions and multiplications for the field.
+    fn mux(&mut self, selectors: &[Variable], values: &[Variable]) -> Variable;
+    /// Returns `if_true` if `cond` is 1, and `if_false` if it's 0. `cond` must be boolean.
+    #[track_caller]
+    fn select(
+        &mut self,
+        cond: impl ToVariableOrValue<CircuitField<C>>,
+        if_true: impl ToVariableOrValue<CircuitField<C>>,
+        if_false: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> Variable {
+        let cond = self.constant(cond);
+        let if_true = self.constant(if_true);
+        let if_false = self.constant(if_false);
+        self.mux(&[cond], &[if_false, if_true])
+    }
+    /// Returns `i0`, `i1`, `i2` or `i3` for `(b0, b1)` being `(0, 0)`, `(1, 0)`, `(0, 1)` or `(1, 1)`.
+    #[track_caller]
+    #[allow(clippy::too_many_arguments)]
+    fn lookup2(
+        &mut self,
+        b0: impl ToVariableOrValue<CircuitField<C>>,
+        b1: impl ToVariableOrValue<CircuitField<C>>,
+        i0: impl ToVariableOrValue<CircuitField<C>>,
+        i1: impl ToVariableOrValue<CircuitField<C>>,
+        i2: impl ToVariableOrValue<CircuitField<C>>,
+        i3: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> Variable {
+        let selectors = [self.constant(b0), self.constant(b1)];
+        let values = [
+            self.constant(i0),
+            self.constant(i1),
+            self.constant(i2),
+            self.constant(i3),
+        ];
+        self.mux(&selectors, &values)
+    }
+    /// Returns `(b, a)` if `cond` is 1, and `(a, b)` if it's 0. `cond` must be boolean.
+    #[track_caller]
+    fn cond_swap(
+        &mut self,
+        cond: impl ToVariableOrValue<CircuitField<C>>,
+        a: impl ToVariableOrValue<CircuitField<C>>,
+        b: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> (Variable, Variable) {
+        let a = self.constant(a);
+        let b = self.constant(b);
+        let first = self.select(cond, b, a);
+        // the second output is linear in the first one
+        let sum = self.add(a, b);
+        let second = self.sub(sum, first);
+        (first, second)
+    }
+    #[track_caller]
     fn assert_is_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>);
+    #[track_caller]
     fn assert_is_non_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>);
+    #[track_caller]
     fn assert_is_bool(&mut self, x: impl ToVariableOrValue<CircuitField<C>>);
+    #[track_caller]
     fn assert_is_equal(
         &mut self,
         x: impl ToVariableOrValue<CircuitField<C>>,
@@ -69,6 +143,7 @@ pub trait BasicAPI<C: Config> {
         let diff = self.sub(x, y);
         self.assert_is_zero(diff);
     }
+    #[track_caller]
     fn assert_is_different(
         &mut self,
         x: impl ToVariableOrValue<CircuitField<C>>,
@@ -77,6 +152,10 @@ pub trait BasicAPI<C: Config> {
         let diff = self.sub(x, y);
         self.assert_is_non_zero(diff);
     }
+    /// Asserts that `x`, as an integer, is below `2^bits`.
+    /// The builder collects these checks and turns them into a single lookup argument when the
+    /// circuit is built, except for GF2, where `x` is decomposed into bits instead.
+    fn assert_in_range(&mut self, x: impl ToVariableOrValue<CircuitField<C>>, bits: usize);
     fn get_random_value(&mut self) -> Variable;
     fn new_hint(
         &mut self,
@@ -84,6 +163,50 @@ pub trait BasicAPI<C: Config> {
         inputs: &[Variable],
         num_outputs: usize,
     ) -> Vec<Variable>;
+    /// Calls a hint whose number of outputs is only known at witness time, at most `max_outputs`.
+    /// Returns the actual number of outputs and `max_outputs` variables, the unused ones being zero.
+    /// The hint must be registered with `HintRegistry::register_dynamic`.
+    ///
+    /// The length is constrained to be at most `max_outputs`, and the unused outputs to be zero.
+    /// This needs a field characteristic above `max_outputs`, otherwise an error is returned.
+    /// Like for any hint, the used outputs themselves are unconstrained.
+    #[track_caller]
+    fn new_dynamic_hint(
+        &mut self,
+        hint_key: &str,
+        inputs: &[Variable],
+        max_outputs: usize,
+    ) -> Result<(Variable, Vec<Variable>), Error>
+    where
+        Self: UnconstrainedAPI<C>,
+    {
+        if CircuitField::<C>::MODULUS <= U256::from(max_outputs as u64) {
+            return Err(Error::UserError(format!(
+                "dynamic hint {hint_key} needs a field characteristic above {max_outputs}"
+            )));
+        }
+        let mut outputs = self.new_hint(hint_key, inputs, max_outputs + 1);
+        let len = outputs.remove(0);
+        // used[i] = 1 iff i < len. The flags are boolean and non-increasing, so their sum, len,
+        // is at most max_outputs and they are exactly the first len ones. Every constraint has
+        // degree 2, so the depth doesn't grow with max_outputs.
+        let mut sum = self.constant(0);
+        let mut prev: Option<Variable> = None;
+        for (i, output) in outputs.iter().enumerate() {
+            let used = self.unconstrained_lesser(i as u32, len);
+            self.assert_is_bool(used);
+            if let Some(prev) = prev {
+                let both = self.mul(used, prev);
+                self.assert_is_equal(used, both);
+            }
+            let used_output = self.mul(*output, used);
+            self.assert_is_equal(*output, used_output);
+            sum = self.add(sum, used);
+            prev = Some(used);
+        }
+        self.assert_is_equal(sum, len);
+        Ok((len, outputs))
+    }
     fn constant(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) -> Variable;
     // try to get the value of a compile-time constant variable
     // this function has different behavior in normal and debug mode, in debug mode it always returns Some(value)
@@ -139,6 +262,9 @@ pub trait RootAPI<C: Config>: Sized + BasicAPI<C> + UnconstrainedAPI<C> + 'stati
     ) -> Vec<Variable>;
     fn hash_to_sub_circuit_id(&mut self, hash: &[u8; 32]) -> usize;
     // This function should only be called in proc macro generated code
+    // The name is only used for reporting, e.g. in the cost profile
+    fn set_sub_circuit_name(&mut self, _circuit_id: usize, _name: &str) {}
+    // This function should only be called in proc macro generated code
     fn call_sub_circuit<F: FnOnce(&mut Self, &Vec<Variable>) -> Vec<Variable>>(
         &mut self,
         circuit_id: usize,
@@ -150,4 +276,18 @@ pub trait RootAPI<C: Config>: Sized + BasicAPI<C> + UnconstrainedAPI<C> + 'stati
     // This function should only be called in proc macro generated code
     fn get_sub_circuit_output_structure(&self, circuit_id: usize) -> Vec<usize>;
     fn set_outputs(&mut self, outputs: Vec<Variable>);
+    /// Creates a lookup table whose `i`-th row maps `keys[i]` to `values[i]`.
+    /// All rows must have the same number of values, which may be zero for a plain set.
+    /// Tables must be created in the root circuit, and can be used from sub circuits.
+    fn new_table(&mut self, keys: &[Variable], values: &[Vec<Variable>]) -> Table;
+    /// Returns the values of the row of `table` with key `key`. If several rows have the key,
+    /// the first one is used.
+    /// Lookups are collected, also from sub circuits, and checked by a single lookup argument
+    /// per table when the circuit is built. The multiplicities needed by the argument are
+    /// computed by the witness solver.
+    fn lookup(
+        &mut self,
+        table: Table,
+        key: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> Vec<Variable>;
 }
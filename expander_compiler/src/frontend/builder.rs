@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::convert::From;
+use std::panic::Location;
 
 use ethnum::U256;
 use tiny_keccak::Hasher;
@@ -14,13 +15,15 @@ use crate::{
         layered::Coef,
     },
     field::{Field, FieldArith},
-    hints::{self, registry::hint_key_to_id},
+    hints::{self, info::HintInfos, registry::hint_key_to_id},
     utils::function_id::get_function_id,
 };
 
 use super::{
     api::{BasicAPI, RootAPI, UnconstrainedAPI},
-    CircuitField,
+    logup,
+    lookup::{self, Table, TableData},
+    range_check, CircuitField,
 };
 
 #[derive(Clone)]
@@ -30,6 +33,12 @@ pub struct Builder<C: Config> {
     var_const_id: Vec<usize>,
     const_values: Vec<CircuitField<C>>,
     num_inputs: usize,
+    // pending assert_in_range checks, resolved by the RootBuilder
+    range_queries: Vec<(Variable, usize)>,
+    // pending table lookups as (table id, key followed by the values), resolved by the RootBuilder
+    table_queries: Vec<(usize, Vec<Variable>)>,
+    // known bounds of variables: x < 2^bits
+    bit_bounds: HashMap<usize, usize>,
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -70,6 +79,18 @@ pub fn ensure_variables_valid(vs: &[Variable]) {
     }
 }
 
+/// Hash identifying the sub circuit of `memorized_simple_call(f, inputs)`, shared by all builders
+/// so that they agree on sub circuit ids.
+pub fn simple_call_hash<F: 'static>(num_inputs: usize) -> [u8; 32] {
+    let mut hasher = tiny_keccak::Keccak::v256();
+    hasher.update(b"simple");
+    hasher.update(&num_inputs.to_le_bytes());
+    hasher.update(&get_function_id::<F>().to_le_bytes());
+    let mut hash = [0u8; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
 pub enum VariableOrValue<F: Field> {
     Variable(Variable),
     Value(F),
@@ -116,6 +137,9 @@ impl<C: Config> Builder<C> {
                 num_inputs,
                 var_const_id: vec![0; num_inputs + 1],
                 const_values: vec![CircuitField::<C>::zero()],
+                range_queries: Vec::new(),
+                table_queries: Vec::new(),
+                bit_bounds: HashMap::new(),
             },
             (1..=num_inputs).map(|id| Variable { id }).collect(),
         )
@@ -151,6 +175,41 @@ impl<C: Config> Builder<C> {
             id: self.var_const_id.len() - 1,
         }
     }
+
+    fn builtin_hint(
+        &mut self,
+        hint_id: hints::BuiltinHintIds,
+        inputs: &[Variable],
+        num_outputs: usize,
+    ) -> Vec<Variable> {
+        self.instructions.push(SourceInstruction::Hint {
+            hint_id: hint_id as u64 as usize,
+            inputs: inputs.iter().map(|v| v.id).collect(),
+            num_outputs,
+        });
+        (0..num_outputs).map(|_| self.new_var()).collect()
+    }
+
+    fn range_count(&mut self, keys: &[Variable], table_size: usize) -> Vec<Variable> {
+        self.builtin_hint(hints::BuiltinHintIds::RangeCount, keys, table_size)
+    }
+
+    fn table_count(&mut self, table_keys: &[Variable], queries: &[Variable]) -> Vec<Variable> {
+        let inputs = [table_keys, queries].concat();
+        self.builtin_hint(hints::BuiltinHintIds::TableCount, &inputs, table_keys.len())
+    }
+
+    fn record_bits(&mut self, x: Variable, bits: usize) {
+        let b = self.bit_bounds.entry(x.id).or_insert(bits);
+        *b = (*b).min(bits);
+    }
+
+    fn known_bits(&mut self, x: Variable) -> Option<usize> {
+        match self.constant_value(x) {
+            Some(xv) => Some(hints::u256_bit_length(xv.to_u256())),
+            None => self.bit_bounds.get(&x.id).copied(),
+        }
+    }
 }
 
 impl<C: Config> BasicAPI<C> for Builder<C> {
@@ -379,9 +438,17 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
         let x = self.convert_to_variable(x);
         self.instructions
             .push(SourceInstruction::ToBinary { x: x.id, num_bits });
-        (0..num_bits).map(|_| self.new_var()).collect()
+        if num_bits <= range_check::max_range_check_bits::<C>() {
+            self.record_bits(x, num_bits);
+        }
+        let bits: Vec<Variable> = (0..num_bits).map(|_| self.new_var()).collect();
+        for bit in bits.iter() {
+            self.record_bits(*bit, 1);
+        }
+        bits
     }
 
+    #[track_caller]
     fn assert_is_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let xc = self.constant_value(x.clone());
         if let Some(xv) = xc {
@@ -390,12 +457,13 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
             }
         }
         let x = self.convert_to_variable(x);
-        self.constraints.push(SourceConstraint {
-            typ: source::ConstraintType::Zero,
-            var: x.id,
-        });
+        self.constraints.push(
+            SourceConstraint::new(x.id, source::ConstraintType::Zero)
+                .with_origin(Some(Location::caller())),
+        );
     }
 
+    #[track_caller]
     fn assert_is_non_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let xc = self.constant_value(x.clone());
         if let Some(xv) = xc {
@@ -404,12 +472,13 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
             }
         }
         let x = self.convert_to_variable(x);
-        self.constraints.push(SourceConstraint {
-            typ: source::ConstraintType::NonZero,
-            var: x.id,
-        });
+        self.constraints.push(
+            SourceConstraint::new(x.id, source::ConstraintType::NonZero)
+                .with_origin(Some(Location::caller())),
+        );
     }
 
+    #[track_caller]
     fn assert_is_bool(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let xc = self.constant_value(x.clone());
         if let Some(xv) = xc {
@@ -418,10 +487,72 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
             }
         }
         let x = self.convert_to_variable(x);
-        self.constraints.push(SourceConstraint {
-            typ: source::ConstraintType::Bool,
-            var: x.id,
-        });
+        self.constraints.push(
+            SourceConstraint::new(x.id, source::ConstraintType::Bool)
+                .with_origin(Some(Location::caller())),
+        );
+        self.record_bits(x, 1);
+    }
+
+    fn mux(&mut self, selectors: &[Variable], values: &[Variable]) -> Variable {
+        ensure_variables_valid(selectors);
+        ensure_variables_valid(values);
+        if selectors.len() > source::MAX_MUX_SELECTORS {
+            panic!(
+                "mux supports at most {} selectors",
+                source::MAX_MUX_SELECTORS
+            );
+        }
+        if values.len() != 1 << selectors.len() {
+            panic!(
+                "mux with {} selectors requires {} values, got {}",
+                selectors.len(),
+                1usize << selectors.len(),
+                values.len()
+            );
+        }
+        let mut index = 0;
+        for (i, s) in selectors.iter().enumerate() {
+            match self.constant_value(s) {
+                Some(v) if v.is_zero() => {}
+                Some(v) if v == CircuitField::<C>::one() => index |= 1 << i,
+                Some(_) => panic!("mux selector is not boolean"),
+                None => {
+                    self.instructions.push(SourceInstruction::Mux {
+                        selectors: selectors.iter().map(|v| v.id).collect(),
+                        values: values.iter().map(|v| v.id).collect(),
+                    });
+                    return self.new_var();
+                }
+            }
+        }
+        values[index]
+    }
+
+    fn assert_in_range(&mut self, x: impl ToVariableOrValue<CircuitField<C>>, bits: usize) {
+        if bits == 0 {
+            self.assert_is_zero(x);
+            return;
+        }
+        let xc = self.constant_value(x.clone());
+        if let Some(xv) = xc {
+            if !range_check::in_range::<C>(xv, bits) {
+                panic!("assert_in_range failed");
+            }
+            return;
+        }
+        let x = self.convert_to_variable(x);
+        if !C::ENABLE_RANDOM_COMBINATION {
+            // the lookup argument needs a large field, fall back to bit decomposition
+            self.to_binary(x, bits);
+            return;
+        }
+        let max_bits = range_check::max_range_check_bits::<C>();
+        if bits > max_bits {
+            panic!("assert_in_range supports at most {max_bits} bits, got {bits}");
+        }
+        self.record_bits(x, bits);
+        self.range_queries.push((x, bits));
     }
 
     fn get_random_value(&mut self) -> Variable {
@@ -473,11 +604,18 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
         x: impl ToVariableOrValue<CircuitField<C>>,
         y: impl ToVariableOrValue<CircuitField<C>>,
     ) -> Variable {
-        let one = self.constant(CircuitField::<C>::one());
-
         let x = self.convert_to_variable(x);
         let y = self.convert_to_variable(y);
 
+        if let (Some(x_bits), Some(y_bits)) = (self.known_bits(x), self.known_bits(y)) {
+            let bits = x_bits.max(y_bits);
+            if bits < range_check::max_range_check_bits::<C>() {
+                return self.lt_bounded(y, x, bits);
+            }
+        }
+
+        let one = self.constant(CircuitField::<C>::one());
+
         // Decompose both numbers to bits
         let num_bits = match CircuitField::<C>::FIELD_SIZE {
             256 => 254, // BN254's field size was set to 256
@@ -529,6 +667,53 @@ impl<C: Config> BasicAPI<C> for Builder<C> {
         let one = self.constant(CircuitField::<C>::one());
         self.sub(one, y_gt_x)
     }
+
+    fn lt_bounded(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+        bits: usize,
+    ) -> Variable {
+        let xc = self.constant_value(x.clone());
+        let yc = self.constant_value(y.clone());
+        if let (Some(xv), Some(yv)) = (xc, yc) {
+            return self.constant((xv.to_u256() < yv.to_u256()) as u32);
+        }
+        if bits == 0 {
+            // both are zero
+            return self.constant(0);
+        }
+        let max_bits = range_check::max_range_check_bits::<C>();
+        if bits >= max_bits {
+            panic!(
+                "lt_bounded supports at most {} bits, got {bits}",
+                max_bits - 1
+            );
+        }
+        // the comparison is only sound for values below 2^bits, so check the ones not known to be
+        let x = self.convert_to_variable(x);
+        let y = self.convert_to_variable(y);
+        for v in [x, y] {
+            if !self.known_bits(v).is_some_and(|b| b <= bits) {
+                self.assert_in_range(v, bits);
+            }
+        }
+        // d = y - x - 1 + 2^bits is in [0, 2^(bits+1)), and its top bit is set iff x < y
+        let pow = CircuitField::<C>::from_u256(U256::ONE << bits as u32);
+        let t = self.sub(y, x);
+        let d = self.add(t, pow - CircuitField::<C>::one());
+        if !C::ENABLE_RANDOM_COMBINATION {
+            return self.to_binary(d, bits + 1)[bits];
+        }
+        let top = self.unconstrained_int_div(d, pow);
+        let low = self.unconstrained_mod(d, pow);
+        self.assert_is_bool(top);
+        self.assert_in_range(low, bits);
+        let t = self.mul(top, pow);
+        let sum = self.add(low, t);
+        self.assert_is_equal(sum, d);
+        top
+    }
 }
 
 // write macro rules for unconstrained binary op definition
@@ -608,7 +793,21 @@ pub struct RootBuilder<C: Config> {
     sub_circuits: HashMap<usize, source::Circuit<C>>,
     sub_circuit_output_structure: HashMap<usize, Vec<usize>>,
     full_hash_id: HashMap<usize, [u8; 32]>,
+    sub_circuit_names: HashMap<usize, String>,
     outputs: Vec<Variable>,
+    hint_infos: HintInfos,
+    // checks exported as extra outputs of each sub circuit
+    sub_circuit_exports: HashMap<usize, SubCircuitExports>,
+    tables: Vec<TableData>,
+}
+
+// The range checks and table lookups of a sub circuit, which are resolved by the root circuit.
+// Their variables are appended to the outputs of the sub circuit, range checks first.
+#[derive(Clone, Default)]
+struct SubCircuitExports {
+    range_bits: Vec<usize>,
+    // (table id, length of the query row)
+    table_queries: Vec<(usize, usize)>,
 }
 
 macro_rules! root_binary_op {
@@ -667,16 +866,34 @@ impl<C: Config> BasicAPI<C> for RootBuilder<C> {
         self.last_builder().assert_is_bool(x)
     }
 
+    fn mux(&mut self, selectors: &[Variable], values: &[Variable]) -> Variable {
+        self.last_builder().mux(selectors, values)
+    }
+
+    fn assert_in_range(&mut self, x: impl ToVariableOrValue<CircuitField<C>>, bits: usize) {
+        self.last_builder().assert_in_range(x, bits)
+    }
+
     fn get_random_value(&mut self) -> Variable {
         self.last_builder().get_random_value()
     }
 
+    #[track_caller]
     fn new_hint(
         &mut self,
         hint_key: &str,
         inputs: &[Variable],
         num_outputs: usize,
     ) -> Vec<Variable> {
+        let (circuit_id, builder) = self.current_builders.last().unwrap();
+        let instruction_index = builder.instructions.len();
+        self.hint_infos.record(
+            hint_key_to_id(hint_key),
+            hint_key,
+            *circuit_id,
+            instruction_index,
+            Location::caller(),
+        );
         self.last_builder().new_hint(hint_key, inputs, num_outputs)
     }
 
@@ -706,6 +923,15 @@ impl<C: Config> BasicAPI<C> for RootBuilder<C> {
     ) -> Variable {
         self.last_builder().geq(x, y)
     }
+
+    fn lt_bounded(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+        bits: usize,
+    ) -> Variable {
+        self.last_builder().lt_bounded(x, y, bits)
+    }
 }
 
 impl<C: Config> RootAPI<C> for RootBuilder<C> {
@@ -715,14 +941,8 @@ impl<C: Config> RootAPI<C> for RootBuilder<C> {
         inputs: &[Variable],
     ) -> Vec<Variable> {
         ensure_variables_valid(inputs);
-        let mut hasher = tiny_keccak::Keccak::v256();
-        hasher.update(b"simple");
-        hasher.update(&inputs.len().to_le_bytes());
-        hasher.update(&get_function_id::<F>().to_le_bytes());
-        let mut hash = [0u8; 32];
-        hasher.finalize(&mut hash);
-
-        let circuit_id = self.hash_to_sub_circuit_id(&hash);
+        let circuit_id = self.hash_to_sub_circuit_id(&simple_call_hash::<F>(inputs.len()));
+        self.set_sub_circuit_name(circuit_id, std::any::type_name::<F>());
 
         self.call_sub_circuit(circuit_id, inputs, f)
     }
@@ -739,6 +959,12 @@ impl<C: Config> RootAPI<C> for RootBuilder<C> {
         circuit_id
     }
 
+    fn set_sub_circuit_name(&mut self, circuit_id: usize, name: &str) {
+        self.sub_circuit_names
+            .entry(circuit_id)
+            .or_insert_with(|| name.to_string());
+    }
+
     fn call_sub_circuit<F: FnOnce(&mut Self, &Vec<Variable>) -> Vec<Variable>>(
         &mut self,
         circuit_id: usize,
@@ -749,7 +975,7 @@ impl<C: Config> RootAPI<C> for RootBuilder<C> {
             self.actually_call_sub_circuit(circuit_id, inputs.len(), f);
         }
         let sub = self.sub_circuits.get(&circuit_id).unwrap();
-        let outputs: Vec<Variable> = (0..sub.outputs.len())
+        let mut outputs: Vec<Variable> = (0..sub.outputs.len())
             .map(|_| self.last_builder().new_var())
             .collect();
         self.last_builder()
@@ -759,6 +985,26 @@ impl<C: Config> RootAPI<C> for RootBuilder<C> {
                 inputs: inputs.iter().map(|v| v.id).collect(),
                 num_outputs: outputs.len(),
             });
+        // range checks and table lookups in the sub circuit are passed to the caller
+        if let Some(exports) = self.sub_circuit_exports.get(&circuit_id).cloned() {
+            let num_exported = exports.range_bits.len()
+                + exports
+                    .table_queries
+                    .iter()
+                    .map(|(_, len)| *len)
+                    .sum::<usize>();
+            let n = outputs.len() - num_exported;
+            let mut exported = outputs.split_off(n).into_iter();
+            let builder = self.last_builder();
+            for bits in exports.range_bits {
+                let v = exported.next().unwrap();
+                builder.range_queries.push((v, bits));
+            }
+            for (table_id, len) in exports.table_queries {
+                let row = exported.by_ref().take(len).collect();
+                builder.table_queries.push((table_id, row));
+            }
+        }
         outputs
     }
 
@@ -783,6 +1029,82 @@ impl<C: Config> RootAPI<C> for RootBuilder<C> {
         ensure_variables_valid(&outputs);
         self.outputs = outputs;
     }
+
+    fn new_table(&mut self, keys: &[Variable], values: &[Vec<Variable>]) -> Table {
+        ensure_variables_valid(keys);
+        if self.current_builders.len() != 1 {
+            panic!("tables must be created in the root circuit");
+        }
+        if keys.is_empty() {
+            panic!("table must have at least one row");
+        }
+        if keys.len() != values.len() {
+            panic!(
+                "table has {} keys but {} rows of values",
+                keys.len(),
+                values.len()
+            );
+        }
+        let num_values = values[0].len();
+        if values.iter().any(|row| row.len() != num_values) {
+            panic!("all rows of a table must have the same number of values");
+        }
+        let rows: Vec<Vec<Variable>> = keys
+            .iter()
+            .zip(values.iter())
+            .map(|(k, row)| {
+                ensure_variables_valid(row);
+                std::iter::once(*k).chain(row.iter().copied()).collect()
+            })
+            .collect();
+        let table_id = self.tables.len();
+        // the witness solver indexes the table once, lookups only get the table id and the key
+        let handle = if num_values == 0 {
+            None
+        } else {
+            let builder = self.last_builder();
+            let header = [
+                builder.convert_to_variable(table_id as u32),
+                builder.convert_to_variable(num_values as u32 + 1),
+            ];
+            let inputs = [&header[..], &rows.concat()].concat();
+            Some(builder.builtin_hint(hints::BuiltinHintIds::TableDefine, &inputs, 1)[0])
+        };
+        self.tables.push(TableData { rows, handle });
+        Table::new(table_id, num_values)
+    }
+
+    fn lookup(
+        &mut self,
+        table: Table,
+        key: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> Vec<Variable> {
+        let key = self.constant(key);
+        let table_id = table.id();
+        let in_root = self.current_builders.len() == 1;
+        let handle = self.tables[table_id].handle;
+        let builder = self.last_builder();
+        let values = match handle {
+            None => vec![],
+            Some(handle) => {
+                // the root uses the output of the definition, so that it's solved first;
+                // sub circuits are called after the table is created, and use its id
+                let handle = if in_root {
+                    handle
+                } else {
+                    builder.convert_to_variable(table_id as u32)
+                };
+                builder.builtin_hint(
+                    hints::BuiltinHintIds::TableLookup,
+                    &[handle, key],
+                    table.num_values(),
+                )
+            }
+        };
+        let query = std::iter::once(key).chain(values.iter().copied()).collect();
+        builder.table_queries.push((table_id, query));
+        values
+    }
 }
 
 impl<C: Config> RootBuilder<C> {
@@ -803,8 +1125,12 @@ impl<C: Config> RootBuilder<C> {
                 current_builders: vec![(0, builder0)],
                 sub_circuits: HashMap::new(),
                 full_hash_id: HashMap::new(),
+                sub_circuit_names: HashMap::new(),
                 sub_circuit_output_structure: HashMap::new(),
                 outputs: Vec::new(),
+                hint_infos: HintInfos::new(),
+                sub_circuit_exports: HashMap::new(),
+                tables: Vec::new(),
             },
             inputs,
             public_inputs,
@@ -812,22 +1138,72 @@ impl<C: Config> RootBuilder<C> {
     }
 
     pub fn build(self) -> source::RootCircuit<C> {
+        self.build_with_hint_infos().0
+    }
+
+    /// Names of the memorized sub circuits, by circuit id.
+    pub fn sub_circuit_names(&self) -> &HashMap<usize, String> {
+        &self.sub_circuit_names
+    }
+
+    pub fn build_with_hint_infos(mut self) -> (source::RootCircuit<C>, HintInfos) {
+        self.resolve_table_lookups();
+        self.resolve_range_checks();
         let mut circuits = self.sub_circuits;
         assert_eq!(self.current_builders.len(), 1);
         for (circuit_id, builder) in self.current_builders {
             circuits.insert(circuit_id, builder.build(&self.outputs));
         }
-        source::RootCircuit {
-            circuits,
-            num_public_inputs: self.num_public_inputs,
-            expected_num_output_zeroes: 0,
-        }
+        self.hint_infos
+            .set_sub_circuit_names(&self.sub_circuit_names);
+        (
+            source::RootCircuit {
+                circuits,
+                num_public_inputs: self.num_public_inputs,
+                expected_num_output_zeroes: 0,
+            },
+            self.hint_infos,
+        )
     }
 
     pub fn last_builder(&mut self) -> &mut Builder<C> {
         &mut self.current_builders.last_mut().unwrap().1
     }
 
+    fn resolve_range_checks(&mut self) {
+        let queries = std::mem::take(&mut self.last_builder().range_queries);
+        if queries.is_empty() {
+            return;
+        }
+        let table_bits = range_check::table_bits::<C>(&queries);
+        let keys = range_check::lookup_keys(self, &queries, table_bits);
+        let counts = self.last_builder().range_count(&keys, 1 << table_bits);
+        let table: Vec<Variable> = (0..counts.len()).map(|i| self.constant(i as u32)).collect();
+        logup::logup_check(self, &keys, &table, &counts);
+    }
+
+    fn resolve_table_lookups(&mut self) {
+        let queries = std::mem::take(&mut self.last_builder().table_queries);
+        let mut queries_by_table = vec![Vec::new(); self.tables.len()];
+        for (table_id, query) in queries {
+            queries_by_table[table_id].push(query);
+        }
+        for (table_id, queries) in queries_by_table.into_iter().enumerate() {
+            if queries.is_empty() {
+                continue;
+            }
+            let rows = self.tables[table_id].rows.clone();
+            let table_keys: Vec<Variable> = rows.iter().map(|row| row[0]).collect();
+            let query_keys: Vec<Variable> = queries.iter().map(|q| q[0]).collect();
+            let counts = self.last_builder().table_count(&table_keys, &query_keys);
+            lookup::table_check(self, &rows, &queries, &counts);
+            // the definition of the table is only used by the lookups in the root otherwise
+            if let Some(handle) = self.tables[table_id].handle {
+                self.assert_is_equal(handle, table_id as u32);
+            }
+        }
+    }
+
     fn actually_call_sub_circuit<F: FnOnce(&mut Self, &Vec<Variable>) -> Vec<Variable>>(
         &mut self,
         circuit_id: usize,
@@ -836,8 +1212,28 @@ impl<C: Config> RootBuilder<C> {
     ) {
         let (sub_builder, sub_inputs) = Builder::new(n);
         self.current_builders.push((circuit_id, sub_builder));
-        let sub_outputs = f(self, &sub_inputs);
-        let (_, sub_builder) = self.current_builders.pop().unwrap();
+        let mut sub_outputs = f(self, &sub_inputs);
+        let (_, mut sub_builder) = self.current_builders.pop().unwrap();
+        let range_queries = std::mem::take(&mut sub_builder.range_queries);
+        let table_queries = std::mem::take(&mut sub_builder.table_queries);
+        if !range_queries.is_empty() || !table_queries.is_empty() {
+            sub_outputs.extend(range_queries.iter().map(|(v, _)| *v));
+            sub_outputs.extend(
+                table_queries
+                    .iter()
+                    .flat_map(|(_, row)| row.iter().copied()),
+            );
+            self.sub_circuit_exports.insert(
+                circuit_id,
+                SubCircuitExports {
+                    range_bits: range_queries.iter().map(|(_, bits)| *bits).collect(),
+                    table_queries: table_queries
+                        .iter()
+                        .map(|(table_id, row)| (*table_id, row.len()))
+                        .collect(),
+                },
+            );
+        }
         let sub = sub_builder.build(&sub_outputs);
         self.sub_circuits.insert(circuit_id, sub);
     }
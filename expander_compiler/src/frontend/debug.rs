@@ -1,11 +1,16 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, panic::Location};
+
+use ethnum::U256;
 
 use crate::{
     circuit::{
         config::Config,
         ir::{
             common::{EvalResult, Instruction},
-            source::{BoolBinOpType, Instruction as IrInstruction, UnconstrainedBinOpType},
+            source::{
+                BoolBinOpType, Instruction as IrInstruction, UnconstrainedBinOpType,
+                MAX_MUX_SELECTORS,
+            },
         },
     },
     field::FieldArith,
@@ -13,14 +18,17 @@ use crate::{
         self,
         registry::{hint_key_to_id, HintCaller},
     },
+    utils::error::Error,
 };
 
 use super::{
     api::{BasicAPI, RootAPI, UnconstrainedAPI},
     builder::{
-        ensure_variables_valid, get_variable_id, new_variable, ToVariableOrValue, VariableOrValue,
+        ensure_variables_valid, get_variable_id, new_variable, simple_call_hash, ToVariableOrValue,
+        VariableOrValue,
     },
-    CircuitField, Variable,
+    lookup::Table,
+    range_check, CircuitField, Variable,
 };
 
 pub struct DebugBuilder<C: Config, H: HintCaller<CircuitField<C>>> {
@@ -29,6 +37,88 @@ pub struct DebugBuilder<C: Config, H: HintCaller<CircuitField<C>>> {
     full_hash_id: HashMap<usize, [u8; 32]>,
     outputs: Vec<Variable>,
     hint_caller: H,
+    collect_failures: bool,
+    failures: Vec<ConstraintFailure<C>>,
+    sub_circuit_stack: Vec<usize>,
+    // values of the tables by key, keeping the first row of each key
+    tables: Vec<HashMap<U256, Vec<CircuitField<C>>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssertionKind {
+    IsZero,
+    IsNonZero,
+    IsBool,
+    IsEqual,
+    IsDifferent,
+    InRange {
+        bits: usize,
+    },
+    /// The key of a lookup isn't in the table.
+    Lookup {
+        table: usize,
+    },
+}
+
+/// A failed assertion recorded by `DebugBuilder` when failures are collected instead of panicking.
+#[derive(Debug, Clone)]
+pub struct ConstraintFailure<C: Config> {
+    pub kind: AssertionKind,
+    /// The values of the asserted variables, two for `IsEqual` and `IsDifferent`, one otherwise.
+    pub values: Vec<CircuitField<C>>,
+    pub location: &'static Location<'static>,
+    /// Ids of the memorized sub-circuits enclosing the assertion, outermost first.
+    pub sub_circuit_stack: Vec<usize>,
+}
+
+impl<C: Config> fmt::Display for ConstraintFailure<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "assert {:?} failed at {} with values {:?}",
+            self.kind, self.location, self.values
+        )?;
+        if !self.sub_circuit_stack.is_empty() {
+            let stack = self
+                .sub_circuit_stack
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            write!(f, " in sub-circuit path {stack}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of `debug_eval_report`, with the circuit outputs and every failed assertion.
+#[derive(Debug, Clone)]
+pub struct ConstraintReport<C: Config> {
+    pub outputs: Vec<CircuitField<C>>,
+    pub failures: Vec<ConstraintFailure<C>>,
+}
+
+impl<C: Config> ConstraintReport<C> {
+    pub fn is_satisfied(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn to_result(&self) -> Result<(), Error> {
+        if self.is_satisfied() {
+            return Ok(());
+        }
+        Err(Error::ConstraintUnsatisfied(self.to_string()))
+    }
+}
+
+impl<C: Config> fmt::Display for ConstraintReport<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} assertion(s) failed", self.failures.len())?;
+        for failure in self.failures.iter() {
+            write!(f, "\n  {failure}")?;
+        }
+        Ok(())
+    }
 }
 
 impl<C: Config, H: HintCaller<CircuitField<C>>> BasicAPI<C> for DebugBuilder<C, H> {
@@ -133,22 +223,88 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> BasicAPI<C> for DebugBuilder<C,
             .map(|v| self.return_as_variable(v))
             .collect()
     }
+    #[track_caller]
+    fn mux(&mut self, selectors: &[Variable], values: &[Variable]) -> Variable {
+        ensure_variables_valid(selectors);
+        ensure_variables_valid(values);
+        if selectors.len() > MAX_MUX_SELECTORS {
+            panic!("mux supports at most {} selectors", MAX_MUX_SELECTORS);
+        }
+        if values.len() != 1 << selectors.len() {
+            panic!(
+                "mux with {} selectors requires {} values, got {}",
+                selectors.len(),
+                1usize << selectors.len(),
+                values.len()
+            );
+        }
+        let mut index = 0;
+        for (i, s) in selectors.iter().enumerate() {
+            let s = self.convert_to_value(s);
+            self.check_assertion(
+                s.is_zero() || s == CircuitField::<C>::one(),
+                AssertionKind::IsBool,
+                vec![s],
+            );
+            if !s.is_zero() {
+                index |= 1 << i;
+            }
+        }
+        values[index]
+    }
+    #[track_caller]
     fn assert_is_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let x = self.convert_to_value(x);
-        assert!(x.is_zero());
+        self.check_assertion(x.is_zero(), AssertionKind::IsZero, vec![x]);
     }
+    #[track_caller]
     fn assert_is_non_zero(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let x = self.convert_to_value(x);
-        assert!(!x.is_zero());
+        self.check_assertion(!x.is_zero(), AssertionKind::IsNonZero, vec![x]);
     }
+    #[track_caller]
     fn assert_is_bool(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) {
         let x = self.convert_to_value(x);
-        assert!(x.is_zero() || x == CircuitField::<C>::one());
+        self.check_assertion(
+            x.is_zero() || x == CircuitField::<C>::one(),
+            AssertionKind::IsBool,
+            vec![x],
+        );
+    }
+    #[track_caller]
+    fn assert_is_equal(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+    ) {
+        let x = self.convert_to_value(x);
+        let y = self.convert_to_value(y);
+        self.check_assertion(x == y, AssertionKind::IsEqual, vec![x, y]);
+    }
+    #[track_caller]
+    fn assert_is_different(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+    ) {
+        let x = self.convert_to_value(x);
+        let y = self.convert_to_value(y);
+        self.check_assertion(x != y, AssertionKind::IsDifferent, vec![x, y]);
+    }
+    #[track_caller]
+    fn assert_in_range(&mut self, x: impl ToVariableOrValue<CircuitField<C>>, bits: usize) {
+        let x = self.convert_to_value(x);
+        self.check_assertion(
+            range_check::in_range::<C>(x, bits),
+            AssertionKind::InRange { bits },
+            vec![x],
+        );
     }
     fn get_random_value(&mut self) -> Variable {
         let v = CircuitField::<C>::random_unsafe(&mut rand::thread_rng());
         self.return_as_variable(v)
     }
+    #[track_caller]
     fn new_hint(
         &mut self,
         hint_key: &str,
@@ -158,15 +314,17 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> BasicAPI<C> for DebugBuilder<C,
         ensure_variables_valid(inputs);
         let inputs: Vec<CircuitField<C>> =
             inputs.iter().map(|v| self.convert_to_value(v)).collect();
-        match self
-            .hint_caller
-            .call(hint_key_to_id(hint_key), &inputs, num_outputs)
-        {
+        match hints::safe_impl(
+            &self.hint_caller,
+            hint_key_to_id(hint_key),
+            &inputs,
+            num_outputs,
+        ) {
             Ok(outputs) => outputs
                 .into_iter()
                 .map(|v| self.return_as_variable(v))
                 .collect(),
-            Err(e) => panic!("Hint error: {e:?}"),
+            Err(e) => panic!("hint `{hint_key}` failed: {e}"),
         }
     }
     fn constant(&mut self, x: impl ToVariableOrValue<CircuitField<C>>) -> Variable {
@@ -201,6 +359,25 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> BasicAPI<C> for DebugBuilder<C,
         let y = self.convert_to_value(y);
         self.return_as_variable(CircuitField::<C>::from((x >= y) as u32))
     }
+
+    #[track_caller]
+    fn lt_bounded(
+        &mut self,
+        x: impl ToVariableOrValue<CircuitField<C>>,
+        y: impl ToVariableOrValue<CircuitField<C>>,
+        bits: usize,
+    ) -> Variable {
+        let x = self.convert_to_value(x);
+        let y = self.convert_to_value(y);
+        for v in [x, y] {
+            self.check_assertion(
+                range_check::in_range::<C>(v, bits),
+                AssertionKind::InRange { bits },
+                vec![v],
+            );
+        }
+        self.return_as_variable(CircuitField::<C>::from((x < y) as u32))
+    }
 }
 
 impl<C: Config, H: HintCaller<CircuitField<C>>> UnconstrainedAPI<C> for DebugBuilder<C, H> {
@@ -451,8 +628,9 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> RootAPI<C> for DebugBuilder<C, H
         inputs: &[Variable],
     ) -> Vec<Variable> {
         ensure_variables_valid(inputs);
-        let inputs = inputs.to_vec();
-        f(self, &inputs)
+        // same id as RootBuilder, so that the reported stack matches the compiled circuit
+        let circuit_id = self.hash_to_sub_circuit_id(&simple_call_hash::<F>(inputs.len()));
+        self.call_sub_circuit(circuit_id, inputs, f)
     }
 
     fn hash_to_sub_circuit_id(&mut self, hash: &[u8; 32]) -> usize {
@@ -469,12 +647,15 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> RootAPI<C> for DebugBuilder<C, H
 
     fn call_sub_circuit<F: FnOnce(&mut Self, &Vec<Variable>) -> Vec<Variable>>(
         &mut self,
-        _: usize,
+        circuit_id: usize,
         inputs: &[Variable],
         f: F,
     ) -> Vec<Variable> {
         let inputs = inputs.to_vec();
-        f(self, &inputs)
+        self.sub_circuit_stack.push(circuit_id);
+        let outputs = f(self, &inputs);
+        self.sub_circuit_stack.pop();
+        outputs
     }
 
     fn register_sub_circuit_output_structure(&mut self, circuit_id: usize, structure: Vec<usize>) {
@@ -498,6 +679,56 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> RootAPI<C> for DebugBuilder<C, H
         ensure_variables_valid(&outputs);
         self.outputs = outputs;
     }
+
+    fn new_table(&mut self, keys: &[Variable], values: &[Vec<Variable>]) -> Table {
+        ensure_variables_valid(keys);
+        if keys.is_empty() {
+            panic!("table must have at least one row");
+        }
+        if keys.len() != values.len() {
+            panic!(
+                "table has {} keys but {} rows of values",
+                keys.len(),
+                values.len()
+            );
+        }
+        let num_values = values[0].len();
+        if values.iter().any(|row| row.len() != num_values) {
+            panic!("all rows of a table must have the same number of values");
+        }
+        let mut rows = HashMap::new();
+        for (k, row) in keys.iter().zip(values.iter()) {
+            ensure_variables_valid(row);
+            let key = self.convert_to_value(k).to_u256();
+            let row: Vec<_> = row.iter().map(|v| self.convert_to_value(v)).collect();
+            rows.entry(key).or_insert(row);
+        }
+        self.tables.push(rows);
+        Table::new(self.tables.len() - 1, num_values)
+    }
+
+    #[track_caller]
+    fn lookup(
+        &mut self,
+        table: Table,
+        key: impl ToVariableOrValue<CircuitField<C>>,
+    ) -> Vec<Variable> {
+        let key = self.convert_to_value(key);
+        let row = self.tables[table.id()].get(&key.to_u256()).cloned();
+        self.check_assertion(
+            row.is_some(),
+            AssertionKind::Lookup { table: table.id() },
+            vec![key],
+        );
+        let values = match row {
+            Some(row) => row,
+            None => vec![CircuitField::<C>::zero(); table.num_values()],
+        };
+        values
+            .into_iter()
+            .map(|v| self.return_as_variable(v))
+            .collect()
+    }
 }
 
 impl<C: Config, H: HintCaller<CircuitField<C>>> DebugBuilder<C, H> {
@@ -512,6 +743,10 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> DebugBuilder<C, H> {
             sub_circuit_output_structure: HashMap::new(),
             full_hash_id: HashMap::new(),
             outputs: vec![],
+            collect_failures: false,
+            failures: vec![],
+            sub_circuit_stack: vec![],
+            tables: vec![],
         };
         let vars = (1..=inputs.len()).map(new_variable).collect();
         let public_vars = (inputs.len() + 1..=inputs.len() + public_inputs.len())
@@ -522,6 +757,32 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> DebugBuilder<C, H> {
         (builder, vars, public_vars)
     }
 
+    /// Records failed assertions instead of panicking on the first one.
+    pub fn set_collect_failures(&mut self, collect_failures: bool) {
+        self.collect_failures = collect_failures;
+    }
+
+    pub fn take_failures(&mut self) -> Vec<ConstraintFailure<C>> {
+        std::mem::take(&mut self.failures)
+    }
+
+    #[track_caller]
+    fn check_assertion(&mut self, ok: bool, kind: AssertionKind, values: Vec<CircuitField<C>>) {
+        if ok {
+            return;
+        }
+        let failure = ConstraintFailure {
+            kind,
+            values,
+            location: Location::caller(),
+            sub_circuit_stack: self.sub_circuit_stack.clone(),
+        };
+        if !self.collect_failures {
+            panic!("{failure}");
+        }
+        self.failures.push(failure);
+    }
+
     fn convert_to_value<T: ToVariableOrValue<CircuitField<C>>>(&self, value: T) -> CircuitField<C> {
         match value.convert_to_variable_or_value() {
             VariableOrValue::Variable(v) => self.values[get_variable_id(v)],
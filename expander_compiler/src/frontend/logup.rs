@@ -0,0 +1,67 @@
+use crate::circuit::config::Config;
+
+use super::{api::BasicAPI, builder::Variable};
+
+#[derive(Clone, Copy)]
+struct Rational {
+    numerator: Variable,
+    denominator: Variable,
+}
+
+fn add_rational<C: Config, B: BasicAPI<C>>(builder: &mut B, a: Rational, b: Rational) -> Rational {
+    let p1 = builder.mul(a.numerator, b.denominator);
+    let p2 = builder.mul(a.denominator, b.numerator);
+    Rational {
+        numerator: builder.add(p1, p2),
+        denominator: builder.mul(a.denominator, b.denominator),
+    }
+}
+
+// pairwise sum, so that the depth is logarithmic
+fn sum_rationals<C: Config, B: BasicAPI<C>>(builder: &mut B, mut vs: Vec<Rational>) -> Rational {
+    while vs.len() > 1 {
+        let mut next = Vec::with_capacity(vs.len().div_ceil(2));
+        for pair in vs.chunks(2) {
+            next.push(if pair.len() == 2 {
+                add_rational(builder, pair[0], pair[1])
+            } else {
+                pair[0]
+            });
+        }
+        vs = next;
+    }
+    vs[0]
+}
+
+/// Asserts that every query is in the table, where `counts[i]` is the number of queries equal
+/// to `table[i]`, using the logarithmic derivative identity
+/// `sum_q 1 / (alpha - q) = sum_i counts[i] / (alpha - table[i])` for a random `alpha`.
+pub fn logup_check<C: Config, B: BasicAPI<C>>(
+    builder: &mut B,
+    queries: &[Variable],
+    table: &[Variable],
+    counts: &[Variable],
+) {
+    let alpha = builder.get_random_value();
+    let one = builder.constant(1);
+    let query_terms = queries
+        .iter()
+        .map(|q| Rational {
+            numerator: one,
+            denominator: builder.sub(alpha, q),
+        })
+        .collect();
+    let query_sum = sum_rationals(builder, query_terms);
+    let table_terms = table
+        .iter()
+        .zip(counts.iter())
+        .map(|(t, c)| Rational {
+            numerator: *c,
+            denominator: builder.sub(alpha, t),
+        })
+        .collect();
+    let table_sum = sum_rationals(builder, table_terms);
+    let p1 = builder.mul(query_sum.numerator, table_sum.denominator);
+    let p2 = builder.mul(query_sum.denominator, table_sum.numerator);
+    builder.assert_is_equal(p1, p2);
+}
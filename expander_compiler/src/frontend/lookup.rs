@@ -0,0 +1,69 @@
+use crate::circuit::config::Config;
+
+use super::{api::BasicAPI, builder::Variable, logup::logup_check};
+
+/// Handle of a lookup table created by `RootAPI::new_table`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Table {
+    id: usize,
+    num_values: usize,
+}
+
+impl Table {
+    pub fn new(id: usize, num_values: usize) -> Self {
+        Table { id, num_values }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Number of values in each row, besides the key.
+    pub fn num_values(&self) -> usize {
+        self.num_values
+    }
+}
+
+/// The rows of a table, each being the key followed by the values.
+#[derive(Clone)]
+pub struct TableData {
+    pub rows: Vec<Vec<Variable>>,
+    /// Output of the hint defining the table for the witness solver, if the table has values.
+    pub handle: Option<Variable>,
+}
+
+// key + sum_j coefs[j] * values[j]
+fn combine_row<C: Config, B: BasicAPI<C>>(
+    builder: &mut B,
+    row: &[Variable],
+    coefs: &[Variable],
+) -> Variable {
+    let mut res = row[0];
+    for (v, c) in row[1..].iter().zip(coefs.iter()) {
+        let t = builder.mul(v, c);
+        res = builder.add(res, t);
+    }
+    res
+}
+
+/// Asserts that every query row (a key and the looked up values) is a row of the table.
+/// The rows are combined into single values with random coefficients, and checked by `logup_check`.
+pub fn table_check<C: Config, B: BasicAPI<C>>(
+    builder: &mut B,
+    rows: &[Vec<Variable>],
+    queries: &[Vec<Variable>],
+    counts: &[Variable],
+) {
+    let coefs: Vec<Variable> = (1..rows[0].len())
+        .map(|_| builder.get_random_value())
+        .collect();
+    let table = rows
+        .iter()
+        .map(|row| combine_row(builder, row, &coefs))
+        .collect::<Vec<_>>();
+    let queries = queries
+        .iter()
+        .map(|q| combine_row(builder, q, &coefs))
+        .collect::<Vec<_>>();
+    logup_check(builder, &queries, &table, counts);
+}
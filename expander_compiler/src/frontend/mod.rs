@@ -1,12 +1,18 @@
 use builder::RootBuilder;
 
-use crate::circuit::layered::{CrossLayerInputType, NormalInputType};
+use crate::circuit::ir::dest::profile::CostProfile;
+use crate::circuit::layered::{check::ConstraintDebugMap, CrossLayerInputType, NormalInputType};
 use crate::circuit::{ir, layered};
+use crate::compile::passes::PassManager;
+use crate::hints::info::HintInfos;
 
 pub mod api;
 pub mod builder;
 pub mod circuit;
 pub mod debug;
+mod logup;
+mod lookup;
+mod range_check;
 pub mod sub_circuit;
 pub mod variables;
 pub mod witness;
@@ -17,10 +23,11 @@ pub use crate::circuit::config::*;
 pub use crate::compile::CompileOptions;
 pub use crate::field::{BN254Fr, Field, FieldArith, GF2, M31};
 pub use crate::hints::registry::{EmptyHintCaller, HintCaller, HintRegistry};
-pub use crate::utils::error::Error;
+pub use crate::utils::error::{Error, ErrorKind};
 pub use api::{BasicAPI, RootAPI};
 pub use builder::Variable;
 pub use circuit::Define;
+pub use lookup::Table;
 pub use macros::memorized;
 pub use witness::WitnessSolver;
 
@@ -36,10 +43,14 @@ pub mod internal {
 pub mod extra {
 
     pub use super::api::UnconstrainedAPI;
-    pub use super::debug::DebugBuilder;
+    pub use super::debug::{AssertionKind, ConstraintFailure, ConstraintReport, DebugBuilder};
     pub use super::sub_circuit::{
         HashStructureAndPrimitive, JoinVecVariables, RebuildVecVariables,
     };
+    pub use crate::circuit::costs::CostModel;
+    pub use crate::circuit::ir::dest::profile::{CircuitProfile, CostProfile, GateCounts};
+    pub use crate::compile::calibrate::calibrate_cost_model;
+    pub use crate::compile::passes::{PassManager, PassPosition};
     pub use crate::hints::registry::{EmptyHintCaller, HintCaller, HintRegistry};
     // pub use crate::utils::serde::Serde;
 
@@ -55,6 +66,39 @@ pub mod extra {
         assignment: &CA,
         hint_caller: H,
     ) -> Vec<CircuitField<C>> {
+        let mut root_builder = debug_define(circuit, assignment, hint_caller, false);
+        root_builder.get_outputs()
+    }
+
+    /// Like `debug_eval`, but keeps going after a failed assertion and reports all of them.
+    pub fn debug_eval_report<
+        C: Config,
+        Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
+        CA: internal::DumpLoadTwoVariables<CircuitField<C>>,
+        H: HintCaller<CircuitField<C>>,
+    >(
+        circuit: &Cir,
+        assignment: &CA,
+        hint_caller: H,
+    ) -> ConstraintReport<C> {
+        let mut root_builder = debug_define(circuit, assignment, hint_caller, true);
+        ConstraintReport {
+            outputs: root_builder.get_outputs(),
+            failures: root_builder.take_failures(),
+        }
+    }
+
+    fn debug_define<
+        C: Config,
+        Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
+        CA: internal::DumpLoadTwoVariables<CircuitField<C>>,
+        H: HintCaller<CircuitField<C>>,
+    >(
+        circuit: &Cir,
+        assignment: &CA,
+        hint_caller: H,
+        collect_failures: bool,
+    ) -> DebugBuilder<C, H> {
         let (num_inputs, num_public_inputs) = circuit.num_vars();
         let (a_num_inputs, a_num_public_inputs) = assignment.num_vars();
         assert_eq!(num_inputs, a_num_inputs);
@@ -64,12 +108,13 @@ pub mod extra {
         assignment.dump_into(&mut inputs, &mut public_inputs);
         let (mut root_builder, input_variables, public_input_variables) =
             DebugBuilder::<C, H>::new(inputs, public_inputs, hint_caller);
+        root_builder.set_collect_failures(collect_failures);
         let mut circuit = circuit.clone();
         let mut vars_ptr = input_variables.as_slice();
         let mut public_vars_ptr = public_input_variables.as_slice();
         circuit.load_from(&mut vars_ptr, &mut public_vars_ptr);
         circuit.define(&mut root_builder);
-        root_builder.get_outputs()
+        root_builder
     }
 }
 
@@ -86,9 +131,9 @@ pub struct CompileResultCrossLayer<C: Config> {
     pub layered_circuit: layered::Circuit<C, CrossLayerInputType>,
 }
 
-fn build<C: Config, Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone>(
+fn define_root<C: Config, Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone>(
     circuit: &Cir,
-) -> ir::source::RootCircuit<C> {
+) -> RootBuilder<C> {
     let (num_inputs, num_public_inputs) = circuit.num_vars();
     let (mut root_builder, input_variables, public_input_variables) =
         RootBuilder::<C>::new(num_inputs, num_public_inputs);
@@ -97,21 +142,103 @@ fn build<C: Config, Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> +
     let mut public_vars_ptr = public_input_variables.as_slice();
     circuit.load_from(&mut vars_ptr, &mut public_vars_ptr);
     circuit.define(&mut root_builder);
-    root_builder.build()
+    root_builder
+}
+
+fn build<C: Config, Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone>(
+    circuit: &Cir,
+) -> (ir::source::RootCircuit<C>, HintInfos) {
+    define_root(circuit).build_with_hint_infos()
+}
+
+// hint call sites are recorded against the source circuit, the solver runs the compiled one
+fn witness_solver<C: Config>(
+    root: &ir::source::RootCircuit<C>,
+    circuit: ir::hint_normalized::RootCircuit<C>,
+    hint_infos: &HintInfos,
+) -> WitnessSolver<C> {
+    WitnessSolver {
+        hint_infos: hint_infos.for_compiled(root, &circuit),
+        circuit,
+    }
 }
 
 pub fn compile<C: Config, Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone>(
     circuit: &Cir,
     options: CompileOptions,
 ) -> Result<CompileResult<C>, Error> {
-    let root = build(circuit);
+    let (root, hint_infos) = build(circuit);
     let (irw, lc) = crate::compile::compile_with_options::<C, _>(&root, options)?;
     Ok(CompileResult {
-        witness_solver: WitnessSolver { circuit: irw },
+        witness_solver: witness_solver(&root, irw, &hint_infos),
+        layered_circuit: lc,
+    })
+}
+
+/// Same as `compile`, but runs the user passes of `passes` and writes its IR dumps.
+pub fn compile_with_passes<
+    C: Config,
+    Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
+>(
+    circuit: &Cir,
+    options: CompileOptions,
+    passes: &PassManager<C>,
+) -> Result<CompileResult<C>, Error> {
+    let (root, hint_infos) = build(circuit);
+    let (irw, lc) = crate::compile::compile_with_passes::<C, _>(&root, options, passes)?;
+    Ok(CompileResult {
+        witness_solver: witness_solver(&root, irw, &hint_infos),
         layered_circuit: lc,
     })
 }
 
+/// Same as `compile_with_passes`, but also returns the map used by
+/// `layered::Circuit::check_witness` to find out which constraints a bad witness violates.
+pub fn compile_with_constraint_debug_map<
+    C: Config,
+    Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
+>(
+    circuit: &Cir,
+    options: CompileOptions,
+    passes: &PassManager<C>,
+) -> Result<(CompileResult<C>, ConstraintDebugMap<C>), Error> {
+    let (root, hint_infos) = build(circuit);
+    let (irw, lc, debug_map) =
+        crate::compile::compile_with_constraint_debug_map::<C, _>(&root, options, passes)?;
+    Ok((
+        CompileResult {
+            witness_solver: witness_solver(&root, irw, &hint_infos),
+            layered_circuit: lc,
+        },
+        debug_map,
+    ))
+}
+
+/// Same as `compile_with_passes`, but also returns the estimated gates of each memorized sub
+/// circuit, named after its function.
+pub fn compile_with_profile<
+    C: Config,
+    Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
+>(
+    circuit: &Cir,
+    options: CompileOptions,
+    passes: &PassManager<C>,
+) -> Result<(CompileResult<C>, CostProfile), Error> {
+    let root_builder = define_root(circuit);
+    let names = root_builder.sub_circuit_names().clone();
+    let (root, hint_infos) = root_builder.build_with_hint_infos();
+    let (irw, lc, mut profile) =
+        crate::compile::compile_with_profile::<C, _>(&root, options, passes)?;
+    profile.set_names(&names);
+    Ok((
+        CompileResult {
+            witness_solver: witness_solver(&root, irw, &hint_infos),
+            layered_circuit: lc,
+        },
+        profile,
+    ))
+}
+
 pub fn compile_cross_layer<
     C: Config,
     Cir: internal::DumpLoadTwoVariables<Variable> + Define<C> + Clone,
@@ -119,10 +246,10 @@ pub fn compile_cross_layer<
     circuit: &Cir,
     options: CompileOptions,
 ) -> Result<CompileResultCrossLayer<C>, Error> {
-    let root = build(circuit);
+    let (root, hint_infos) = build(circuit);
     let (irw, lc) = crate::compile::compile_with_options::<C, _>(&root, options)?;
     Ok(CompileResultCrossLayer {
-        witness_solver: WitnessSolver { circuit: irw },
+        witness_solver: witness_solver(&root, irw, &hint_infos),
         layered_circuit: lc,
     })
 }
@@ -0,0 +1,85 @@
+use ethnum::U256;
+
+use crate::{circuit::config::Config, field::FieldArith, hints::u256_bit_length};
+
+use super::{
+    api::{BasicAPI, UnconstrainedAPI},
+    builder::Variable,
+    CircuitField,
+};
+
+// Bounds for the size of the lookup table, which contains all values below 2^table_bits
+const MIN_TABLE_BITS: usize = 4;
+const MAX_TABLE_BITS: usize = 15;
+
+/// The largest `bits` accepted by `assert_in_range`, so that 2^bits is below the modulus.
+pub fn max_range_check_bits<C: Config>() -> usize {
+    u256_bit_length(CircuitField::<C>::MODULUS) - 1
+}
+
+pub fn in_range<C: Config>(x: CircuitField<C>, bits: usize) -> bool {
+    bits >= 256 || (x.to_u256() >> bits as u32) == U256::ZERO
+}
+
+fn pow2<C: Config>(k: usize) -> CircuitField<C> {
+    CircuitField::<C>::from_u256(U256::ONE << k as u32)
+}
+
+fn ceil_log2(n: usize) -> usize {
+    n.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Chooses the table size from the number of queries: a table much larger than the number
+/// of queries would dominate the cost.
+/// The table is also never larger than needed for the widest query, and small enough that
+/// `x * 2^(table_bits - w)` can't wrap around for any `x < 2^table_bits`.
+pub fn table_bits<C: Config>(queries: &[(Variable, usize)]) -> usize {
+    let widest = queries.iter().map(|(_, bits)| *bits).max().unwrap_or(1);
+    ceil_log2(queries.len())
+        .clamp(MIN_TABLE_BITS, MAX_TABLE_BITS)
+        .min((max_range_check_bits::<C>() + 1) / 2)
+        .min(widest)
+        .max(1)
+}
+
+/// Splits every query into chunks of at most `table_bits` bits, and returns the values to
+/// look up in the table of all values below 2^table_bits.
+///
+/// A chunk `c` of `w < table_bits` bits is checked by looking up both `c` and `c * 2^(table_bits - w)`.
+pub fn lookup_keys<C: Config, B: BasicAPI<C> + UnconstrainedAPI<C>>(
+    builder: &mut B,
+    queries: &[(Variable, usize)],
+    table_bits: usize,
+) -> Vec<Variable> {
+    let mut keys = Vec::new();
+    for &(x, bits) in queries.iter() {
+        let mut chunks = Vec::new();
+        if bits <= table_bits {
+            chunks.push((x, bits));
+        } else {
+            let n = bits.div_ceil(table_bits);
+            let mut sum = builder.constant(0);
+            for i in 0..n {
+                let shifted = builder.unconstrained_int_div(x, pow2::<C>(i * table_bits));
+                let chunk = builder.unconstrained_mod(shifted, pow2::<C>(table_bits));
+                let w = if i == n - 1 {
+                    bits - i * table_bits
+                } else {
+                    table_bits
+                };
+                let t = builder.mul(chunk, pow2::<C>(i * table_bits));
+                sum = builder.add(sum, t);
+                chunks.push((chunk, w));
+            }
+            builder.assert_is_equal(sum, x);
+        }
+        for (c, w) in chunks {
+            keys.push(c);
+            if w < table_bits {
+                let shifted = builder.mul(c, pow2::<C>(table_bits - w));
+                keys.push(shifted);
+            }
+        }
+    }
+    keys
+}
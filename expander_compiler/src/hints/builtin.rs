@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::hash::{DefaultHasher, Hash, Hasher};
 
 use ethnum::U256;
@@ -27,6 +28,10 @@ pub enum BuiltinHintIds {
     Lesser,
     Greater,
     ToBinary,
+    RangeCount,
+    TableLookup,
+    TableCount,
+    TableDefine,
 }
 
 #[cfg(not(target_pointer_width = "64"))]
@@ -61,6 +66,18 @@ impl BuiltinHintIds {
             x if x == BuiltinHintIds::Lesser as u64 as usize => Some(BuiltinHintIds::Lesser),
             x if x == BuiltinHintIds::Greater as u64 as usize => Some(BuiltinHintIds::Greater),
             x if x == BuiltinHintIds::ToBinary as u64 as usize => Some(BuiltinHintIds::ToBinary),
+            x if x == BuiltinHintIds::RangeCount as u64 as usize => {
+                Some(BuiltinHintIds::RangeCount)
+            }
+            x if x == BuiltinHintIds::TableLookup as u64 as usize => {
+                Some(BuiltinHintIds::TableLookup)
+            }
+            x if x == BuiltinHintIds::TableCount as u64 as usize => {
+                Some(BuiltinHintIds::TableCount)
+            }
+            x if x == BuiltinHintIds::TableDefine as u64 as usize => {
+                Some(BuiltinHintIds::TableDefine)
+            }
             _ => None,
         }
     }
@@ -150,6 +167,54 @@ fn validate_builtin_hint(
                 ));
             }
         }
+        BuiltinHintIds::RangeCount => {
+            if num_inputs == 0 {
+                return Err(Error::InternalError(
+                    "range_count requires at least 1 input".to_string(),
+                ));
+            }
+            if num_outputs == 0 {
+                return Err(Error::InternalError(
+                    "range_count requires at least 1 output".to_string(),
+                ));
+            }
+        }
+        BuiltinHintIds::TableLookup => {
+            if num_outputs == 0 {
+                return Err(Error::InternalError(
+                    "table_lookup requires at least 1 output".to_string(),
+                ));
+            }
+            if num_inputs != 2 {
+                return Err(Error::InternalError(
+                    "table_lookup requires exactly 2 inputs".to_string(),
+                ));
+            }
+        }
+        BuiltinHintIds::TableCount => {
+            if num_outputs == 0 {
+                return Err(Error::InternalError(
+                    "table_count requires at least 1 output".to_string(),
+                ));
+            }
+            if num_inputs < num_outputs {
+                return Err(Error::InternalError(
+                    "table_count requires all table keys as inputs".to_string(),
+                ));
+            }
+        }
+        BuiltinHintIds::TableDefine => {
+            if num_inputs < 3 {
+                return Err(Error::InternalError(
+                    "table_define requires a table id, a row length and rows as inputs".to_string(),
+                ));
+            }
+            if num_outputs != 1 {
+                return Err(Error::InternalError(
+                    "table_define requires exactly 1 output".to_string(),
+                ));
+            }
+        }
     }
     Ok(())
 }
@@ -236,6 +301,15 @@ pub fn impl_builtin_hint<F: Field>(
         BuiltinHintIds::Lesser => binop_hint(inputs, |x, y| F::from((x < y) as u32)),
         BuiltinHintIds::Greater => binop_hint(inputs, |x, y| F::from((x > y) as u32)),
         BuiltinHintIds::ToBinary => to_binary(inputs[0], num_outputs).unwrap(), // TODO: error propagation
+        BuiltinHintIds::RangeCount => range_count(inputs, num_outputs),
+        // the tables only exist in the witness solver, see `LookupTables`
+        BuiltinHintIds::TableLookup => stub_impl_general(
+            BuiltinHintIds::TableLookup as u64 as usize,
+            &inputs.to_vec(),
+            num_outputs,
+        ),
+        BuiltinHintIds::TableCount => table_count(inputs, num_outputs),
+        BuiltinHintIds::TableDefine => vec![inputs[0]],
     }
 }
 
@@ -265,6 +339,35 @@ pub fn to_binary<F: Field>(x: F, num_outputs: usize) -> Result<Vec<F>, Error> {
     Ok(outputs)
 }
 
+// outputs[i] is the number of inputs equal to i, inputs out of range are ignored
+// (the lookup argument using these counts will fail for them)
+fn range_count<F: Field>(inputs: &[F], num_outputs: usize) -> Vec<F> {
+    let mut counts = vec![0u32; num_outputs];
+    for x in inputs.iter() {
+        let x = x.to_u256();
+        if x < U256::from(num_outputs as u64) {
+            counts[x.as_usize()] += 1;
+        }
+    }
+    counts.into_iter().map(F::from).collect()
+}
+
+// inputs are the num_outputs table keys followed by the queried keys
+// outputs[i] is the number of queries matching the i-th key, only the first of duplicate keys is counted
+fn table_count<F: Field>(inputs: &[F], num_outputs: usize) -> Vec<F> {
+    let mut row_of_key = HashMap::new();
+    for (i, k) in inputs[..num_outputs].iter().enumerate() {
+        row_of_key.entry(k.to_u256()).or_insert(i);
+    }
+    let mut counts = vec![0u32; num_outputs];
+    for q in inputs[num_outputs..].iter() {
+        if let Some(&i) = row_of_key.get(&q.to_u256()) {
+            counts[i] += 1;
+        }
+    }
+    counts.into_iter().map(F::from).collect()
+}
+
 pub fn stub_impl<F: Field>(hint_id: usize, inputs: &Vec<F>, num_outputs: usize) -> Vec<F> {
     match BuiltinHintIds::from_usize(hint_id) {
         Some(hint_id) => impl_builtin_hint(hint_id, inputs, num_outputs),
@@ -307,6 +410,14 @@ pub fn random_builtin(mut rand: impl RngCore) -> (usize, usize, usize) {
                 BuiltinHintIds::ToBinary => {
                     return (hint_id as usize, 1, 300);
                 }
+                BuiltinHintIds::RangeCount => {
+                    return (hint_id as usize, 5, 16);
+                }
+                BuiltinHintIds::TableCount => {
+                    return (hint_id as usize, 7, 4);
+                }
+                // lookups need a table defined earlier in the same witness
+                BuiltinHintIds::TableLookup | BuiltinHintIds::TableDefine => {}
             }
         }
     }
@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    panic::Location,
+};
+
+use serdes::{ExpSerde, SerdeResult};
+
+use crate::{
+    circuit::ir::common::{Instruction, IrConfig, RootCircuit},
+    utils::error::Error,
+};
+
+/// Maps hint ids back to their keys, and hint instructions back to the place where they were
+/// created, so that witness solving errors are readable.
+///
+/// Call sites are keyed by circuit id and instruction index, so a key used at many places
+/// still reports the instruction that failed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HintInfos {
+    keys: HashMap<usize, String>,
+    // (circuit id, instruction index) -> call sites, more than one if compilation merged hints
+    call_sites: HashMap<(usize, usize), Vec<String>>,
+    sub_circuit_names: HashMap<usize, String>,
+}
+
+impl HintInfos {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the hint instruction at `instruction_index` of circuit `circuit_id`.
+    pub fn record(
+        &mut self,
+        hint_id: usize,
+        key: &str,
+        circuit_id: usize,
+        instruction_index: usize,
+        call_site: &Location<'static>,
+    ) {
+        self.keys.entry(hint_id).or_insert_with(|| key.to_string());
+        self.call_sites
+            .insert((circuit_id, instruction_index), vec![call_site.to_string()]);
+    }
+
+    pub fn key(&self, hint_id: usize) -> Option<&str> {
+        self.keys.get(&hint_id).map(|key| key.as_str())
+    }
+
+    pub fn set_sub_circuit_names(&mut self, names: &HashMap<usize, String>) {
+        self.sub_circuit_names = names.clone();
+    }
+
+    /// Moves the call sites recorded against `source` to the instructions of `compiled`.
+    ///
+    /// Compilation keeps the circuit ids and the order of the user hints, but may drop
+    /// unreachable ones. So the j-th occurrence of a hint in a compiled circuit is one of the
+    /// occurrences j..=j+k of the source circuit, where k is the number of dropped ones.
+    pub fn for_compiled<A: IrConfig, B: IrConfig>(
+        &self,
+        source: &RootCircuit<A>,
+        compiled: &RootCircuit<B>,
+    ) -> HintInfos {
+        let mut res = HintInfos {
+            keys: self.keys.clone(),
+            call_sites: HashMap::new(),
+            sub_circuit_names: self.sub_circuit_names.clone(),
+        };
+        for (&circuit_id, circuit) in compiled.circuits.iter() {
+            let src_circuit = match source.circuits.get(&circuit_id) {
+                Some(c) => c,
+                None => continue,
+            };
+            let mut src_sites: HashMap<usize, Vec<Option<&Vec<String>>>> = HashMap::new();
+            for (i, insn) in src_circuit.instructions.iter().enumerate() {
+                if let Some(hint_id) = insn.as_hint() {
+                    src_sites
+                        .entry(hint_id)
+                        .or_default()
+                        .push(self.call_sites.get(&(circuit_id, i)));
+                }
+            }
+            let mut dst_indices: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (i, insn) in circuit.instructions.iter().enumerate() {
+                if let Some(hint_id) = insn.as_hint() {
+                    dst_indices.entry(hint_id).or_default().push(i);
+                }
+            }
+            for (hint_id, indices) in dst_indices {
+                let sites = match src_sites.get(&hint_id) {
+                    Some(sites) if sites.len() >= indices.len() => sites,
+                    _ => continue,
+                };
+                let slack = sites.len() - indices.len();
+                for (j, index) in indices.into_iter().enumerate() {
+                    let mut candidates: Vec<String> = Vec::new();
+                    for site in sites[j..=j + slack].iter().flatten().copied().flatten() {
+                        if !candidates.contains(site) {
+                            candidates.push(site.clone());
+                        }
+                    }
+                    if !candidates.is_empty() {
+                        res.call_sites.insert((circuit_id, index), candidates);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn circuit_name(&self, circuit_id: usize) -> String {
+        if circuit_id == 0 {
+            return "root".to_string();
+        }
+        match self.sub_circuit_names.get(&circuit_id) {
+            Some(name) => name.clone(),
+            None => circuit_id.to_string(),
+        }
+    }
+
+    /// Adds the hint key, sub-circuit path and call site to an error returned by a hint.
+    ///
+    /// The last element of `sub_circuit_path` is the circuit running the hint, and
+    /// `instruction_index` the index of the hint instruction in it.
+    pub fn hint_error(
+        &self,
+        hint_id: usize,
+        sub_circuit_path: &[usize],
+        instruction_index: usize,
+        err: Error,
+    ) -> Error {
+        let path = sub_circuit_path
+            .iter()
+            .map(|&id| self.circuit_name(id))
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        let name = match self.keys.get(&hint_id) {
+            Some(key) => format!("hint `{key}`"),
+            None => format!("hint with id {hint_id}"),
+        };
+        let call_sites = sub_circuit_path
+            .last()
+            .and_then(|&circuit_id| self.call_sites.get(&(circuit_id, instruction_index)));
+        let prefix = match call_sites {
+            Some(sites) if sites.len() == 1 => format!(
+                "{name} (created at {}) failed in sub-circuit path {path}",
+                sites[0]
+            ),
+            Some(sites) => format!(
+                "{name} (created at one of {}) failed in sub-circuit path {path}",
+                sites.join(", ")
+            ),
+            None => format!("{name} failed in sub-circuit path {path}"),
+        };
+        Error::hint_failed(prefix, err)
+    }
+}
+
+fn serialize_string<W: Write>(s: &str, mut writer: W) -> SerdeResult<()> {
+    s.as_bytes().to_vec().serialize_into(&mut writer)
+}
+
+fn deserialize_string<R: Read>(mut reader: R) -> SerdeResult<String> {
+    let bytes = Vec::<u8>::deserialize_from(&mut reader)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn serialize_names<W: Write>(names: &HashMap<usize, String>, mut writer: W) -> SerdeResult<()> {
+    let mut ids: Vec<usize> = names.keys().cloned().collect();
+    ids.sort();
+    ids.len().serialize_into(&mut writer)?;
+    for id in ids {
+        id.serialize_into(&mut writer)?;
+        serialize_string(&names[&id], &mut writer)?;
+    }
+    Ok(())
+}
+
+fn deserialize_names<R: Read>(mut reader: R) -> SerdeResult<HashMap<usize, String>> {
+    let n = usize::deserialize_from(&mut reader)?;
+    let mut names = HashMap::with_capacity(n);
+    for _ in 0..n {
+        let id = usize::deserialize_from(&mut reader)?;
+        names.insert(id, deserialize_string(&mut reader)?);
+    }
+    Ok(names)
+}
+
+impl ExpSerde for HintInfos {
+    fn serialize_into<W: Write>(&self, mut writer: W) -> SerdeResult<()> {
+        serialize_names(&self.keys, &mut writer)?;
+        let mut locations: Vec<(usize, usize)> = self.call_sites.keys().cloned().collect();
+        locations.sort();
+        locations.len().serialize_into(&mut writer)?;
+        for location in locations {
+            let sites = &self.call_sites[&location];
+            location.0.serialize_into(&mut writer)?;
+            location.1.serialize_into(&mut writer)?;
+            sites.len().serialize_into(&mut writer)?;
+            for site in sites.iter() {
+                serialize_string(site, &mut writer)?;
+            }
+        }
+        serialize_names(&self.sub_circuit_names, &mut writer)
+    }
+
+    fn deserialize_from<R: Read>(mut reader: R) -> SerdeResult<Self> {
+        let keys = deserialize_names(&mut reader)?;
+        let n = usize::deserialize_from(&mut reader)?;
+        let mut call_sites = HashMap::with_capacity(n);
+        for _ in 0..n {
+            let circuit_id = usize::deserialize_from(&mut reader)?;
+            let instruction_index = usize::deserialize_from(&mut reader)?;
+            let num_sites = usize::deserialize_from(&mut reader)?;
+            let mut sites = Vec::with_capacity(num_sites);
+            for _ in 0..num_sites {
+                sites.push(deserialize_string(&mut reader)?);
+            }
+            call_sites.insert((circuit_id, instruction_index), sites);
+        }
+        let sub_circuit_names = deserialize_names(&mut reader)?;
+        Ok(HintInfos {
+            keys,
+            call_sites,
+            sub_circuit_names,
+        })
+    }
+}
@@ -1,9 +1,12 @@
 pub mod builtin;
+pub mod info;
 pub mod registry;
+pub mod tables;
 
 pub use builtin::*;
 
 use registry::HintCaller;
+use tables::LookupTables;
 
 use crate::{field::Field, utils::error::Error};
 
@@ -14,7 +17,54 @@ pub fn safe_impl<F: Field>(
     num_outputs: usize,
 ) -> Result<Vec<F>, Error> {
     match BuiltinHintIds::from_usize(hint_id) {
+        Some(BuiltinHintIds::TableLookup) => Err(Error::InternalError(
+            "table lookups can only be evaluated with the tables of the witness".to_string(),
+        )),
         Some(hint_id) => Ok(impl_builtin_hint(hint_id, inputs, num_outputs)),
-        None => hint_caller.call(hint_id, inputs, num_outputs),
+        None => match hint_caller.call_dynamic(hint_id, inputs) {
+            Some(values) => {
+                let mut outputs = vec![F::zero(); num_outputs];
+                pad_dynamic_outputs(values?, &mut outputs)?;
+                Ok(outputs)
+            }
+            None => hint_caller.call(hint_id, inputs, num_outputs),
+        },
     }
 }
+
+/// Same as `safe_impl`, but the table hints define and look up the tables in `tables`.
+pub fn safe_impl_with_tables<F: Field>(
+    hint_caller: &impl HintCaller<F>,
+    tables: &mut LookupTables<F>,
+    hint_id: usize,
+    inputs: &[F],
+    num_outputs: usize,
+) -> Result<Vec<F>, Error> {
+    match BuiltinHintIds::from_usize(hint_id) {
+        Some(BuiltinHintIds::TableDefine) => tables.define(inputs),
+        Some(BuiltinHintIds::TableLookup) => tables.lookup(inputs, num_outputs),
+        _ => safe_impl(hint_caller, hint_id, inputs, num_outputs),
+    }
+}
+
+/// Writes the result of a dynamic-output hint as `[len, values..., 0, ...]`.
+/// Unused outputs are always zero, so the witness is deterministic for a given result.
+/// `BasicAPI::new_dynamic_hint` constrains this layout in the circuit.
+pub fn pad_dynamic_outputs<F: Field>(values: Vec<F>, outputs: &mut [F]) -> Result<(), Error> {
+    if values.len() >= outputs.len() {
+        return Err(Error::UserError(format!(
+            "dynamic hint returned {} outputs, but at most {} are allowed",
+            values.len(),
+            outputs.len().saturating_sub(1)
+        )));
+    }
+    outputs[0] = F::from(values.len() as u32);
+    for (i, x) in outputs.iter_mut().skip(1).enumerate() {
+        *x = if i < values.len() {
+            values[i]
+        } else {
+            F::zero()
+        };
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use tiny_keccak::Hasher;
 
@@ -6,11 +6,17 @@ use crate::{field::Field, utils::error::Error};
 
 use super::{stub_impl, BuiltinHintIds};
 
-type HintFn<F> = fn(&[F], &mut [F]) -> Result<(), Error>;
+/// A hint implementation. Closures may capture external state (lookup tables,
+/// precomputed values, ...), which is shared between all callers of the registry.
+pub type HintFn<F> = dyn Fn(&[F], &mut [F]) -> Result<(), Error> + Send + Sync;
+
+/// A hint whose number of outputs is only known at witness time.
+pub type DynamicHintFn<F> = dyn Fn(&[F]) -> Result<Vec<F>, Error> + Send + Sync;
 
 #[derive(Default, Clone)]
 pub struct HintRegistry<F: Field> {
-    hints: HashMap<usize, HintFn<F>>,
+    hints: HashMap<usize, Arc<HintFn<F>>>,
+    dynamic_hints: HashMap<usize, Arc<DynamicHintFn<F>>>,
 }
 
 pub fn hint_key_to_id(key: &str) -> usize {
@@ -31,18 +37,38 @@ impl<F: Field> HintRegistry<F> {
         Self::default()
     }
     pub fn register(&mut self, key: &str, hint: fn(&[F], &mut [F]) -> Result<(), Error>) {
+        self.register_arc(key, Arc::new(hint));
+    }
+    pub fn register_closure(&mut self, key: &str, hint: Box<HintFn<F>>) {
+        self.register_arc(key, Arc::from(hint));
+    }
+    /// Registers a shared hint. The same `Arc` may be registered in several registries,
+    /// e.g. one per rayon worker, without cloning the captured state.
+    pub fn register_arc(&mut self, key: &str, hint: Arc<HintFn<F>>) {
         let id = hint_key_to_id(key);
-        if self.hints.contains_key(&id) {
+        if self.hints.contains_key(&id) || self.dynamic_hints.contains_key(&id) {
             panic!("Hint with id {id} already exists");
         }
         self.hints.insert(id, hint);
     }
+    pub fn register_dynamic(&mut self, key: &str, hint: fn(&[F]) -> Result<Vec<F>, Error>) {
+        self.register_dynamic_closure(key, Box::new(hint));
+    }
+    /// Registers a hint used with `BasicAPI::new_dynamic_hint`. The witness solver lays out its
+    /// result with `pad_dynamic_outputs`, so the witness size does not depend on it.
+    pub fn register_dynamic_closure(&mut self, key: &str, hint: Box<DynamicHintFn<F>>) {
+        let id = hint_key_to_id(key);
+        if self.hints.contains_key(&id) || self.dynamic_hints.contains_key(&id) {
+            panic!("Hint with id {id} already exists");
+        }
+        self.dynamic_hints.insert(id, Arc::from(hint));
+    }
     pub fn call(&self, id: usize, args: &[F], num_outputs: usize) -> Result<Vec<F>, Error> {
         if let Some(hint) = self.hints.get(&id) {
             let mut outputs = vec![F::zero(); num_outputs];
             hint(args, &mut outputs).map(|_| outputs)
         } else {
-            panic!("Hint with id {id} not found");
+            Err(Error::UserError(format!("hint with id {id} not found")))
         }
     }
 }
@@ -59,12 +85,20 @@ pub struct StubHintCaller;
 
 pub trait HintCaller<F: Field>: Sync + 'static {
     fn call(&self, id: usize, args: &[F], num_outputs: usize) -> Result<Vec<F>, Error>;
+    /// Calls a dynamic-output hint, returning None if `id` is not one.
+    /// The caller pads the result with `pad_dynamic_outputs`.
+    fn call_dynamic(&self, _id: usize, _args: &[F]) -> Option<Result<Vec<F>, Error>> {
+        None
+    }
 }
 
 impl<F: Field + 'static> HintCaller<F> for HintRegistry<F> {
     fn call(&self, id: usize, args: &[F], num_outputs: usize) -> Result<Vec<F>, Error> {
         self.call(id, args, num_outputs)
     }
+    fn call_dynamic(&self, id: usize, args: &[F]) -> Option<Result<Vec<F>, Error>> {
+        self.dynamic_hints.get(&id).map(|hint| hint(args))
+    }
 }
 
 impl<F: Field> HintCaller<F> for EmptyHintCaller {
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use ethnum::U256;
+
+use crate::{field::Field, utils::error::Error};
+
+/// The lookup tables of the witness being solved, indexed by key.
+///
+/// The frontend emits one `TableDefine` hint per table, which gets the whole table, and the
+/// `TableLookup` hints only get the table id and the key. So each table is indexed once per
+/// witness, and a lookup is a map access.
+pub struct LookupTables<F: Field> {
+    tables: HashMap<usize, HashMap<U256, Vec<F>>>,
+}
+
+impl<F: Field> Default for LookupTables<F> {
+    fn default() -> Self {
+        LookupTables {
+            tables: HashMap::new(),
+        }
+    }
+}
+
+impl<F: Field> LookupTables<F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // inputs are the table id, the row length and the rows, each being a key and its values
+    // the output is the table id, which the lookups in the same circuit take as input
+    pub fn define(&mut self, inputs: &[F]) -> Result<Vec<F>, Error> {
+        let table_id = inputs[0].to_u256().as_usize();
+        let row_len = inputs[1].to_u256();
+        let rows = &inputs[2..];
+        if row_len == U256::ZERO
+            || row_len > U256::from(rows.len() as u64)
+            || rows.len() % row_len.as_usize() != 0
+        {
+            return Err(Error::InternalError(
+                "table_define rows don't match the row length".to_string(),
+            ));
+        }
+        let row_len = row_len.as_usize();
+        let mut table = HashMap::with_capacity(rows.len() / row_len);
+        for row in rows.chunks(row_len) {
+            // the first row with a key is used
+            table
+                .entry(row[0].to_u256())
+                .or_insert_with(|| row[1..].to_vec());
+        }
+        self.tables.insert(table_id, table);
+        Ok(vec![inputs[0]])
+    }
+
+    // inputs are the table id and the key
+    // outputs are the values of the row with the key, or zeros if there's none
+    // (the lookup argument will fail in that case)
+    pub fn lookup(&self, inputs: &[F], num_outputs: usize) -> Result<Vec<F>, Error> {
+        let table_id = inputs[0].to_u256().as_usize();
+        let table = self.tables.get(&table_id).ok_or_else(|| {
+            Error::InternalError(format!("table {table_id} is used before it's defined"))
+        })?;
+        Ok(table
+            .get(&inputs[1].to_u256())
+            .cloned()
+            .unwrap_or_else(|| vec![F::zero(); num_outputs]))
+    }
+}
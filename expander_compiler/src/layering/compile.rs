@@ -1,13 +1,15 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+
 use crate::circuit::{
     config::Config,
     ir::dest::{Circuit as IrCircuit, Instruction, RootCircuit as IrRootCircuit},
     ir::expr::Expression,
     layered::{Coef, InputType, Segment},
 };
-use crate::utils::pool::Pool;
+use crate::utils::{misc::topo_levels, pool::Pool};
 
 use super::layer_layout::merge_layouts;
 use super::layer_layout::{LayerLayout, LayerLayoutContext, LayerReq};
@@ -104,14 +106,31 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
         self.dfs_topo_sort(0);
 
         // 2. compute min and max layers for each circuit
-        for id in self.order.clone() {
-            self.compute_min_max_layers(id);
+        // a circuit only depends on its sub circuits, so each level is processed in parallel
+        for level in topo_levels(&self.order, &self.rc.sub_circuit_graph_edges()) {
+            let mut ics: Vec<(usize, IrContext<'a, C>)> = level
+                .iter()
+                .map(|id| (*id, self.circuits.remove(id).unwrap()))
+                .collect();
+            let circuits = &self.circuits;
+            let root_has_constraints = ics
+                .par_iter_mut()
+                .map(|(id, ic)| Self::compute_min_max_layers(ic, *id, circuits))
+                .reduce(|| false, |a, b| a || b);
+            self.root_has_constraints |= root_has_constraints;
+            self.circuits.extend(ics);
         }
 
         // 3. prepare layer layout contexts
-        for id in self.order.clone() {
-            self.prepare_layer_layout_context(id);
-        }
+        // it only needs the output layers of the sub circuits, so all circuits are independent
+        let output_layers: HashMap<usize, usize> = self
+            .circuits
+            .iter()
+            .map(|(id, ic)| (*id, ic.output_layer))
+            .collect();
+        self.circuits
+            .par_iter_mut()
+            .for_each(|(_, ic)| Self::prepare_layer_layout_context(ic, &output_layers));
 
         // 4. solve layer layout for root circuit (it also recursively solves all required sub-circuits)
         let mut layout_ids = Vec::with_capacity(self.circuits[&0].output_layer + 1);
@@ -198,16 +217,21 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
         );
     }
 
-    fn compute_min_max_layers(&mut self, circuit_id: usize) {
+    // returns whether the root circuit has constraints
+    fn compute_min_max_layers(
+        ic: &mut IrContext<'a, C>,
+        circuit_id: usize,
+        circuits: &HashMap<usize, IrContext<'a, C>>,
+    ) -> bool {
         // variables
         // 0..nbVariable: normal variables
         // next nbSubCircuits terms: sub circuit virtual variables (in order to lower the number of edges)
         // next ? terms: random sum of constraints
-        let mut ic = self.circuits.remove(&circuit_id).unwrap();
         let nv = ic.num_var;
         let ns = ic.num_sub_circuits;
         let mut n = nv + ns;
-        let circuit = self.rc.circuits.get(&circuit_id).unwrap();
+        let circuit = ic.circuit;
+        let mut root_has_constraints = false;
 
         let pre_alloc_size = n + EXTRA_PRE_ALLOC_SIZE.min(n);
         ic.min_layer = Vec::with_capacity(pre_alloc_size);
@@ -265,7 +289,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
                         add_edge(*x, k);
                     }
                     q1.push(k);
-                    layer_advance[k] = self.circuits[sub_circuit_id].output_layer - 1;
+                    layer_advance[k] = circuits[sub_circuit_id].output_layer - 1;
                     let mut outputs = Vec::new();
                     for _ in 0..*num_outputs {
                         add_edge(k, cur_var_idx);
@@ -328,7 +352,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
             cc[xl].variables.push(x);
         }
         for i in 0..ic.sub_circuit_insn_ids.len() {
-            let sub_circuit = &self.circuits[&ic.sub_circuit_insn_refs[i].sub_circuit_id];
+            let sub_circuit = &circuits[&ic.sub_circuit_insn_refs[i].sub_circuit_id];
             ic.output_layer = ic
                 .output_layer
                 .max(ic.sub_circuit_start_layer[i] + sub_circuit.output_layer);
@@ -354,7 +378,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
                     cc_i.variables.push(i - 1 - first + n);
                 }
                 cc.truncate(last + 1);
-                self.root_has_constraints = true;
+                root_has_constraints = true;
             }
         }
         let mut cc: Vec<Option<CombinedConstraint>> = cc.into_iter().map(Some).collect();
@@ -428,7 +452,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
             }
             let mut any_constraint = false;
 
-            for v in self.circuits[&ic.sub_circuit_insn_refs[i].sub_circuit_id]
+            for v in circuits[&ic.sub_circuit_insn_refs[i].sub_circuit_id]
                 .combined_constraints
                 .iter()
             {
@@ -493,7 +517,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
         ic.min_used_layer.push(0);
         ic.min_used_layer.extend(0..ic.output_layer);
         for (i, sc) in ic.sub_circuit_insn_refs.iter().enumerate() {
-            let sub_circuit = &self.circuits[&sc.sub_circuit_id];
+            let sub_circuit = &circuits[&sc.sub_circuit_id];
             let input_layer = ic.sub_circuit_start_layer[i];
             for j in 0..=sub_circuit.output_layer {
                 ic.min_used_layer[j + input_layer] = ic.min_used_layer[j + input_layer]
@@ -509,6 +533,6 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
             }
         }
 
-        self.circuits.insert(circuit_id, ic);
+        root_has_constraints
     }
 }
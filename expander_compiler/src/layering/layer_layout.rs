@@ -5,7 +5,7 @@ use crate::{
     utils::{misc::next_power_of_two, pool::Pool},
 };
 
-use super::compile::CompileContext;
+use super::compile::{CompileContext, IrContext};
 
 #[derive(Default, Clone)]
 pub struct LayerLayoutContext {
@@ -86,9 +86,11 @@ pub struct LayerReq {
 }
 
 impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
-    pub fn prepare_layer_layout_context(&mut self, circuit_id: usize) {
-        let mut ic = self.circuits.remove(&circuit_id).unwrap();
-
+    // `output_layers` are the output layers of all circuits
+    pub fn prepare_layer_layout_context(
+        ic: &mut IrContext<'a, C>,
+        output_layers: &HashMap<usize, usize>,
+    ) {
         // find out the variables in each layer
         ic.lcs = vec![LayerLayoutContext::default(); ic.output_layer + 1];
         for i in 0..=ic.output_layer {
@@ -116,7 +118,7 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
         for (i, insn_id) in ic.sub_circuit_insn_ids.iter().cloned().enumerate() {
             let insn = &ic.sub_circuit_insn_refs[i];
             let input_layer = ic.sub_circuit_start_layer[i];
-            let output_layer = self.circuits[&insn.sub_circuit_id].output_layer + input_layer;
+            let output_layer = output_layers[&insn.sub_circuit_id] + input_layer;
             let input_ids = insn.inputs.clone();
             ic.lcs[input_layer]
                 .req
@@ -183,7 +185,6 @@ impl<'a, C: Config, I: InputType> CompileContext<'a, C, I> {
                 }
             }
         }
-        self.circuits.insert(circuit_id, ic);
     }
 
     pub fn solve_layer_layout(&mut self, req: &LayerReq) -> usize {
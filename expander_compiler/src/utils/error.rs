@@ -1,24 +1,104 @@
-use std::fmt;
+use std::{error, fmt};
 
-#[derive(Debug, PartialEq, Eq)]
+/// The category of an error, for callers that need to handle failures programmatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    User,
+    Internal,
+    ConstraintUnsatisfied,
+    HintFailed,
+    InvalidShape,
+    IrValidationFailed,
+    DeserializationFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     UserError(String),
     InternalError(String),
+    ConstraintUnsatisfied(String),
+    InvalidShape(String),
+    DeserializationFailed(String),
+    /// A hint returned an error, `source` is the error from the hint itself.
+    HintFailed {
+        context: String,
+        source: Box<Error>,
+    },
+    /// An IR or layered circuit failed validation, `source` tells which check failed.
+    IrValidationFailed(Box<Error>),
+    /// Created by `prepend`, keeps the kind of `source`.
+    WithContext {
+        context: String,
+        source: Box<Error>,
+    },
 }
 
 impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::UserError(_) => ErrorKind::User,
+            Error::InternalError(_) => ErrorKind::Internal,
+            Error::ConstraintUnsatisfied(_) => ErrorKind::ConstraintUnsatisfied,
+            Error::InvalidShape(_) => ErrorKind::InvalidShape,
+            Error::DeserializationFailed(_) => ErrorKind::DeserializationFailed,
+            Error::HintFailed { .. } => ErrorKind::HintFailed,
+            Error::IrValidationFailed(_) => ErrorKind::IrValidationFailed,
+            Error::WithContext { source, .. } => source.kind(),
+        }
+    }
+
+    /// Returns true if this error or any error in its source chain has the given kind.
+    pub fn has_kind(&self, kind: ErrorKind) -> bool {
+        if self.kind() == kind {
+            return true;
+        }
+        match self.inner() {
+            Some(source) => source.has_kind(kind),
+            None => false,
+        }
+    }
+
     pub fn is_user(&self) -> bool {
-        matches!(self, Error::UserError(_))
+        !self.is_internal()
     }
 
     pub fn is_internal(&self) -> bool {
-        matches!(self, Error::InternalError(_))
+        match self {
+            Error::InternalError(_) => true,
+            Error::HintFailed { source, .. }
+            | Error::IrValidationFailed(source)
+            | Error::WithContext { source, .. } => source.is_internal(),
+            _ => false,
+        }
     }
 
     pub fn prepend(&self, prefix: &str) -> Error {
+        Error::WithContext {
+            context: prefix.to_string(),
+            source: Box::new(self.clone()),
+        }
+    }
+
+    pub fn hint_failed(context: String, source: Error) -> Error {
+        Error::HintFailed {
+            context,
+            source: Box::new(source),
+        }
+    }
+
+    pub fn ir_validation_failed(source: Error) -> Error {
+        match source {
+            Error::IrValidationFailed(_) => source,
+            _ => Error::IrValidationFailed(Box::new(source)),
+        }
+    }
+
+    fn inner(&self) -> Option<&Error> {
         match self {
-            Error::UserError(s) => Error::UserError(format!("{prefix}: {s}")),
-            Error::InternalError(s) => Error::InternalError(format!("{prefix}: {s}")),
+            Error::HintFailed { source, .. }
+            | Error::IrValidationFailed(source)
+            | Error::WithContext { source, .. } => Some(source),
+            _ => None,
         }
     }
 }
@@ -26,8 +106,56 @@ impl Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::UserError(s) => write!(f, "{s}"),
-            Error::InternalError(s) => write!(f, "{s}"),
+            Error::UserError(s)
+            | Error::InternalError(s)
+            | Error::ConstraintUnsatisfied(s)
+            | Error::InvalidShape(s)
+            | Error::DeserializationFailed(s) => write!(f, "{s}"),
+            Error::HintFailed { context, source } | Error::WithContext { context, source } => {
+                write!(f, "{context}: {source}")
+            }
+            Error::IrValidationFailed(source) => write!(f, "{source}"),
         }
     }
 }
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.inner().map(|e| e as &(dyn error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_error_chain() {
+        let e = Error::hint_failed(
+            "hint `a` failed".to_string(),
+            Error::UserError("bad input".to_string()),
+        )
+        .prepend("witness solving failed");
+        assert_eq!(
+            e.to_string(),
+            "witness solving failed: hint `a` failed: bad input"
+        );
+        assert_eq!(e.kind(), ErrorKind::HintFailed);
+        assert!(e.has_kind(ErrorKind::User));
+        assert!(!e.has_kind(ErrorKind::InvalidShape));
+        assert!(e.is_user());
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "hint `a` failed: bad input"
+        );
+
+        let e = Error::ir_validation_failed(Error::ir_validation_failed(Error::InternalError(
+            "circuit is not a DAG".to_string(),
+        )));
+        assert_eq!(e.to_string(), "circuit is not a DAG");
+        assert_eq!(e.kind(), ErrorKind::IrValidationFailed);
+        assert!(e.is_internal());
+        assert!(e.source().unwrap().source().is_none());
+    }
+}
@@ -60,3 +60,51 @@ pub fn topo_order(vertices: &HashSet<usize>, edges: &HashMap<usize, HashSet<usiz
     }
     queue
 }
+
+// groups the vertices of a DAG into levels, so that each edge goes from a later level to an
+// earlier one, and vertices in the same level are independent
+// `order` must list each vertex after all vertices it has edges to, and the vertices of each
+// level are kept in this order
+pub fn topo_levels(order: &[usize], edges: &HashMap<usize, HashSet<usize>>) -> Vec<Vec<usize>> {
+    let mut level: HashMap<usize, usize> = HashMap::new();
+    let mut res: Vec<Vec<usize>> = Vec::new();
+    for &v in order.iter() {
+        let l = match edges.get(&v) {
+            Some(tos) => tos.iter().map(|to| level[to] + 1).max().unwrap_or(0),
+            None => 0,
+        };
+        level.insert(v, l);
+        if res.len() <= l {
+            res.resize(l + 1, Vec::new());
+        }
+        res[l].push(v);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topo_levels() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 0 -> 3, 4 -> 2
+        let mut edges: HashMap<usize, HashSet<usize>> = HashMap::new();
+        edges.insert(0, [1, 2, 3].into_iter().collect());
+        edges.insert(1, [3].into_iter().collect());
+        edges.insert(2, [3].into_iter().collect());
+        edges.insert(4, [2].into_iter().collect());
+        let vertices: HashSet<usize> = (0..5).collect();
+        let mut order = topo_order(&vertices, &edges);
+        order.reverse();
+        let levels = topo_levels(&order, &edges);
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0], vec![3]);
+        let mut l1 = levels[1].clone();
+        l1.sort();
+        assert_eq!(l1, vec![1, 2]);
+        let mut l2 = levels[2].clone();
+        l2.sort();
+        assert_eq!(l2, vec![0, 4]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Versioned containers for the artifacts passed between setup, proving and verification.
+//!
+//! Every artifact starts with a header recording the format version, the kind of artifact,
+//! `Config::CONFIG_ID`, the name of the PCS and the hash of the computation graph it belongs
+//! to. Loading an artifact checks the header against the expected context, so that e.g. a
+//! proof can't be verified with a setup generated for another graph, config or PCS.
+
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
+
+use gkr_engine::{ExpanderPCS, FieldEngine};
+use serdes::ExpSerde;
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::{circuit::config::Config, utils::error::Error};
+
+use super::{
+    context::ComputationGraph,
+    proving_system::{
+        expander::structs::{ExpanderProverSetup, ExpanderVerifierSetup},
+        CombinedProof, KernelWiseProvingSystem,
+    },
+};
+
+/// Bumped whenever the serialization of any artifact changes.
+pub const ARTIFACT_FORMAT_VERSION: usize = 1;
+
+const ARTIFACT_MAGIC: &[u8; 8] = b"ECCZKCU\0";
+
+pub type GraphHash = [u8; 32];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    ComputationGraph = 1,
+    ProverSetup = 2,
+    VerifierSetup = 3,
+    Proof = 4,
+}
+
+impl ArtifactKind {
+    fn from_u8(x: u8) -> Option<Self> {
+        match x {
+            1 => Some(ArtifactKind::ComputationGraph),
+            2 => Some(ArtifactKind::ProverSetup),
+            3 => Some(ArtifactKind::VerifierSetup),
+            4 => Some(ArtifactKind::Proof),
+            _ => None,
+        }
+    }
+}
+
+pub trait Artifact: ExpSerde {
+    const KIND: ArtifactKind;
+}
+
+impl<C: Config> Artifact for ComputationGraph<C> {
+    const KIND: ArtifactKind = ArtifactKind::ComputationGraph;
+}
+
+impl<F: FieldEngine, PCS: ExpanderPCS<F>> Artifact for ExpanderProverSetup<F, PCS> {
+    const KIND: ArtifactKind = ArtifactKind::ProverSetup;
+}
+
+impl<F: FieldEngine, PCS: ExpanderPCS<F>> Artifact for ExpanderVerifierSetup<F, PCS> {
+    const KIND: ArtifactKind = ArtifactKind::VerifierSetup;
+}
+
+impl<C: Config, KP: KernelWiseProvingSystem<C>> Artifact for CombinedProof<C, KP> {
+    const KIND: ArtifactKind = ArtifactKind::Proof;
+}
+
+/// Everything an artifact must match to be loaded, except its kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactContext {
+    pub config_id: usize,
+    pub pcs_name: String,
+    pub graph_hash: GraphHash,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    pub format_version: usize,
+    pub kind: ArtifactKind,
+    pub context: ArtifactContext,
+}
+
+pub fn computation_graph_hash<C: Config>(computation_graph: &ComputationGraph<C>) -> GraphHash {
+    let mut bytes = Vec::new();
+    computation_graph.serialize_into(&mut bytes).unwrap();
+    let mut hasher = Keccak::v256();
+    hasher.update(&bytes);
+    let mut res = [0u8; 32];
+    hasher.finalize(&mut res);
+    res
+}
+
+impl ArtifactContext {
+    pub fn new<C: Config, PCS: ExpanderPCS<C::FieldConfig>>(
+        computation_graph: &ComputationGraph<C>,
+    ) -> Self {
+        ArtifactContext {
+            config_id: C::CONFIG_ID,
+            pcs_name: PCS::NAME.to_string(),
+            graph_hash: computation_graph_hash(computation_graph),
+        }
+    }
+}
+
+fn io_error(what: &str, e: std::io::Error) -> Error {
+    Error::UserError(format!("failed to {what} artifact: {e}"))
+}
+
+fn malformed(msg: String) -> Error {
+    Error::DeserializationFailed(format!("malformed artifact: {msg}"))
+}
+
+// Like `CompileCache::get`, rejects bytes left after the payload, e.g. two artifacts
+// concatenated into one file.
+fn check_fully_read<R: Read>(mut reader: R) -> Result<(), Error> {
+    let mut byte = [0u8; 1];
+    match reader.read(&mut byte) {
+        Ok(0) => Ok(()),
+        Ok(_) => Err(malformed("trailing bytes after the payload".to_string())),
+        Err(e) => Err(malformed(e.to_string())),
+    }
+}
+
+impl ArtifactHeader {
+    fn serialize_into<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let pcs_name = self.context.pcs_name.as_bytes();
+        let mut bytes = Vec::with_capacity(64 + pcs_name.len());
+        bytes.extend_from_slice(ARTIFACT_MAGIC);
+        bytes.extend_from_slice(&(self.format_version as u64).to_le_bytes());
+        bytes.push(self.kind as u8);
+        bytes.extend_from_slice(&(self.context.config_id as u64).to_le_bytes());
+        bytes.extend_from_slice(&(pcs_name.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(pcs_name);
+        bytes.extend_from_slice(&self.context.graph_hash);
+        writer.write_all(&bytes).map_err(|e| io_error("write", e))
+    }
+
+    fn deserialize_from<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut read_exact = |buf: &mut [u8]| {
+            reader
+                .read_exact(buf)
+                .map_err(|e| malformed(format!("truncated header: {e}")))
+        };
+        let mut magic = [0u8; 8];
+        read_exact(&mut magic)?;
+        if magic != *ARTIFACT_MAGIC {
+            return Err(malformed("bad magic bytes".to_string()));
+        }
+        let mut u64_buf = [0u8; 8];
+        read_exact(&mut u64_buf)?;
+        let format_version = u64::from_le_bytes(u64_buf) as usize;
+        if format_version != ARTIFACT_FORMAT_VERSION {
+            return Err(Error::UserError(format!(
+                "artifact format version {format_version} is not supported, expected {ARTIFACT_FORMAT_VERSION}"
+            )));
+        }
+        let mut kind = [0u8; 1];
+        read_exact(&mut kind)?;
+        let kind = ArtifactKind::from_u8(kind[0])
+            .ok_or_else(|| malformed(format!("unknown artifact kind {}", kind[0])))?;
+        read_exact(&mut u64_buf)?;
+        let config_id = u64::from_le_bytes(u64_buf) as usize;
+        read_exact(&mut u64_buf)?;
+        let pcs_name_len = u64::from_le_bytes(u64_buf) as usize;
+        if pcs_name_len > 256 {
+            return Err(malformed(format!("pcs name too long: {pcs_name_len}")));
+        }
+        let mut pcs_name = vec![0u8; pcs_name_len];
+        read_exact(&mut pcs_name)?;
+        let pcs_name = String::from_utf8(pcs_name)
+            .map_err(|_| malformed("pcs name is not utf-8".to_string()))?;
+        let mut graph_hash = [0u8; 32];
+        read_exact(&mut graph_hash)?;
+        Ok(ArtifactHeader {
+            format_version,
+            kind,
+            context: ArtifactContext {
+                config_id,
+                pcs_name,
+                graph_hash,
+            },
+        })
+    }
+
+    fn check(&self, kind: ArtifactKind, context: &ArtifactContext) -> Result<(), Error> {
+        if self.kind != kind {
+            return Err(Error::UserError(format!(
+                "artifact kind mismatch: expected {:?}, got {:?}",
+                kind, self.kind
+            )));
+        }
+        if self.context.config_id != context.config_id {
+            return Err(Error::UserError(format!(
+                "artifact config id mismatch: expected {}, got {}",
+                context.config_id, self.context.config_id
+            )));
+        }
+        if self.context.pcs_name != context.pcs_name {
+            return Err(Error::UserError(format!(
+                "artifact pcs mismatch: expected {}, got {}",
+                context.pcs_name, self.context.pcs_name
+            )));
+        }
+        if self.context.graph_hash != context.graph_hash {
+            return Err(Error::UserError(
+                "artifact was produced for a different computation graph".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub fn write_artifact<T: Artifact, W: Write>(
+    mut writer: W,
+    context: &ArtifactContext,
+    value: &T,
+) -> Result<(), Error> {
+    ArtifactHeader {
+        format_version: ARTIFACT_FORMAT_VERSION,
+        kind: T::KIND,
+        context: context.clone(),
+    }
+    .serialize_into(&mut writer)?;
+    value
+        .serialize_into(&mut writer)
+        .map_err(|e| Error::UserError(format!("failed to serialize artifact: {e:?}")))
+}
+
+/// Reads an artifact, and checks that it was produced in `context`.
+pub fn read_artifact<T: Artifact, R: Read>(
+    mut reader: R,
+    context: &ArtifactContext,
+) -> Result<T, Error> {
+    let header = ArtifactHeader::deserialize_from(&mut reader)?;
+    header.check(T::KIND, context)?;
+    let value = T::deserialize_from(&mut reader).map_err(|e| malformed(format!("{e:?}")))?;
+    check_fully_read(reader)?;
+    Ok(value)
+}
+
+/// Reads a computation graph and the context of its artifacts. The header must match the
+/// config and the PCS, and its hash must match the content.
+pub fn read_computation_graph<C: Config, PCS: ExpanderPCS<C::FieldConfig>, R: Read>(
+    mut reader: R,
+) -> Result<(ComputationGraph<C>, ArtifactContext), Error> {
+    let header = ArtifactHeader::deserialize_from(&mut reader)?;
+    let context = ArtifactContext {
+        config_id: C::CONFIG_ID,
+        pcs_name: PCS::NAME.to_string(),
+        graph_hash: header.context.graph_hash,
+    };
+    header.check(ArtifactKind::ComputationGraph, &context)?;
+    let computation_graph = ComputationGraph::<C>::deserialize_from(&mut reader)
+        .map_err(|e| malformed(format!("{e:?}")))?;
+    check_fully_read(reader)?;
+    if computation_graph_hash(&computation_graph) != context.graph_hash {
+        return Err(malformed(
+            "computation graph doesn't match the hash in its header".to_string(),
+        ));
+    }
+    Ok((computation_graph, context))
+}
+
+pub fn save_artifact<T: Artifact, P: AsRef<Path>>(
+    path: P,
+    context: &ArtifactContext,
+    value: &T,
+) -> Result<(), Error> {
+    let mut bytes = Vec::new();
+    write_artifact(&mut bytes, context, value)?;
+    fs::write(path.as_ref(), bytes).map_err(|e| io_error("write", e))
+}
+
+pub fn load_artifact<T: Artifact, P: AsRef<Path>>(
+    path: P,
+    context: &ArtifactContext,
+) -> Result<T, Error> {
+    let bytes = fs::read(path.as_ref()).map_err(|e| io_error("read", e))?;
+    read_artifact(bytes.as_slice(), context)
+}
+
+pub fn load_computation_graph<C: Config, PCS: ExpanderPCS<C::FieldConfig>, P: AsRef<Path>>(
+    path: P,
+) -> Result<(ComputationGraph<C>, ArtifactContext), Error> {
+    let bytes = fs::read(path.as_ref()).map_err(|e| io_error("read", e))?;
+    read_computation_graph::<C, PCS, _>(bytes.as_slice())
+}
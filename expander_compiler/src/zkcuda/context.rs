@@ -1,5 +1,8 @@
 use arith::SimdField;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    slice::ParallelSlice,
+};
 use serdes::ExpSerde;
 
 use crate::{
@@ -11,7 +14,7 @@ use crate::{
 };
 
 use super::{
-    kernel::{compile_primitive, Kernel, KernelPrimitive},
+    kernel::{compile_primitive, compile_with_spec_and_shapes, IOVecSpec, Kernel, KernelPrimitive},
     shape::{
         keep_shape_products_until, keep_shape_since, merge_shape_products, prefix_products,
         prefix_products_to_shape, shape_prepend, shape_vec_len, shape_vec_padded_len, BitOrder,
@@ -54,6 +57,18 @@ pub struct ProofTemplate {
     pub commitment_bit_orders: Vec<BitOrder>,
     pub parallel_count: usize,
     pub is_broadcast: Vec<bool>,
+    pub public_commitments: Vec<PublicCommitment>,
+    // whether the first commitment is a mask selecting the active parallel instances
+    pub is_masked: bool,
+}
+
+/// A commitment of a proof template whose values are known to the verifier.
+#[derive(PartialEq, Eq, Clone, Debug, ExpSerde)]
+pub struct PublicCommitment {
+    // index in commitment_indices
+    pub position: usize,
+    // shape of the device memory before padding
+    pub shape: Shape,
 }
 
 impl ProofTemplate {
@@ -72,6 +87,27 @@ impl ProofTemplate {
     pub fn is_broadcast(&self) -> &[bool] {
         &self.is_broadcast
     }
+    pub fn public_commitments(&self) -> &[PublicCommitment] {
+        &self.public_commitments
+    }
+    pub fn is_masked(&self) -> bool {
+        self.is_masked
+    }
+}
+
+// device memory ids and shapes of the public commitments, in order of first use
+fn public_device_memories(proof_templates: &[ProofTemplate]) -> Vec<(usize, &Shape)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut res = Vec::new();
+    for template in proof_templates.iter() {
+        for pc in template.public_commitments.iter() {
+            let id = template.commitment_indices[pc.position];
+            if seen.insert(id) {
+                res.push((id, &pc.shape));
+            }
+        }
+    }
+    res
 }
 
 #[derive(Default, Clone, Debug, ExpSerde)]
@@ -91,6 +127,32 @@ impl<C: Config> ComputationGraph<C> {
     pub fn proof_templates(&self) -> &[ProofTemplate] {
         &self.proof_templates
     }
+
+    /// Returns the ids of the committed device memories holding public inputs or outputs,
+    /// and their shapes before padding. Each one is listed once, in order of first use.
+    pub fn public_commitments(&self) -> Vec<(usize, &Shape)> {
+        public_device_memories(&self.proof_templates)
+    }
+
+    /// Pads the public values, given in the order of `public_commitments`, to the committed
+    /// layout. Returns None if their number or lengths don't match.
+    pub fn pad_public_values(
+        &self,
+        public_values: &[Vec<SIMDField<C>>],
+    ) -> Option<Vec<(usize, Vec<SIMDField<C>>)>> {
+        let public_commitments = self.public_commitments();
+        if public_commitments.len() != public_values.len() {
+            return None;
+        }
+        let mut res = Vec::with_capacity(public_values.len());
+        for ((id, shape), values) in public_commitments.into_iter().zip(public_values.iter()) {
+            if shape_vec_len(shape) != values.len() {
+                return None;
+            }
+            res.push((id, multi_dimension_data_padding(shape, values)));
+        }
+        Some(res)
+    }
 }
 
 pub trait ComputationGraphDefine<C: Config> {
@@ -120,10 +182,68 @@ pub struct Context<C: Config, H: HintCaller<CircuitField<C>> = EmptyHintCaller>
     hint_caller: H,
     // Pointer-identity cache for kernel_primitives.add() — avoids O(circuit_size) Hash
     kernel_ptr_cache: std::collections::HashMap<usize, usize>,
+    // kernels from KernelPrimitive::repeated, by (id of the kernel in kernel_primitives,
+    // iterations). They are boxed so that their pointers stay valid for kernel_ptr_cache
+    repeated_kernels: std::collections::HashMap<(usize, usize), Box<KernelPrimitive<C>>>,
+    // kernels copying device memories for slice, concat, stack and gather, by
+    // (input shapes, operation)
+    copy_kernels: std::collections::HashMap<CopyKernelKey, Box<KernelPrimitive<C>>>,
     // current state of the context
     state: ContextState,
 }
 
+type CopyKernelKey = (Vec<Shape>, CopyOp);
+
+// what a copy kernel computes from its inputs
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum CopyOp {
+    Gather { axis: usize, indices: Vec<usize> },
+    Concat { axis: usize },
+}
+
+impl CopyOp {
+    fn axis(&self) -> usize {
+        match self {
+            CopyOp::Gather { axis, .. } | CopyOp::Concat { axis } => *axis,
+        }
+    }
+
+    fn output_shape(&self, input_shapes: &[Shape]) -> Shape {
+        let mut shape = input_shapes[0].clone();
+        match self {
+            CopyOp::Gather { axis, indices } => shape[*axis] = indices.len(),
+            CopyOp::Concat { axis } => shape[*axis] = input_shapes.iter().map(|s| s[*axis]).sum(),
+        }
+        shape
+    }
+
+    // the i-th output is the sources[i].1-th value of the sources[i].0-th input
+    fn sources(&self, input_shapes: &[Shape]) -> Vec<(usize, usize)> {
+        let shape = &input_shapes[0];
+        let axis = self.axis();
+        let mut sources = Vec::new();
+        let outer = shape_vec_len(&shape[..axis]);
+        let inner = shape_vec_len(&shape[axis + 1..]);
+        for o in 0..outer {
+            match self {
+                CopyOp::Gather { indices, .. } => {
+                    let dim = shape[axis];
+                    for &i in indices {
+                        sources.extend((0..inner).map(|t| (0, (o * dim + i) * inner + t)));
+                    }
+                }
+                CopyOp::Concat { .. } => {
+                    for (k, s) in input_shapes.iter().enumerate() {
+                        let dim = s[axis];
+                        sources.extend((o * dim * inner..(o + 1) * dim * inner).map(|j| (k, j)));
+                    }
+                }
+            }
+        }
+        sources
+    }
+}
+
 impl<C: Config> Default for Context<C> {
     fn default() -> Self {
         Self::new(EmptyHintCaller)
@@ -137,6 +257,15 @@ fn ensure_handle(handle: DeviceMemoryHandle) -> DeviceMemoryHandleRaw {
     }
 }
 
+fn handle_shape(handle: &DeviceMemoryHandle) -> Result<Shape, Error> {
+    match handle {
+        Some(handle) => Ok(handle.shape_history.shape()),
+        None => Err(Error::InvalidShape(
+            "empty device memory handle".to_string(),
+        )),
+    }
+}
+
 fn pack_vec<C: Config>(v: &[CircuitField<C>]) -> Vec<SIMDField<C>> {
     v.iter()
         .map(|x| {
@@ -149,6 +278,96 @@ fn pack_vec<C: Config>(v: &[CircuitField<C>]) -> Vec<SIMDField<C>> {
         .collect::<Vec<_>>()
 }
 
+// outputs of an instance of a masked kernel where the mask is 0, in the layout of the outputs
+// of ir_for_calling
+fn masked_out_outputs<C: Config>(
+    kernel: &KernelPrimitive<C>,
+    ir_inputs: &[SIMDField<C>],
+) -> Vec<SIMDField<C>> {
+    // the outputs start with the inputs, and the outputs which aren't inputs are zero
+    let n_in = *kernel.ir_input_offsets().last().unwrap();
+    let mut outputs = vec![SIMDField::<C>::zero(); *kernel.ir_output_offsets().last().unwrap()];
+    outputs[..n_in].copy_from_slice(&ir_inputs[..n_in]);
+    for (i, spec) in kernel.io_specs().iter().enumerate() {
+        if spec.is_input && spec.is_output {
+            let input_start = kernel.ir_input_offsets()[i];
+            let output_start = kernel.ir_output_offsets()[i];
+            outputs[output_start..output_start + spec.len]
+                .copy_from_slice(&ir_inputs[input_start..input_start + spec.len]);
+        }
+    }
+    outputs
+}
+
+// Evaluates a masked kernel, whose first input is the mask, on a pack of instances. `eval` runs
+// on the whole pack if every lane is active. Otherwise it runs on each active lane alone, so
+// that the hints and assertions never see the placeholder inputs of the inactive lanes, whose
+// outputs come from `inactive`.
+fn eval_masked_lanes<C: Config>(
+    inputs: Vec<SIMDField<C>>,
+    eval: impl Fn(Vec<SIMDField<C>>) -> Result<Vec<SIMDField<C>>, Error>,
+    inactive: impl Fn(&[SIMDField<C>]) -> Vec<SIMDField<C>>,
+) -> Result<Vec<SIMDField<C>>, Error> {
+    let mask = inputs[0].unpack();
+    if mask.iter().all(|x| !x.is_zero()) {
+        return eval(inputs);
+    }
+    if mask.iter().all(|x| x.is_zero()) {
+        return Ok(inactive(&inputs));
+    }
+    let unpacked = inputs.iter().map(|x| x.unpack()).collect::<Vec<_>>();
+    let mut lanes_outputs = Vec::with_capacity(mask.len());
+    for (lane, m) in mask.iter().enumerate() {
+        let lane_inputs = pack_vec::<C>(&unpacked.iter().map(|x| x[lane]).collect::<Vec<_>>());
+        let lane_outputs = if m.is_zero() {
+            inactive(&lane_inputs)
+        } else {
+            eval(lane_inputs)?
+        };
+        lanes_outputs.push(unpack_vec::<C>(&lane_outputs));
+    }
+    let num_outputs = lanes_outputs[0].len();
+    Ok((0..num_outputs)
+        .map(|i| SIMDField::<C>::pack(&lanes_outputs.iter().map(|x| x[i]).collect::<Vec<_>>()))
+        .collect())
+}
+
+// If `indices` has a power of two length 2^k and maps every index to the index with the same
+// bits in another order, returns the axes transposing the k bits, as axes of length 2 with the
+// most significant bit first.
+fn bit_permutation_axes(indices: &[usize]) -> Option<Vec<usize>> {
+    if !indices.len().is_power_of_two() || indices.len() < 2 {
+        return None;
+    }
+    let k = indices.len().trailing_zeros() as usize;
+    let mut axes = Vec::with_capacity(k);
+    for j in 0..k {
+        let target = indices[1 << (k - 1 - j)];
+        if !target.is_power_of_two() || target >= indices.len() {
+            return None;
+        }
+        axes.push(k - 1 - target.trailing_zeros() as usize);
+    }
+    let mut seen = vec![false; k];
+    for &a in axes.iter() {
+        if seen[a] {
+            return None;
+        }
+        seen[a] = true;
+    }
+    let permuted = |i: usize| {
+        (0..k)
+            .filter(|&j| (i >> (k - 1 - j)) & 1 == 1)
+            .map(|j| 1 << (k - 1 - axes[j]))
+            .sum::<usize>()
+    };
+    if indices.iter().enumerate().all(|(i, &x)| x == permuted(i)) {
+        Some(axes)
+    } else {
+        None
+    }
+}
+
 fn unpack_vec<C: Config>(v: &[SIMDField<C>]) -> Vec<CircuitField<C>> {
     v.iter().map(|x| x.unpack()[0]).collect()
 }
@@ -228,6 +447,8 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             hint_caller,
             state: ContextState::ComputationGraphNotDone,
             kernel_ptr_cache: std::collections::HashMap::new(),
+            repeated_kernels: std::collections::HashMap::new(),
+            copy_kernels: std::collections::HashMap::new(),
         }
     }
 
@@ -336,7 +557,11 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
     ) -> Result<(), Error> {
         assert_eq!(self.state, ContextState::ComputationGraphNotDone);
         if kernel.io_shapes().len() != ios.len() {
-            panic!("Invalid number of inputs/outputs");
+            return Err(Error::InvalidShape(format!(
+                "invalid number of inputs/outputs: kernel expects {}, got {}",
+                kernel.io_shapes().len(),
+                ios.len()
+            )));
         }
         let mut is_broadcast = Vec::with_capacity(ios.len());
         for (i, ((kernel_shape, io), spec)) in kernel
@@ -357,8 +582,13 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             let io_shape = if let Some(handle) = io {
                 handle.shape_history.shape()
             } else {
-                panic!("Missing input at index {i}")
+                return Err(Error::InvalidShape(format!("missing input at index {i}")));
             };
+            if spec.is_public && !io.as_ref().unwrap().shape_history.is_identity_permutation() {
+                return Err(Error::InvalidShape(format!(
+                    "public input at index {i} must not be transposed"
+                )));
+            }
             match check_shape_compat(kernel_shape, &io_shape, num_parallel) {
                 Some(ib) => {
                     let isl = io
@@ -374,22 +604,30 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
                     is_broadcast.push(ib)
                 }
                 None => {
-                    panic!(
+                    return Err(Error::InvalidShape(format!(
                         "Incompatible shapes: want {:?}, got {:?}, num_parallel={} (Hint: if you want to broadcast, use {:?}, otherwise use {:?})",
                         kernel_shape,
                         io_shape,
                         num_parallel,
                         kernel_shape,
                         shape_prepend(kernel_shape, num_parallel)
-                    );
+                    )));
                 }
             }
         }
         for (io_spec, ib) in kernel.io_specs().iter().zip(is_broadcast.iter()) {
             if io_spec.is_output && *ib {
-                panic!("Output is broadcasted, but it shouldn't be");
+                return Err(Error::InvalidShape(
+                    "output is broadcasted, but it shouldn't be".to_string(),
+                ));
             }
         }
+        if kernel.is_masked() && is_broadcast[0] {
+            return Err(Error::InvalidShape(
+                "mask is broadcasted, but it should have one value per parallel instance"
+                    .to_string(),
+            ));
+        }
 
         // Use pointer-identity cache to skip O(circuit_size) Hash+Clone.
         // KernelPrimitive's derived Hash is pathologically slow (250× vs raw hash)
@@ -473,9 +711,23 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
         let ir_outputs_per_parallel: Vec<Result<Vec<SIMDField<C>>, Error>> = ir_inputs_per_parallel
             .into_par_iter()
             .map(|ir_inputs| {
-                kernel
-                    .ir_for_calling()
-                    .eval_safe_simd(ir_inputs, &[], &self.hint_caller)
+                let eval = |ir_inputs: Vec<SIMDField<C>>| {
+                    kernel
+                        .ir_for_calling()
+                        .eval_safe_simd(ir_inputs, &[], &self.hint_caller)
+                };
+                if kernel.is_masked() {
+                    let mask = ir_inputs[0];
+                    if mask * mask != mask {
+                        return Err(Error::UserError(
+                            "mask values must be 0 or 1".to_string(),
+                        ));
+                    }
+                    return eval_masked_lanes::<C>(ir_inputs, eval, |ir_inputs| {
+                        masked_out_outputs(kernel, ir_inputs)
+                    });
+                }
+                eval(ir_inputs)
             })
             .collect();
         for ir_outputs in ir_outputs_per_parallel {
@@ -535,6 +787,291 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
         Ok(())
     }
 
+    /// Calls a kernel from `KernelPrimitive::masked` on the parallel instances where `mask` is 1.
+    /// The mask must have one value, 0 or 1, per parallel instance.
+    pub fn call_kernel_masked(
+        &mut self,
+        kernel: &KernelPrimitive<C>,
+        num_parallel: usize,
+        mask: &DeviceMemoryHandle,
+        ios: &mut [DeviceMemoryHandle],
+    ) -> Result<(), Error> {
+        if !kernel.is_masked() {
+            return Err(Error::UserError(
+                "call_kernel_masked expects a kernel from KernelPrimitive::masked".to_string(),
+            ));
+        }
+        let mut all_ios = Vec::with_capacity(ios.len() + 1);
+        all_ios.push(mask.clone());
+        all_ios.extend_from_slice(ios);
+        self.call_kernel(kernel, num_parallel, &mut all_ios)?;
+        ios.clone_from_slice(&all_ios[1..]);
+        Ok(())
+    }
+
+    /// Runs `kernel` `n` times on `state`, feeding the output of each iteration into the next
+    /// one, and replaces `state` with the final state. The kernel must have a single
+    /// input-output argument, and `state` one value of it per parallel instance.
+    ///
+    /// All the iterations share a single proof template, and the intermediate states are
+    /// committed together, so the computation graph doesn't grow with `n`.
+    pub fn repeat_kernel(
+        &mut self,
+        kernel: &KernelPrimitive<C>,
+        n: usize,
+        state: &mut DeviceMemoryHandle,
+    ) -> Result<(), Error> {
+        assert_eq!(self.state, ContextState::ComputationGraphNotDone);
+        if n == 0 {
+            return Err(Error::UserError(
+                "repeat_kernel needs at least one iteration".to_string(),
+            ));
+        }
+        let handle = match state {
+            Some(handle) => handle.clone(),
+            None => return Err(Error::InvalidShape("missing state".to_string())),
+        };
+        let kernel_shape = &kernel.io_shapes()[0];
+        let state_shape = handle.shape_history.shape();
+        if state_shape.len() != kernel_shape.len() + 1 || state_shape[1..] != kernel_shape[..] {
+            return Err(Error::InvalidShape(format!(
+                "Incompatible shapes: want [num_parallel] + {kernel_shape:?}, got {state_shape:?}"
+            )));
+        }
+        let num_parallel = state_shape[0];
+        if n == 1 {
+            return self.call_kernel(kernel, num_parallel, std::slice::from_mut(state));
+        }
+
+        // the address of the kernel may be reused by another one after it's dropped, so the key
+        // is its id, found by content
+        let key = (self.kernel_primitives.add(kernel), n);
+        if !self.repeated_kernels.contains_key(&key) {
+            let repeated = kernel.repeated(n)?;
+            self.repeated_kernels.insert(key, Box::new(repeated));
+        }
+
+        // evaluate the first n - 1 iterations to get the intermediate states
+        let len = kernel.io_specs()[0].len;
+        let input_start = kernel.ir_input_offsets()[0];
+        let output_start = kernel.ir_output_offsets()[0];
+        let values = handle
+            .shape_history
+            .permute_vec(&self.device_memories[handle.id].values);
+        let traces: Vec<Result<Vec<SIMDField<C>>, Error>> = values
+            .par_chunks(len)
+            .map(|initial| {
+                let mut trace = Vec::with_capacity((n - 1) * len);
+                let mut cur = initial.to_vec();
+                for _ in 0..n - 1 {
+                    let mut ir_inputs =
+                        vec![SIMDField::<C>::zero(); kernel.ir_for_calling().input_size()];
+                    ir_inputs[input_start..input_start + len].copy_from_slice(&cur);
+                    let ir_outputs = kernel.ir_for_calling().eval_safe_simd(
+                        ir_inputs,
+                        &[],
+                        &self.hint_caller,
+                    )?;
+                    cur = ir_outputs[output_start..output_start + len].to_vec();
+                    trace.extend_from_slice(&cur);
+                }
+                Ok(trace)
+            })
+            .collect();
+        let mut trace_values = Vec::with_capacity(num_parallel * (n - 1) * len);
+        for trace in traces {
+            trace_values.extend(trace?);
+        }
+        let trace = make_device_mem(
+            &mut self.device_memories,
+            trace_values,
+            shape_prepend(&shape_prepend(kernel_shape, n - 1), num_parallel),
+        );
+
+        // the kernel is taken out of the map while calling it, its box keeps the same address
+        let repeated = self.repeated_kernels.remove(&key).unwrap();
+        let mut ios = [state.clone(), trace];
+        let res = self.call_kernel(&repeated, num_parallel, &mut ios);
+        self.repeated_kernels.insert(key, repeated);
+        res?;
+        *state = ios[0].clone();
+        Ok(())
+    }
+
+    /// Selects `range` along `axis` of `handle`.
+    pub fn slice(
+        &mut self,
+        handle: &DeviceMemoryHandle,
+        axis: usize,
+        range: std::ops::Range<usize>,
+    ) -> Result<DeviceMemoryHandle, Error> {
+        let indices: Vec<usize> = range.collect();
+        self.gather(handle, axis, &indices)
+    }
+
+    /// Selects the entries at `indices` along `axis` of `handle`, in this order. Indices may
+    /// repeat. If the axis has a power of two length and `indices` only permute the bits of the
+    /// index, the result is a transposed view of `handle`, and nothing is copied.
+    pub fn gather(
+        &mut self,
+        handle: &DeviceMemoryHandle,
+        axis: usize,
+        indices: &[usize],
+    ) -> Result<DeviceMemoryHandle, Error> {
+        let shape = handle_shape(handle)?;
+        if axis >= shape.len() {
+            return Err(Error::InvalidShape(format!(
+                "axis {axis} out of range for shape {shape:?}"
+            )));
+        }
+        let dim = shape[axis];
+        if let Some(&i) = indices.iter().find(|&&i| i >= dim) {
+            return Err(Error::InvalidShape(format!(
+                "index {i} out of range for axis {axis} of shape {shape:?}"
+            )));
+        }
+        if indices.iter().copied().eq(0..dim) {
+            return Ok(handle.clone());
+        }
+        if let Some(bit_axes) = bit_permutation_axes(indices) {
+            // split the axis into one axis of length 2 per bit, most significant first
+            let k = bit_axes.len();
+            let mut split_shape = shape[..axis].to_vec();
+            split_shape.extend(std::iter::repeat_n(2, k));
+            split_shape.extend_from_slice(&shape[axis + 1..]);
+            let mut axes: Vec<usize> = (0..axis).collect();
+            axes.extend(bit_axes.iter().map(|&a| axis + a));
+            axes.extend(axis + k..split_shape.len());
+            return Ok(handle.reshape(&split_shape).transpose(&axes).reshape(&shape));
+        }
+        self.copy_device_memories(
+            vec![handle.clone()],
+            CopyOp::Gather {
+                axis,
+                indices: indices.to_vec(),
+            },
+        )
+    }
+
+    /// Concatenates `handles` along `axis`. All other dimensions must match.
+    pub fn concat(
+        &mut self,
+        handles: &[DeviceMemoryHandle],
+        axis: usize,
+    ) -> Result<DeviceMemoryHandle, Error> {
+        if handles.is_empty() {
+            return Err(Error::UserError("nothing to concatenate".to_string()));
+        }
+        let shapes = handles
+            .iter()
+            .map(handle_shape)
+            .collect::<Result<Vec<_>, _>>()?;
+        let shape = &shapes[0];
+        if axis >= shape.len() {
+            return Err(Error::InvalidShape(format!(
+                "axis {axis} out of range for shape {shape:?}"
+            )));
+        }
+        for other in shapes.iter().skip(1) {
+            if other.len() != shape.len()
+                || other[..axis] != shape[..axis]
+                || other[axis + 1..] != shape[axis + 1..]
+            {
+                return Err(Error::InvalidShape(format!(
+                    "can't concatenate shapes {shape:?} and {other:?} along axis {axis}"
+                )));
+            }
+        }
+        if handles.len() == 1 {
+            return Ok(handles[0].clone());
+        }
+        self.copy_device_memories(handles.to_vec(), CopyOp::Concat { axis })
+    }
+
+    /// Stacks `handles` along a new axis at position `axis`. All shapes must match.
+    pub fn stack(
+        &mut self,
+        handles: &[DeviceMemoryHandle],
+        axis: usize,
+    ) -> Result<DeviceMemoryHandle, Error> {
+        let mut expanded = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let mut shape = handle_shape(handle)?;
+            if axis > shape.len() {
+                return Err(Error::InvalidShape(format!(
+                    "axis {axis} out of range for stacking shape {shape:?}"
+                )));
+            }
+            shape.insert(axis, 1);
+            expanded.push(handle.reshape(&shape));
+        }
+        self.concat(&expanded, axis)
+    }
+
+    // Apart from the bit permutations handled by `gather`, selecting entries of a device memory,
+    // or combining several of them, can't be expressed as a permutation of one committed device
+    // memory, so the result is computed by a copy kernel. This also holds for slices aligned to
+    // powers of two, and for stacking along a new leading axis: the proving systems open every
+    // commitment as a whole vector, so a device memory can't be a view of a part of another one.
+    fn copy_device_memories(
+        &mut self,
+        handles: Vec<DeviceMemoryHandle>,
+        op: CopyOp,
+    ) -> Result<DeviceMemoryHandle, Error> {
+        let input_shapes = handles
+            .iter()
+            .map(handle_shape)
+            .collect::<Result<Vec<_>, _>>()?;
+        let shape = op.output_shape(&input_shapes);
+        if shape_vec_len(&shape) == 0 {
+            return Err(Error::InvalidShape(format!(
+                "empty device memories are not supported, got shape {shape:?}"
+            )));
+        }
+        let key = (input_shapes, op);
+        // the kernel is taken out of the map while calling it, its box keeps the same address
+        let kernel = match self.copy_kernels.remove(&key) {
+            Some(kernel) => kernel,
+            None => {
+                let (input_shapes, op) = &key;
+                let sources = op.sources(input_shapes);
+                let mut io_specs: Vec<IOVecSpec> = input_shapes
+                    .iter()
+                    .map(|s| IOVecSpec {
+                        len: shape_vec_len(s),
+                        is_input: true,
+                        is_output: false,
+                        is_public: false,
+                    })
+                    .collect();
+                io_specs.push(IOVecSpec {
+                    len: sources.len(),
+                    is_input: false,
+                    is_output: true,
+                    is_public: false,
+                });
+                let mut io_shapes = input_shapes.clone();
+                io_shapes.push(shape.clone());
+                let n = input_shapes.len();
+                Box::new(compile_with_spec_and_shapes(
+                    |_api, io| {
+                        for (i, &(k, j)) in sources.iter().enumerate() {
+                            io[n][i] = io[k][j];
+                        }
+                    },
+                    &io_specs,
+                    &io_shapes,
+                )?)
+            }
+        };
+        let mut ios = handles;
+        ios.push(None);
+        let res = self.call_kernel(&kernel, 1, &mut ios);
+        self.copy_kernels.insert(key, kernel);
+        res?;
+        Ok(ios.pop().unwrap().reshape(&shape))
+    }
+
     fn get_current_device_memory_shapes(&self) -> Vec<Shape> {
         self.device_memories
             .iter()
@@ -631,9 +1168,26 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             for (i, kernel) in cg.kernels.iter().enumerate() {
                 assert_eq!(self.kernels.add(kernel), i);
             }
-            assert!(cg.commitments_lens.len() >= self.device_memories.len());
-            for (dm_shape, cm_len) in dm_shapes.iter().zip(cg.commitments_lens.iter()) {
-                assert_eq!(shape_vec_padded_len(dm_shape), *cm_len);
+            if cg.commitments_lens.len() < self.device_memories.len() {
+                return Err(Error::InvalidShape(format!(
+                    "computation graph has {} commitments, but the context has {} device memories",
+                    cg.commitments_lens.len(),
+                    self.device_memories.len()
+                )));
+            }
+            for (i, (dm_shape, cm_len)) in dm_shapes
+                .iter()
+                .zip(cg.commitments_lens.iter())
+                .enumerate()
+            {
+                if shape_vec_padded_len(dm_shape) != *cm_len {
+                    return Err(Error::InvalidShape(format!(
+                        "device memory {} has padded length {}, but the computation graph expects {}",
+                        i,
+                        shape_vec_padded_len(dm_shape),
+                        cm_len
+                    )));
+                }
             }
             (
                 Some(cg.kernels),
@@ -700,6 +1254,7 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             let mut commitment_bit_orders: Vec<BitOrder> = Vec::new();
             let mut any_shape = None;
             let mut is_broadcast = Vec::new();
+            let mut public_commitments = Vec::new();
             for (((spec, pad_shape), handle), &ib) in kernel_primitive
                 .io_specs()
                 .iter()
@@ -709,7 +1264,14 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             {
                 if spec.is_input {
                     let shape = pad_shape.as_ref().unwrap();
-                    commitment_indices.push(handle.as_ref().unwrap().id);
+                    let id = handle.as_ref().unwrap().id;
+                    if spec.is_public {
+                        public_commitments.push(PublicCommitment {
+                            position: commitment_indices.len(),
+                            shape: dm_shapes[id].clone(),
+                        });
+                    }
+                    commitment_indices.push(id);
                     commitment_bit_orders.push(shape.1.clone());
                     is_broadcast.push(ib);
                     if !ib {
@@ -726,7 +1288,14 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
             {
                 if spec.is_output {
                     let shape = pad_shape.as_ref().unwrap();
-                    commitment_indices.push(handle.as_ref().unwrap().id);
+                    let id = handle.as_ref().unwrap().id;
+                    if spec.is_public {
+                        public_commitments.push(PublicCommitment {
+                            position: commitment_indices.len(),
+                            shape: dm_shapes[id].clone(),
+                        });
+                    }
+                    commitment_indices.push(id);
                     commitment_bit_orders.push(shape.1.clone());
                     is_broadcast.push(ib);
                     if !ib {
@@ -756,6 +1325,8 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
                 commitment_bit_orders,
                 parallel_count: dim0_len,
                 is_broadcast,
+                public_commitments,
+                is_masked: kernel_primitive.is_masked(),
             });
         }
 
@@ -902,10 +1473,22 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
                 }
                 hints_inputs_per_parallel.push(inputs);
             }
+            // the hints of inactive instances are unconstrained, so they are not computed
+            let num_hints = hint_solver.circuits[&0].outputs.len();
             let hints_per_parallel: Vec<Result<Vec<SIMDField<C>>, Error>> =
                 hints_inputs_per_parallel
                     .into_par_iter()
-                    .map(|inputs| hint_solver.eval_safe_simd(inputs, &[], &self.hint_caller))
+                    .map(|inputs| {
+                        let eval = |inputs: Vec<SIMDField<C>>| {
+                            hint_solver.eval_safe_simd(inputs, &[], &self.hint_caller)
+                        };
+                        if kernel_primitive.is_masked() {
+                            return eval_masked_lanes::<C>(inputs, eval, |_| {
+                                vec![SIMDField::<C>::zero(); num_hints]
+                            });
+                        }
+                        eval(inputs)
+                    })
                     .collect();
             let mut hints_all = Vec::new();
             for hints in hints_per_parallel {
@@ -948,6 +1531,20 @@ impl<C: Config, H: HintCaller<CircuitField<C>>> Context<C, H> {
         self.export_device_memories_impl()
     }
 
+    /// Exports the values of the public inputs and outputs, in the order of
+    /// `ComputationGraph::public_commitments`. The verifier checks the proof against them.
+    pub fn export_public_values(&self) -> Vec<Vec<SIMDField<C>>> {
+        assert_ne!(
+            self.state,
+            ContextState::ComputationGraphNotDone,
+            "Please compile or load the computation graph before exporting public values."
+        );
+        public_device_memories(&self.proof_templates)
+            .into_iter()
+            .map(|(id, _)| self.device_memories[id].values.clone())
+            .collect()
+    }
+
     fn export_device_memories_impl(&self) -> Vec<Vec<SIMDField<C>>> {
         use rayon::prelude::*;
         self.device_memories
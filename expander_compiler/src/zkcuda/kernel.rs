@@ -1,11 +1,16 @@
 use crate::circuit::input_mapping::EMPTY;
-use crate::circuit::ir::common::Instruction;
+use crate::circuit::ir::common::{Instruction as _, TracedConstraint};
+use crate::circuit::ir::expr::{LinComb, LinCombTerm};
+use crate::circuit::ir::hint_normalized::Instruction;
 use crate::compile::{
     compile_step_1, compile_step_2, compile_step_3, print_ir_stats, print_layered_circuit_stats,
     CompileOptions,
 };
-use crate::frontend::{BasicAPI, Error, Variable, API};
-use crate::zkcuda::shape::{shape_padded_mapping, shape_vec_len, shape_vec_padded_len, Shape};
+use crate::field::FieldArith;
+use crate::frontend::{BasicAPI, CircuitField, Error, Variable, API};
+use crate::zkcuda::shape::{
+    shape_padded_mapping, shape_prepend, shape_vec_len, shape_vec_padded_len, Shape,
+};
 use crate::{
     circuit::{
         config::Config,
@@ -29,6 +34,8 @@ pub struct KernelPrimitive<C: Config> {
 
     io_specs: Vec<IOVecSpec>,
     io_shapes: Vec<Shape>,
+    // The first input is a mask selecting the active parallel instances, see `masked`.
+    is_masked: bool,
 }
 
 impl<C: Config> KernelPrimitive<C> {
@@ -50,6 +57,219 @@ impl<C: Config> KernelPrimitive<C> {
     pub fn io_shapes(&self) -> &[Shape] {
         &self.io_shapes
     }
+    pub fn is_masked(&self) -> bool {
+        self.is_masked
+    }
+
+    /// Returns a copy of the kernel with an additional first input, a boolean mask with one
+    /// value per parallel instance. The constraints only apply to the instances where the mask
+    /// is 1. In the other instances, outputs are zero and input-outputs keep their values.
+    pub fn masked(&self) -> Result<Self, Error> {
+        if self.is_masked {
+            return Err(Error::UserError("kernel is already masked".to_string()));
+        }
+        let mut r = self.ir_for_later_compilation.clone();
+        for (id, circuit) in r.circuits.iter_mut() {
+            *circuit = mask_circuit(circuit, *id == 0);
+        }
+        let n_in = self.ir_input_offsets.last().unwrap() + 1;
+        let mask = 1;
+        let old_outputs = &self.ir_for_later_compilation.circuits[&0].outputs;
+        let one = CircuitField::<C>::one();
+        let r0 = r.circuits.get_mut(&0).unwrap();
+        let mut num_vars = r0.get_num_variables();
+        let mut push = |instructions: &mut Vec<Instruction<C>>, insn: Instruction<C>| {
+            instructions.push(insn);
+            num_vars += 1;
+            num_vars
+        };
+        let mask_sq = push(&mut r0.instructions, Instruction::Mul(vec![mask, mask]));
+        let not_bool = push(
+            &mut r0.instructions,
+            lin_comb(&[(mask_sq, one), (mask, -one)]),
+        );
+        r0.constraints.push(not_bool.into());
+        let mut outputs: Vec<usize> = (1..=n_in).collect();
+        for (i, spec) in self.io_specs.iter().enumerate() {
+            if !spec.is_output {
+                continue;
+            }
+            for k in 0..spec.len {
+                let pos = self.ir_output_offsets[i] + k;
+                // variables are shifted by the mask
+                let out = old_outputs[pos] + 1;
+                let expected = pos + 2;
+                let selected = if spec.is_input {
+                    let input = self.ir_input_offsets[i] + k + 2;
+                    let diff = push(&mut r0.instructions, lin_comb(&[(out, one), (input, -one)]));
+                    let masked_diff =
+                        push(&mut r0.instructions, Instruction::Mul(vec![mask, diff]));
+                    push(
+                        &mut r0.instructions,
+                        lin_comb(&[(input, one), (masked_diff, one)]),
+                    )
+                } else {
+                    push(&mut r0.instructions, Instruction::Mul(vec![mask, out]))
+                };
+                let eq = push(
+                    &mut r0.instructions,
+                    lin_comb(&[(expected, one), (selected, -one)]),
+                );
+                r0.constraints.push(eq.into());
+                outputs.push(selected);
+            }
+        }
+        r0.outputs = outputs;
+        r.validate()
+            .map_err(|e| e.prepend("masked kernel circuit invalid"))?;
+
+        let mut ir_input_offsets = vec![0];
+        ir_input_offsets.extend(self.ir_input_offsets.iter().map(|x| x + 1));
+        let mut ir_output_offsets = vec![n_in];
+        ir_output_offsets.extend(self.ir_output_offsets.iter().map(|x| x + 1));
+        let mut io_specs = vec![IOVecSpec {
+            len: 1,
+            is_input: true,
+            is_output: false,
+            is_public: false,
+        }];
+        io_specs.extend(self.io_specs.iter().cloned());
+        let mut io_shapes = vec![vec![]];
+        io_shapes.extend(self.io_shapes.iter().cloned());
+        Ok(KernelPrimitive {
+            ir_for_calling: build_ir_for_calling(&r, n_in)?,
+            ir_for_later_compilation: r,
+            ir_input_offsets,
+            ir_output_offsets,
+            io_specs,
+            io_shapes,
+            is_masked: true,
+        })
+    }
+
+    /// Returns a kernel running this one `n` times, where the output of each iteration is the
+    /// input of the next one. This kernel must have a single input-output argument, the state.
+    /// The returned kernel has a second input holding the `n - 1` intermediate states, so all
+    /// the iterations are proven at once, with a single commitment for these states.
+    pub fn repeated(&self, n: usize) -> Result<Self, Error> {
+        if n < 2 {
+            return Err(Error::UserError(
+                "a repeated kernel needs at least 2 iterations".to_string(),
+            ));
+        }
+        if self.is_masked
+            || self.io_specs.len() != 1
+            || !self.io_specs[0].is_input
+            || !self.io_specs[0].is_output
+        {
+            return Err(Error::UserError(
+                "only kernels with a single input-output argument can be repeated".to_string(),
+            ));
+        }
+        let len = self.io_specs[0].len;
+        let ir_for_later_compilation = chain_calls(&self.ir_for_later_compilation, n, len, false);
+        ir_for_later_compilation
+            .validate()
+            .map_err(|e| e.prepend("repeated kernel circuit invalid"))?;
+        // the intermediate states are known when calling, so only the last iteration is needed
+        let ir_for_calling = chain_calls(&self.ir_for_calling, n, len, true);
+        ir_for_calling
+            .validate()
+            .map_err(|e| e.prepend("repeated kernel circuit invalid"))?;
+        Ok(KernelPrimitive {
+            ir_for_later_compilation,
+            ir_for_calling,
+            ir_input_offsets: vec![0, len, n * len],
+            ir_output_offsets: vec![n * len, (n + 1) * len, (n + 1) * len],
+            io_specs: vec![
+                self.io_specs[0].clone(),
+                IOVecSpec {
+                    len: (n - 1) * len,
+                    is_input: true,
+                    is_output: false,
+                    is_public: false,
+                },
+            ],
+            io_shapes: vec![
+                self.io_shapes[0].clone(),
+                shape_prepend(&self.io_shapes[0], n - 1),
+            ],
+            is_masked: false,
+        })
+    }
+}
+
+fn lin_comb<C: Config>(terms: &[(usize, CircuitField<C>)]) -> Instruction<C> {
+    Instruction::LinComb(LinComb {
+        terms: terms
+            .iter()
+            .map(|&(var, coef)| LinCombTerm { var, coef })
+            .collect(),
+        constant: CircuitField::<C>::zero(),
+    })
+}
+
+// Adds the mask as an input, the first one of the root circuit and the last one of sub
+// circuits, passes it to every sub circuit call, and multiplies every constraint by it.
+fn mask_circuit<C: Config>(
+    circuit: &ir::hint_normalized::Circuit<C>,
+    is_root: bool,
+) -> ir::hint_normalized::Circuit<C> {
+    let n = circuit.num_inputs;
+    let map = |x: usize| if is_root || x > n { x + 1 } else { x };
+    let mask = if is_root { 1 } else { n + 1 };
+    let mut instructions: Vec<Instruction<C>> = circuit
+        .instructions
+        .iter()
+        .map(|insn| match insn.replace_vars(map) {
+            Instruction::SubCircuitCall {
+                sub_circuit_id,
+                mut inputs,
+                num_outputs,
+            } => {
+                inputs.push(mask);
+                Instruction::SubCircuitCall {
+                    sub_circuit_id,
+                    inputs,
+                    num_outputs,
+                }
+            }
+            insn => insn,
+        })
+        .collect();
+    let mut num_vars = circuit.get_num_variables() + 1;
+    let mut constraints = Vec::with_capacity(circuit.constraints.len());
+    for cons in circuit.constraints.iter() {
+        instructions.push(Instruction::Mul(vec![mask, map(cons.var)]));
+        num_vars += 1;
+        constraints.push(TracedConstraint {
+            var: num_vars,
+            ..*cons
+        });
+    }
+    ir::hint_normalized::Circuit {
+        instructions,
+        constraints,
+        outputs: circuit.outputs.iter().map(|x| map(*x)).collect(),
+        num_inputs: n + 1,
+    }
+}
+
+// The circuit used to compute the outputs when calling the kernel, without the constraints
+// of the root circuit.
+fn build_ir_for_calling<C: Config>(
+    r: &ir::hint_normalized::RootCircuit<C>,
+    n_in: usize,
+) -> Result<ir::hint_normalized::RootCircuit<C>, Error> {
+    let mut r2 = r.clone();
+    r2.circuits.get_mut(&0).unwrap().constraints = Vec::new();
+    let mut tmp_im = InputMapping::new_identity(r2.input_size());
+    let r2 = compile_step_4(r2, &mut tmp_im, CompileOptions::default())?;
+    // No inputs should be removed in this step.
+    for (i, x) in tmp_im.mapping().iter().take(n_in).enumerate() {
+        assert_eq!(i, *x);
+    }
+    Ok(r2)
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, ExpSerde)]
@@ -59,6 +279,49 @@ pub struct Kernel<C: Config> {
     pub layered_circuit_input: Vec<LayeredCircuitInputVec>,
 }
 
+// Builds a root circuit with inputs s_0, ..., s_n, each one of length `len`, that calls the
+// root circuit of `r` on (s_i, s_{i+1}) for every i, or only for i = n - 1 if `last_only`.
+// Its outputs are s_0, ..., s_{n-1}, followed by the outputs of the last call.
+fn chain_calls<C: Config>(
+    r: &ir::hint_normalized::RootCircuit<C>,
+    n: usize,
+    len: usize,
+    last_only: bool,
+) -> ir::hint_normalized::RootCircuit<C> {
+    let mut r = r.clone();
+    let step_id = r.circuits.keys().max().unwrap() + 1;
+    let step = r.circuits.remove(&0).unwrap();
+    // the inputs for the expected outputs may have been removed from the circuit for calling
+    let step_num_inputs = step.num_inputs;
+    let step_num_outputs = step.outputs.len();
+    r.circuits.insert(step_id, step);
+
+    let num_inputs = (n + 1) * len;
+    let mut num_vars = num_inputs;
+    let mut instructions = Vec::with_capacity(n);
+    let first = if last_only { n - 1 } else { 0 };
+    for i in first..n {
+        instructions.push(Instruction::SubCircuitCall {
+            sub_circuit_id: step_id,
+            inputs: (i * len + 1..=i * len + step_num_inputs).collect(),
+            num_outputs: step_num_outputs,
+        });
+        num_vars += step_num_outputs;
+    }
+    let mut outputs: Vec<usize> = (1..=n * len).collect();
+    outputs.extend(num_vars - len + 1..=num_vars);
+    r.circuits.insert(
+        0,
+        ir::hint_normalized::Circuit {
+            instructions,
+            constraints: Vec::new(),
+            outputs,
+            num_inputs,
+        },
+    );
+    r
+}
+
 impl<C: Config> Kernel<C> {
     pub fn layered_circuit(&self) -> &LayeredCircuit<C, NormalInputType> {
         &self.layered_circuit
@@ -76,6 +339,8 @@ pub struct IOVecSpec {
     pub len: usize,
     pub is_input: bool,
     pub is_output: bool,
+    // The values are known to the verifier, and checked against the commitment in verification.
+    pub is_public: bool,
 }
 
 #[derive(Default, Debug, Copy, Clone, Hash, PartialEq, Eq, ExpSerde)]
@@ -157,14 +422,7 @@ where
         assert_eq!(*x, i);
     }
     print_ir_stats(&r);
-    let mut r2 = r.clone();
-    r2.circuits.get_mut(&0).unwrap().constraints = Vec::new();
-    let mut tmp_im = InputMapping::new_identity(r2.input_size());
-    let r2 = compile_step_4(r2, &mut tmp_im, CompileOptions::default())?;
-    // No inputs should be removed in this step.
-    for (i, x) in tmp_im.mapping().iter().take(n_in).enumerate() {
-        assert_eq!(i, *x);
-    }
+    let r2 = build_ir_for_calling(&r, n_in)?;
 
     Ok(KernelPrimitive {
         ir_for_later_compilation: r,
@@ -173,6 +431,7 @@ where
         ir_output_offsets: outputs_offsets,
         io_specs: io_specs.to_vec(),
         io_shapes: shapes.to_vec(),
+        is_masked: false,
     })
 }
 
@@ -317,7 +576,14 @@ fn reorder_ir_inputs<C: Config>(
         }
     }
     r0.instructions = new_insns;
-    r0.constraints = r0.constraints.iter().map(|x| var_new_id[*x]).collect();
+    r0.constraints = r0
+        .constraints
+        .iter()
+        .map(|x| TracedConstraint {
+            var: var_new_id[x.var],
+            ..*x
+        })
+        .collect();
     r0.outputs = r0.outputs.iter().map(|x| var_new_id[*x]).collect();
 
     lc_in
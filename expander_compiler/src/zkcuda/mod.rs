@@ -1,3 +1,4 @@
+pub mod artifact;
 pub mod context;
 pub mod kernel;
 pub mod mpi_mem_share;
@@ -7,7 +7,7 @@ use crate::{
     },
     frontend::Config,
     zkcuda::{
-        context::{ComputationGraph, ProofTemplate},
+        context::{ComputationGraph, ProofTemplate, PublicCommitment},
         kernel::{Kernel, LayeredCircuitInputVec},
         shape::BitOrder,
     },
@@ -109,6 +109,13 @@ impl MPISharedMemory for ProofTemplate {
                 .sum::<usize>()
             + self.parallel_count.bytes_size()
             + self.is_broadcast.bytes_size()
+            + self.public_commitments.len().bytes_size()
+            + self
+                .public_commitments
+                .iter()
+                .map(|pc| pc.bytes_size())
+                .sum::<usize>()
+            + (self.is_masked as usize).bytes_size()
     }
 
     fn to_memory(&self, ptr: &mut *mut u8) {
@@ -120,6 +127,11 @@ impl MPISharedMemory for ProofTemplate {
             .for_each(|order| order.to_memory(ptr));
         self.parallel_count.to_memory(ptr);
         self.is_broadcast.to_memory(ptr);
+        self.public_commitments.len().to_memory(ptr);
+        self.public_commitments
+            .iter()
+            .for_each(|pc| pc.to_memory(ptr));
+        (self.is_masked as usize).to_memory(ptr);
     }
 
     fn new_from_memory(ptr: &mut *mut u8) -> Self {
@@ -131,6 +143,11 @@ impl MPISharedMemory for ProofTemplate {
             .collect();
         let parallel_count = usize::new_from_memory(ptr);
         let is_broadcast = Vec::<bool>::new_from_memory(ptr);
+        let public_commitments_len = usize::new_from_memory(ptr);
+        let public_commitments = (0..public_commitments_len)
+            .map(|_| PublicCommitment::new_from_memory(ptr))
+            .collect();
+        let is_masked = usize::new_from_memory(ptr) != 0;
 
         ProofTemplate {
             kernel_id,
@@ -138,6 +155,8 @@ impl MPISharedMemory for ProofTemplate {
             commitment_bit_orders,
             parallel_count,
             is_broadcast,
+            public_commitments,
+            is_masked,
         }
     }
 
@@ -147,6 +166,30 @@ impl MPISharedMemory for ProofTemplate {
             .into_iter()
             .for_each(|order| order.discard_control_of_shared_mem());
         self.is_broadcast.discard_control_of_shared_mem();
+        self.public_commitments
+            .into_iter()
+            .for_each(|pc| pc.discard_control_of_shared_mem());
+    }
+}
+
+impl MPISharedMemory for PublicCommitment {
+    fn bytes_size(&self) -> usize {
+        self.position.bytes_size() + self.shape.bytes_size()
+    }
+
+    fn to_memory(&self, ptr: &mut *mut u8) {
+        self.position.to_memory(ptr);
+        self.shape.to_memory(ptr);
+    }
+
+    fn new_from_memory(ptr: &mut *mut u8) -> Self {
+        let position = usize::new_from_memory(ptr);
+        let shape = Vec::<usize>::new_from_memory(ptr);
+        PublicCommitment { position, shape }
+    }
+
+    fn discard_control_of_shared_mem(self) {
+        self.shape.discard_control_of_shared_mem();
     }
 }
 
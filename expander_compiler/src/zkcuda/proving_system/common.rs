@@ -3,12 +3,17 @@ use crate::{
         config::{Config, SIMDField},
         layered::{Circuit, NormalInputType},
     },
-    zkcuda::kernel::LayeredCircuitInputVec,
+    utils::misc::next_power_of_two,
+    zkcuda::{
+        context::{ComputationGraph, ProofTemplate},
+        kernel::LayeredCircuitInputVec,
+    },
 };
 
 use arith::Field;
 
 use super::super::kernel::Kernel;
+use super::{CombinedProof, KernelWiseProvingSystem};
 
 pub fn check_inputs<C: Config>(
     kernel: &Kernel<C>,
@@ -59,3 +64,64 @@ pub fn prepare_inputs<C: Config>(
     }
     lc_input
 }
+
+/// Pads `public_values` and places them at the index of their commitment, None for the
+/// commitments that are not public. Returns None if they don't match the computation graph.
+pub fn public_values_by_commitment<C: Config>(
+    computation_graph: &ComputationGraph<C>,
+    public_values: &[Vec<SIMDField<C>>],
+) -> Option<Vec<Option<Vec<SIMDField<C>>>>> {
+    let padded = computation_graph.pad_public_values(public_values)?;
+    let mut res = vec![None; computation_graph.commitments_lens().len()];
+    for (id, vals) in padded {
+        res[id] = Some(vals);
+    }
+    Some(res)
+}
+
+/// Selects the public values of the commitments used by `template`.
+pub fn template_public_values<'a, C: Config>(
+    template: &ProofTemplate,
+    by_commitment: &'a [Option<Vec<SIMDField<C>>>],
+) -> Vec<Option<&'a [SIMDField<C>]>> {
+    template
+        .commitment_indices()
+        .iter()
+        .map(|idx| by_commitment[*idx].as_deref())
+        .collect()
+}
+
+/// Verifies every kernel of a `CombinedProof`, checking the opened claims of the public
+/// commitments against `public_values`.
+pub fn verify_kernels_with_public_values<C: Config, KP: KernelWiseProvingSystem<C>>(
+    verifier_setup: &KP::VerifierSetup,
+    computation_graph: &ComputationGraph<C>,
+    proof: &CombinedProof<C, KP>,
+    public_values: &[Vec<SIMDField<C>>],
+) -> bool {
+    let by_commitment = match public_values_by_commitment(computation_graph, public_values) {
+        Some(by_commitment) => by_commitment,
+        None => return false,
+    };
+    proof
+        .proofs
+        .iter()
+        .zip(computation_graph.proof_templates().iter())
+        .all(|(local_proof, template)| {
+            let local_commitments = template
+                .commitment_indices()
+                .iter()
+                .map(|idx| &proof.commitments[*idx])
+                .collect::<Vec<_>>();
+
+            KP::verify_kernel_with_public_values(
+                verifier_setup,
+                &computation_graph.kernels()[template.kernel_id()],
+                local_proof,
+                &local_commitments,
+                &template_public_values(template, &by_commitment),
+                next_power_of_two(template.parallel_count()),
+                template.is_broadcast(),
+            )
+        })
+}
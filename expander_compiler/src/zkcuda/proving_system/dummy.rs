@@ -8,7 +8,7 @@ use crate::zkcuda::proving_system::{CombinedProof, KernelWiseProvingSystem, Prov
 
 use super::super::kernel::Kernel;
 
-use super::{check_inputs, prepare_inputs, Commitment};
+use super::{check_inputs, prepare_inputs, verify_kernels_with_public_values, Commitment};
 
 // dummy implementation of these traits
 
@@ -125,6 +125,29 @@ impl<C: Config> KernelWiseProvingSystem<C> for DummyProvingSystem<C> {
         }
         true
     }
+
+    fn verify_kernel_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        kernel: &Kernel<C>,
+        proof: &Self::Proof,
+        commitments: &[&Self::Commitment],
+        public_values: &[Option<&[SIMDField<C>]>],
+        parallel_count: usize,
+        is_broadcast: &[bool],
+    ) -> bool {
+        commitments
+            .iter()
+            .zip(public_values)
+            .all(|(commitment, vals)| vals.is_none_or(|vals| commitment.vals == vals))
+            && Self::verify_kernel(
+                verifier_setup,
+                kernel,
+                proof,
+                commitments,
+                parallel_count,
+                is_broadcast,
+            )
+    }
 }
 
 // TODO: Generate this with procedural macros
@@ -215,6 +238,20 @@ impl<C: Config> ProvingSystem<C> for DummyProvingSystem<C> {
         verified.iter().all(|x| *x)
     }
 
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &ComputationGraph<C>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<C>>],
+    ) -> bool {
+        verify_kernels_with_public_values::<C, Self>(
+            verifier_setup,
+            computation_graph,
+            proof,
+            public_values,
+        )
+    }
+
     fn post_process() {
         <Self as KernelWiseProvingSystem<C>>::post_process();
     }
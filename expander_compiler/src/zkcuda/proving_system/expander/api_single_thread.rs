@@ -13,7 +13,8 @@ use crate::zkcuda::proving_system::expander::prove_impl::{
 use crate::zkcuda::proving_system::expander::setup_impl::local_setup_impl;
 use crate::zkcuda::proving_system::expander::verify_impl::verify_pcs_opening_and_aggregation_no_mpi;
 use crate::zkcuda::proving_system::{
-    common::check_inputs, CombinedProof, KernelWiseProvingSystem, ProvingSystem,
+    common::{check_inputs, verify_kernels_with_public_values},
+    CombinedProof, KernelWiseProvingSystem, ProvingSystem,
 };
 
 use super::structs::{
@@ -114,6 +115,26 @@ where
         commitments: &[&Self::Commitment],
         parallel_count: usize,
         is_broadcast: &[bool],
+    ) -> bool {
+        <Self as KernelWiseProvingSystem<ECCConfig>>::verify_kernel_with_public_values(
+            verifier_setup,
+            kernel,
+            proof,
+            commitments,
+            &vec![None; commitments.len()],
+            parallel_count,
+            is_broadcast,
+        )
+    }
+
+    fn verify_kernel_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        kernel: &Kernel<ECCConfig>,
+        proof: &Self::Proof,
+        commitments: &[&Self::Commitment],
+        public_values: &[Option<&[SIMDField<C>]>],
+        parallel_count: usize,
+        is_broadcast: &[bool],
     ) -> bool {
         let timer = Timer::new("verify", true);
         let mut expander_circuit = kernel.layered_circuit().export_to_expander_flatten();
@@ -145,6 +166,7 @@ where
                 claimed_v0,
                 claimed_v1,
                 commitments,
+                public_values,
                 is_broadcast,
                 i,
                 parallel_count,
@@ -256,6 +278,20 @@ impl<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>> ProvingSyste
         verified.iter().all(|x| *x)
     }
 
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &ComputationGraph<ECCConfig>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<ECCConfig>>],
+    ) -> bool {
+        verify_kernels_with_public_values::<ECCConfig, Self>(
+            verifier_setup,
+            computation_graph,
+            proof,
+            public_values,
+        )
+    }
+
     fn post_process() {
         <Self as KernelWiseProvingSystem<ECCConfig>>::post_process();
     }
@@ -22,11 +22,26 @@ use crate::{
     },
 };
 
+/// Checks that `claim` is the evaluation at `challenge` of the values known to the verifier.
+/// With the opening of the commitment at the same point, it binds the commitment to them.
+pub fn check_public_claim<F: FieldEngine>(
+    public_vals: &[F::SimdCircuitField],
+    val_len: usize,
+    challenge: &ExpanderSingleVarChallenge<F>,
+    claim: &F::ChallengeField,
+) -> bool {
+    public_vals.len() == val_len
+        && F::single_core_eval_circuit_vals_at_expander_challenge(public_vals, challenge) == *claim
+}
+
+/// Verifies the opening of `commitment` at `challenge`. If the committed values are public,
+/// `public_vals` are the values, and the claim is checked against them.
 pub fn verify_pcs<C, ECCConfig>(
     mut proof_reader: impl Read,
     commitment: &ExpanderCommitment<C::FieldConfig, C::PCSConfig>,
     challenge: &ExpanderSingleVarChallenge<C::FieldConfig>,
     claim: &<C::FieldConfig as FieldEngine>::ChallengeField,
+    public_vals: Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>,
     v_keys: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>,
     transcript: &mut C::TranscriptConfig,
 ) -> bool
@@ -38,6 +53,12 @@ where
         <ExpanderCommitment<C::FieldConfig, C::PCSConfig> as Commitment<ECCConfig>>::vals_len(
             commitment,
         );
+    if let Some(vals) = public_vals {
+        if !check_public_claim(vals, val_len, challenge, claim) {
+            println!("Public values don't match the opened commitment");
+            return false;
+        }
+    }
 
     let params =
         <C::PCSConfig as ExpanderPCS<C::FieldConfig>>::gen_params(val_len.ilog2() as usize, 1);
@@ -76,6 +97,7 @@ pub fn verify_pcs_opening_and_aggregation_no_mpi_impl<C, ECCConfig>(
     challenge: &ExpanderSingleVarChallenge<C::FieldConfig>,
     y: &<C::FieldConfig as FieldEngine>::ChallengeField,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     is_broadcast: &[bool],
     parallel_index: usize,
     parallel_count: usize,
@@ -86,10 +108,11 @@ where
     ECCConfig: Config<FieldConfig = C::FieldConfig>,
 {
     let mut target_y = <C::FieldConfig as FieldEngine>::ChallengeField::ZERO;
-    for ((input, commitment), ib) in kernel
+    for (((input, commitment), vals), ib) in kernel
         .layered_circuit_input()
         .iter()
         .zip(commitments.iter())
+        .zip(public_vals)
         .zip(is_broadcast)
     {
         let val_len =
@@ -115,6 +138,7 @@ where
             commitment,
             &challenge_for_pcs,
             &claim,
+            *vals,
             v_keys,
             transcript,
         );
@@ -136,6 +160,7 @@ where
     *y == target_y
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn verify_pcs_opening_and_aggregation_no_mpi<C, ECCConfig>(
     mut proof_reader: impl Read,
     kernel: &Kernel<ECCConfig>,
@@ -144,6 +169,7 @@ pub fn verify_pcs_opening_and_aggregation_no_mpi<C, ECCConfig>(
     claim_v0: <C::FieldConfig as FieldEngine>::ChallengeField,
     claim_v1: Option<<C::FieldConfig as FieldEngine>::ChallengeField>,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     is_broadcast: &[bool],
     parallel_index: usize,
     parallel_count: usize,
@@ -176,6 +202,7 @@ where
                 &challenge,
                 &claim,
                 commitments,
+                public_vals,
                 is_broadcast,
                 parallel_index,
                 parallel_count,
@@ -8,7 +8,7 @@ use crate::{frontend::{Config, SIMDField}, utils::misc::next_power_of_two,
     zkcuda::{context::ComputationGraph, proving_system::{common::check_inputs,
         expander::{prove_impl::{get_local_vals, prepare_expander_circuit, prepare_inputs_with_local_vals},
             structs::{ExpanderProof, ExpanderProverSetup, ExpanderVerifierSetup}},
-                CombinedProof, Expander, ProvingSystem}}};
+                public_values_by_commitment, template_public_values, CombinedProof, Expander, ProvingSystem}}};
 
 pub struct ExpanderLocalDeferred<C: GKREngine> { _config: std::marker::PhantomData<C> }
 
@@ -72,57 +72,75 @@ impl<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>> ProvingSyste
     }
 
     fn verify(vs: &Self::VerifierSetup, cg: &ComputationGraph<ECCConfig>, proof: &Self::Proof) -> bool {
-        use crate::zkcuda::proving_system::expander::verify_impl::verify_pcs_opening_and_aggregation_no_mpi;
-        // Parallel template verification — each template is independent
-        let vs_ptr = vs as *const _ as usize;
-        use rayon::prelude::*;
-        let results: Vec<bool> = cg.proof_templates().par_iter().enumerate().map(|(ti, tmpl)| {
-            let vs: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig> = unsafe { &*(vs_ptr as *const _) };
-            let kernel = &cg.kernels()[tmpl.kernel_id()];
-            let pc = next_power_of_two(tmpl.parallel_count());
-            let comms: Vec<_> = tmpl.commitment_indices().iter().map(|&i| &proof.commitments[i]).collect();
-            let lp = &proof.proofs[ti];
-            let mut ec = kernel.layered_circuit().export_to_expander_flatten();
+        verify_templates::<C, ECCConfig>(vs, cg, proof, &vec![None; cg.commitments_lens().len()])
+    }
+
+    fn verify_with_public_values(vs: &Self::VerifierSetup, cg: &ComputationGraph<ECCConfig>, proof: &Self::Proof, public_values: &[Vec<SIMDField<ECCConfig>>]) -> bool {
+        match public_values_by_commitment(cg, public_values) {
+            Some(by_commitment) => verify_templates::<C, ECCConfig>(vs, cg, proof, &by_commitment),
+            None => false,
+        }
+    }
+}
+
+// `public_values` holds the values of the public commitments at their index, None for the others
+fn verify_templates<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>>(
+    vs: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>, cg: &ComputationGraph<ECCConfig>,
+    proof: &CombinedProof<ECCConfig, Expander<C>>, public_values: &[Option<Vec<SIMDField<ECCConfig>>>],
+) -> bool {
+    use crate::zkcuda::proving_system::expander::verify_impl::verify_pcs_opening_and_aggregation_no_mpi;
+    // Parallel template verification — each template is independent
+    let vs_ptr = vs as *const _ as usize;
+    use rayon::prelude::*;
+    let results: Vec<bool> = cg.proof_templates().par_iter().enumerate().map(|(ti, tmpl)| {
+        let vs: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig> = unsafe { &*(vs_ptr as *const _) };
+        let kernel = &cg.kernels()[tmpl.kernel_id()];
+        let pc = next_power_of_two(tmpl.parallel_count());
+        let comms: Vec<_> = tmpl.commitment_indices().iter().map(|&i| &proof.commitments[i]).collect();
+        let public_vals = template_public_values(tmpl, public_values);
+        let lp = &proof.proofs[ti];
+        let mut ec = kernel.layered_circuit().export_to_expander_flatten();
 
-            if pc > 1 {
-                let mut t = C::TranscriptConfig::new();
-                ec.fill_rnd_coefs(&mut t);
-                let mut cur = Cursor::new(&lp.data[0].bytes);
-                let (ok, ch, _v0, _v1) = gkr_verify(pc, &ec, &[], &<C::FieldConfig as FieldEngine>::ChallengeField::ZERO, &mut t, &mut cur);
-                if !ok { return false; }
-                let chs = if let Some(cy) = ch.challenge_y() { vec![ch.challenge_x(), cy] } else { vec![ch.challenge_x()] };
-                for sc in &chs {
-                    for (&ref comm, &_ib) in comms.iter().zip(tmpl.is_broadcast().iter()) {
-                        let commitment_len = comm.vals_len;
-                        let local_size = commitment_len >> sc.r_mpi.len();
-                        let n_local = if local_size > 0 { local_size.ilog2() as usize } else { 0 };
-                        let mut eval_ch = sc.clone();
-                        eval_ch.rz.truncate(n_local);
-                        let _v: <C::FieldConfig as FieldEngine>::ChallengeField = t.generate_field_element();
-                        let max_len = *vs.v_keys.keys().max().unwrap();
-                        let params = <C::PCSConfig as ExpanderPCS<C::FieldConfig>>::gen_params(max_len.ilog2() as usize, 1);
-                        let v_key = vs.v_keys.get(&max_len).unwrap();
-                        let mut pcs_ch = sc.clone();
-                        pcs_ch.rz.extend_from_slice(&pcs_ch.r_mpi);
-                        pcs_ch.r_mpi = vec![];
-                        let target_rz = max_len.ilog2() as usize;
-                        while pcs_ch.rz.len() < target_rz { pcs_ch.rz.push(<C::FieldConfig as FieldEngine>::ChallengeField::ZERO); }
-                        // TODO: actually verify PCS opening against commitment
-                        let _ = (v_key, &params, &pcs_ch, comm, &mut cur);
-                    }
+        if pc > 1 {
+            // the openings are not checked yet, so public values can't be bound to the proof
+            if public_vals.iter().any(|v| v.is_some()) { return false; }
+            let mut t = C::TranscriptConfig::new();
+            ec.fill_rnd_coefs(&mut t);
+            let mut cur = Cursor::new(&lp.data[0].bytes);
+            let (ok, ch, _v0, _v1) = gkr_verify(pc, &ec, &[], &<C::FieldConfig as FieldEngine>::ChallengeField::ZERO, &mut t, &mut cur);
+            if !ok { return false; }
+            let chs = if let Some(cy) = ch.challenge_y() { vec![ch.challenge_x(), cy] } else { vec![ch.challenge_x()] };
+            for sc in &chs {
+                for (&ref comm, &_ib) in comms.iter().zip(tmpl.is_broadcast().iter()) {
+                    let commitment_len = comm.vals_len;
+                    let local_size = commitment_len >> sc.r_mpi.len();
+                    let n_local = if local_size > 0 { local_size.ilog2() as usize } else { 0 };
+                    let mut eval_ch = sc.clone();
+                    eval_ch.rz.truncate(n_local);
+                    let _v: <C::FieldConfig as FieldEngine>::ChallengeField = t.generate_field_element();
+                    let max_len = *vs.v_keys.keys().max().unwrap();
+                    let params = <C::PCSConfig as ExpanderPCS<C::FieldConfig>>::gen_params(max_len.ilog2() as usize, 1);
+                    let v_key = vs.v_keys.get(&max_len).unwrap();
+                    let mut pcs_ch = sc.clone();
+                    pcs_ch.rz.extend_from_slice(&pcs_ch.r_mpi);
+                    pcs_ch.r_mpi = vec![];
+                    let target_rz = max_len.ilog2() as usize;
+                    while pcs_ch.rz.len() < target_rz { pcs_ch.rz.push(<C::FieldConfig as FieldEngine>::ChallengeField::ZERO); }
+                    // TODO: actually verify PCS opening against commitment
+                    let _ = (v_key, &params, &pcs_ch, comm, &mut cur);
                 }
-                true
-            } else {
-                let mut t = C::TranscriptConfig::new();
-                ec.fill_rnd_coefs(&mut t);
-                let mut cur = Cursor::new(&lp.data[0].bytes);
-                let (ok, ch, v0, v1) = gkr_verify(1, &ec, &[], &<C::FieldConfig as FieldEngine>::ChallengeField::ZERO, &mut t, &mut cur);
-                if !ok { return false; }
-                verify_pcs_opening_and_aggregation_no_mpi::<C, ECCConfig>(&mut cur, kernel, vs, &ch, v0, v1, &comms, tmpl.is_broadcast(), 0, 1, &mut t)
             }
-        }).collect();
-        results.iter().all(|&x| x)
-    }
+            true
+        } else {
+            let mut t = C::TranscriptConfig::new();
+            ec.fill_rnd_coefs(&mut t);
+            let mut cur = Cursor::new(&lp.data[0].bytes);
+            let (ok, ch, v0, v1) = gkr_verify(1, &ec, &[], &<C::FieldConfig as FieldEngine>::ChallengeField::ZERO, &mut t, &mut cur);
+            if !ok { return false; }
+            verify_pcs_opening_and_aggregation_no_mpi::<C, ECCConfig>(&mut cur, kernel, vs, &ch, v0, v1, &comms, &public_vals, tmpl.is_broadcast(), 0, 1, &mut t)
+        }
+    }).collect();
+    results.iter().all(|&x| x)
 }
 
 fn prove_one<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>>(
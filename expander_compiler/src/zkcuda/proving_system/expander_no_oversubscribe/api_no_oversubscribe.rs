@@ -69,6 +69,28 @@ where
         }
     }
 
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &ComputationGraph<ZC::ECCConfig>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<ZC::ECCConfig>>],
+    ) -> bool {
+        match ZC::BATCH_PCS {
+            true => ExpanderPCSDefered::<ZC::GKRConfig>::verify_with_public_values(
+                verifier_setup,
+                computation_graph,
+                proof,
+                public_values,
+            ),
+            false => ParallelizedExpander::<ZC::GKRConfig>::verify_with_public_values(
+                verifier_setup,
+                computation_graph,
+                proof,
+                public_values,
+            ),
+        }
+    }
+
     fn post_process() {
         wait_async(ClientHttpHelper::request_exit())
     }
@@ -10,7 +10,9 @@ use crate::zkcuda::proving_system::expander_parallelized::client_utils::{
     ClientHttpHelper,
 };
 use crate::zkcuda::proving_system::expander_parallelized::verify_impl::verify_kernel;
-use crate::zkcuda::proving_system::{CombinedProof, ProvingSystem};
+use crate::zkcuda::proving_system::{
+    public_values_by_commitment, template_public_values, CombinedProof, ProvingSystem,
+};
 
 use super::super::Expander;
 
@@ -54,34 +56,62 @@ impl<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>> ProvingSyste
         computation_graph: &ComputationGraph<ECCConfig>,
         proof: &Self::Proof,
     ) -> bool {
-        let verification_timer = Timer::new("Verify all kernels", true);
-        let verified = proof
-            .proofs
-            .iter()
-            .zip(computation_graph.proof_templates().iter())
-            .map(|(local_proof, template)| {
-                let local_commitments = template
-                    .commitment_indices()
-                    .iter()
-                    .map(|idx| &proof.commitments[*idx])
-                    .collect::<Vec<_>>();
-
-                verify_kernel::<C, ECCConfig>(
-                    verifier_setup,
-                    &computation_graph.kernels()[template.kernel_id()],
-                    local_proof,
-                    &local_commitments,
-                    next_power_of_two(template.parallel_count()),
-                    template.is_broadcast(),
-                )
-            })
-            .collect::<Vec<_>>();
-        verification_timer.stop();
+        let no_public_values = vec![None; computation_graph.commitments_lens().len()];
+        verify_kernels::<C, ECCConfig>(verifier_setup, computation_graph, proof, &no_public_values)
+    }
 
-        verified.iter().all(|x| *x)
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &ComputationGraph<ECCConfig>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<ECCConfig>>],
+    ) -> bool {
+        match public_values_by_commitment(computation_graph, public_values) {
+            Some(by_commitment) => verify_kernels::<C, ECCConfig>(
+                verifier_setup,
+                computation_graph,
+                proof,
+                &by_commitment,
+            ),
+            None => false,
+        }
     }
 
     fn post_process() {
         wait_async(ClientHttpHelper::request_exit())
     }
 }
+
+fn verify_kernels<C: GKREngine, ECCConfig: Config<FieldConfig = C::FieldConfig>>(
+    verifier_setup: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>,
+    computation_graph: &ComputationGraph<ECCConfig>,
+    proof: &CombinedProof<ECCConfig, Expander<C>>,
+    public_values: &[Option<Vec<SIMDField<ECCConfig>>>],
+) -> bool {
+    let verification_timer = Timer::new("Verify all kernels", true);
+    let verified = proof
+        .proofs
+        .iter()
+        .zip(computation_graph.proof_templates().iter())
+        .map(|(local_proof, template)| {
+            let local_commitments = template
+                .commitment_indices()
+                .iter()
+                .map(|idx| &proof.commitments[*idx])
+                .collect::<Vec<_>>();
+
+            verify_kernel::<C, ECCConfig>(
+                verifier_setup,
+                &computation_graph.kernels()[template.kernel_id()],
+                local_proof,
+                &local_commitments,
+                &template_public_values(template, public_values),
+                next_power_of_two(template.parallel_count()),
+                template.is_broadcast(),
+            )
+        })
+        .collect::<Vec<_>>();
+    verification_timer.stop();
+
+    verified.iter().all(|x| *x)
+}
@@ -24,11 +24,13 @@ use crate::{
     },
 };
 
+/// `public_vals` are the values of the public commitments, None for the private ones.
 pub fn verify_kernel<C, ECCConfig>(
     verifier_setup: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>,
     kernel: &Kernel<ECCConfig>,
     proof: &ExpanderProof,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     parallel_count: usize,
     is_broadcast: &[bool],
 ) -> bool
@@ -65,6 +67,7 @@ where
         claimed_v0,
         claimed_v1,
         commitments,
+        public_vals,
         is_broadcast,
         parallel_count,
         &mut transcript,
@@ -86,6 +89,7 @@ pub fn verify_pcs_opening_and_aggregation_mpi_impl<C, ECCConfig>(
     challenge: &ExpanderSingleVarChallenge<C::FieldConfig>,
     y: &<C::FieldConfig as FieldEngine>::ChallengeField,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     is_broadcast: &[bool],
     parallel_count: usize,
     transcript: &mut C::TranscriptConfig,
@@ -95,10 +99,11 @@ where
     ECCConfig: Config<FieldConfig = C::FieldConfig>,
 {
     let mut target_y = <C::FieldConfig as FieldEngine>::ChallengeField::ZERO;
-    for ((input, commitment), ib) in kernel
+    for (((input, commitment), vals), ib) in kernel
         .layered_circuit_input()
         .iter()
         .zip(commitments.iter())
+        .zip(public_vals)
         .zip(is_broadcast)
     {
         let val_len =
@@ -118,6 +123,7 @@ where
             commitment,
             &challenge_for_pcs,
             &claim,
+            *vals,
             v_keys,
             transcript,
         );
@@ -145,6 +151,7 @@ pub fn verify_pcs_opening_and_aggregation_mpi<C, ECCConfig>(
     claim_v0: <C::FieldConfig as FieldEngine>::ChallengeField,
     claim_v1: Option<<C::FieldConfig as FieldEngine>::ChallengeField>,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     is_broadcast: &[bool],
     parallel_count: usize,
     transcript: &mut C::TranscriptConfig,
@@ -182,6 +189,7 @@ where
                 challenge,
                 claim,
                 commitments,
+                public_vals,
                 is_broadcast,
                 parallel_count,
                 transcript,
@@ -8,7 +8,7 @@ use crate::{
             client_launch_server_and_setup, client_parse_args, client_send_witness_and_prove,
             wait_async, ClientHttpHelper,
         },
-        CombinedProof, Expander, ProvingSystem,
+        public_values_by_commitment, CombinedProof, Expander, ProvingSystem,
     },
 };
 
@@ -56,7 +56,30 @@ where
         computation_graph: &crate::zkcuda::context::ComputationGraph<ECCConfig>,
         proof: &Self::Proof,
     ) -> bool {
-        super::verify_impl::verify(verifier_setup, computation_graph, proof.clone())
+        let no_public_values = vec![None; computation_graph.commitments_lens().len()];
+        super::verify_impl::verify(
+            verifier_setup,
+            computation_graph,
+            proof.clone(),
+            &no_public_values,
+        )
+    }
+
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &crate::zkcuda::context::ComputationGraph<ECCConfig>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<ECCConfig>>],
+    ) -> bool {
+        match public_values_by_commitment(computation_graph, public_values) {
+            Some(by_commitment) => super::verify_impl::verify(
+                verifier_setup,
+                computation_graph,
+                proof.clone(),
+                &by_commitment,
+            ),
+            None => false,
+        }
     }
 
     fn post_process() {
@@ -10,15 +10,18 @@ use gkr_engine::{
 use serdes::ExpSerde;
 
 use crate::{
-    frontend::Config,
+    frontend::{Config, SIMDField},
     utils::misc::next_power_of_two,
     zkcuda::{
         context::ComputationGraph,
         kernel::Kernel,
         proving_system::{
-            expander::structs::{ExpanderCommitment, ExpanderProof, ExpanderVerifierSetup},
+            expander::{
+                structs::{ExpanderCommitment, ExpanderProof, ExpanderVerifierSetup},
+                verify_impl::check_public_claim,
+            },
             expander_parallelized::prove_impl::partition_challenge_and_location_for_pcs_mpi,
-            CombinedProof, Commitment, Expander,
+            template_public_values, CombinedProof, Commitment, Expander,
         },
     },
 };
@@ -90,10 +93,13 @@ where
     (true, challenge)
 }
 
+/// `public_vals` are the values of the opened commitments known to the verifier, None for the
+/// others. Their claims in the batch opening are checked against them.
 pub fn verify_defered_pcs_opening<C, ECCConfig>(
     proof: &BytesProof,
     verifier_setup: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>,
     commitments: &[&ExpanderCommitment<C::FieldConfig, C::PCSConfig>],
+    public_vals: &[Option<&[<C::FieldConfig as FieldEngine>::SimdCircuitField]>],
     challenges: &[ExpanderSingleVarChallenge<C::FieldConfig>],
 ) -> bool
 where
@@ -110,13 +116,34 @@ where
     let mut defered_proof_bytes = proof.bytes.clone();
     let mut cursor = Cursor::new(&mut defered_proof_bytes);
 
+    let vals =
+        Vec::<<C::FieldConfig as FieldEngine>::ChallengeField>::deserialize_from(&mut cursor)
+            .unwrap();
+    if vals.len() != challenges.len() {
+        println!("Number of claims and challenges don't match");
+        return false;
+    }
+    let public_verified = commitments
+        .iter()
+        .zip(public_vals)
+        .zip(challenges.iter().zip(vals.iter()))
+        .all(|((commitment, public_vals), (challenge, claim))| {
+            public_vals.is_none_or(|public_vals| {
+                let val_len = <ExpanderCommitment<C::FieldConfig, C::PCSConfig> as Commitment<
+                    ECCConfig,
+                >>::vals_len(commitment);
+                check_public_claim(public_vals, val_len, challenge, claim)
+            })
+        });
+    if !public_verified {
+        println!("Public values don't match the opened commitments");
+        return false;
+    }
+
     let commitments = commitments
         .iter()
         .map(|commitment| &commitment.commitment)
         .collect::<Vec<_>>();
-    let vals =
-        Vec::<<C::FieldConfig as FieldEngine>::ChallengeField>::deserialize_from(&mut cursor)
-            .unwrap();
     let opening =
         <C::PCSConfig as ExpanderPCS<C::FieldConfig>>::BatchOpening::deserialize_from(&mut cursor)
             .unwrap();
@@ -136,10 +163,13 @@ where
     pcs_verified
 }
 
+/// `public_values` holds the values of the public commitments at their index, None for the
+/// others.
 pub fn verify<C, ECCConfig>(
     verifier_setup: &ExpanderVerifierSetup<C::FieldConfig, C::PCSConfig>,
     computation_graph: &ComputationGraph<ECCConfig>,
     mut proof: CombinedProof<ECCConfig, Expander<C>>,
+    public_values: &[Option<Vec<SIMDField<ECCConfig>>>],
 ) -> bool
 where
     C: GKREngine,
@@ -197,6 +227,12 @@ where
         .copied()
         .collect::<Vec<_>>();
 
+    let public_vals = computation_graph
+        .proof_templates()
+        .iter()
+        .flat_map(|template| template_public_values(template, public_values))
+        .collect::<Vec<_>>();
+
     let challenges = verified_with_pcs_claims
         .iter()
         .flat_map(|(_, _, c)| c.clone())
@@ -206,6 +242,7 @@ where
         &pcs_batch_opening.data[0],
         verifier_setup,
         &commitments_ref,
+        &public_vals,
         &challenges,
     );
     pcs_verification_timer.stop();
@@ -42,6 +42,29 @@ pub trait KernelWiseProvingSystem<C: Config> {
         is_broadcast: &[bool],
     ) -> bool;
 
+    /// Like `verify_kernel`, but `public_values` holds the values of the commitments known to
+    /// the verifier, None for the others, and the proof must be consistent with them.
+    /// Systems that can't check it reject every proof with public values.
+    fn verify_kernel_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        kernel: &Kernel<C>,
+        proof: &Self::Proof,
+        commitments: &[&Self::Commitment],
+        public_values: &[Option<&[SIMDField<C>]>],
+        parallel_count: usize,
+        is_broadcast: &[bool],
+    ) -> bool {
+        public_values.iter().all(|vals| vals.is_none())
+            && Self::verify_kernel(
+                verifier_setup,
+                kernel,
+                proof,
+                commitments,
+                parallel_count,
+                is_broadcast,
+            )
+    }
+
     fn post_process() {}
 }
 
@@ -79,6 +102,19 @@ pub trait ProvingSystem<C: Config> {
         proof: &Self::Proof,
     ) -> bool;
 
+    /// Verifies the proof, and checks that the public inputs and outputs of the kernels have
+    /// the values `public_values`, given in the order of `ComputationGraph::public_commitments`.
+    fn verify_with_public_values(
+        verifier_setup: &Self::VerifierSetup,
+        computation_graph: &ComputationGraph<C>,
+        proof: &Self::Proof,
+        public_values: &[Vec<SIMDField<C>>],
+    ) -> bool {
+        computation_graph.public_commitments().is_empty()
+            && public_values.is_empty()
+            && Self::verify(verifier_setup, computation_graph, proof)
+    }
+
     /// This is a dedicated function to stop the running service
     /// For most proving systems, this is a no-op
     fn post_process() {}
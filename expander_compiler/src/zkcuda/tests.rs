@@ -170,3 +170,523 @@ fn context_shape_test_2_success() {
     call_kernel!(ctx, identity_5, 3, mut a).unwrap();
     let _ = (a, b);
 }
+
+#[kernel]
+fn public_sum<C: Config>(
+    api: &mut API<C>,
+    a: &[InputVariable; 2],
+    k: &PublicInputVariable,
+    b: &mut PublicOutputVariable,
+) {
+    let s = api.add(a[0], a[1]);
+    *b = api.mul(s, k);
+}
+
+fn context_public_values_impl<P: ProvingSystem<M31Config>>() {
+    use arith::SimdField;
+    type C = M31Config;
+    type F = CircuitField<C>;
+    let public_sum = compile_public_sum::<C>().unwrap();
+    assert_eq!(
+        public_sum
+            .io_specs()
+            .iter()
+            .map(|s| s.is_public)
+            .collect::<Vec<_>>(),
+        vec![false, true, true]
+    );
+
+    let mut ctx: Context<C> = Context::default();
+    let a = ctx.copy_to_device(&vec![vec![F::from(1u32), F::from(2u32)]; 3]);
+    let k = ctx.copy_to_device(&F::from(5u32));
+    let mut b = None;
+    call_kernel!(ctx, public_sum, 3, a, k, mut b).unwrap();
+    assert_eq!(ctx.copy_to_host::<Vec<F>>(b), vec![F::from(15u32); 3]);
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    let public_commitments = computation_graph.public_commitments();
+    assert_eq!(public_commitments.len(), 2);
+    assert_eq!(*public_commitments[0].1, Vec::<usize>::new());
+    assert_eq!(*public_commitments[1].1, vec![3]);
+
+    let public_values = ctx.export_public_values();
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    assert!(P::verify_with_public_values(
+        &verifier_setup,
+        &computation_graph,
+        &proof,
+        &public_values
+    ));
+
+    // the verifier only knows the claimed values
+    let pack = |v: u32| SIMDField::<C>::pack(&vec![F::from(v); SIMDField::<C>::PACK_SIZE]);
+    let claimed = vec![vec![pack(5)], vec![pack(15); 3]];
+    assert!(P::verify_with_public_values(
+        &verifier_setup,
+        &computation_graph,
+        &proof,
+        &claimed
+    ));
+    let wrong = vec![vec![pack(5)], vec![pack(15), pack(15), pack(16)]];
+    assert!(!P::verify_with_public_values(
+        &verifier_setup,
+        &computation_graph,
+        &proof,
+        &wrong
+    ));
+    assert!(!P::verify_with_public_values(
+        &verifier_setup,
+        &computation_graph,
+        &proof,
+        &claimed[..1]
+    ));
+    P::post_process();
+}
+
+#[test]
+#[allow(deprecated)]
+fn context_public_values() {
+    context_public_values_impl::<DummyProvingSystem<M31Config>>();
+    context_public_values_impl::<Expander<M31Config>>();
+}
+
+#[test]
+fn artifact_roundtrip_and_checks() {
+    use super::artifact::*;
+    use gkr_engine::GKREngine;
+    type C = M31Config;
+    type F = CircuitField<C>;
+    type Pcs = <C as GKREngine>::PCSConfig;
+    type P = Expander<C>;
+
+    let gen_graph = |n: usize| {
+        let identity_1 = compile_identity_1::<C>().unwrap();
+        let mut ctx: Context<C> = Context::default();
+        let mut a = ctx.copy_to_device(&vec![F::one(); n]);
+        call_kernel!(ctx, identity_1, n, mut a).unwrap();
+        let computation_graph = ctx.compile_computation_graph().unwrap();
+        ctx.solve_witness().unwrap();
+        (computation_graph, ctx.export_device_memories())
+    };
+    let (computation_graph, device_memories) = gen_graph(4);
+    let context = ArtifactContext::new::<C, Pcs>(&computation_graph);
+    let (prover_setup, verifier_setup) = <P as ProvingSystem<C>>::setup(&computation_graph);
+    let proof = <P as ProvingSystem<C>>::prove(&prover_setup, &computation_graph, device_memories);
+
+    let mut graph_bytes = vec![];
+    write_artifact(&mut graph_bytes, &context, &computation_graph).unwrap();
+    let mut setup_bytes = vec![];
+    write_artifact(&mut setup_bytes, &context, &verifier_setup).unwrap();
+    let mut proof_bytes = vec![];
+    write_artifact(&mut proof_bytes, &context, &proof).unwrap();
+
+    let (loaded_graph, loaded_context) =
+        read_computation_graph::<C, Pcs, _>(graph_bytes.as_slice()).unwrap();
+    assert_eq!(loaded_context, context);
+    let verifier_setup = read_artifact(setup_bytes.as_slice(), &loaded_context).unwrap();
+    let proof = read_artifact(proof_bytes.as_slice(), &loaded_context).unwrap();
+    assert!(<P as ProvingSystem<C>>::verify(
+        &verifier_setup,
+        &loaded_graph,
+        &proof
+    ));
+
+    // wrong kind
+    assert!(
+        read_artifact::<<P as ProvingSystem<C>>::Proof, _>(setup_bytes.as_slice(), &context)
+            .is_err()
+    );
+    // another computation graph
+    let (other_graph, _) = gen_graph(8);
+    let other_context = ArtifactContext::new::<C, Pcs>(&other_graph);
+    assert_ne!(other_context.graph_hash, context.graph_hash);
+    assert!(read_artifact::<<P as ProvingSystem<C>>::Proof, _>(
+        proof_bytes.as_slice(),
+        &other_context
+    )
+    .is_err());
+    // another config
+    let bad_config = ArtifactContext {
+        config_id: BN254Config::CONFIG_ID,
+        ..context.clone()
+    };
+    assert!(read_artifact::<<P as ProvingSystem<C>>::Proof, _>(
+        proof_bytes.as_slice(),
+        &bad_config
+    )
+    .is_err());
+    // another format version
+    let mut bad_version = proof_bytes.clone();
+    bad_version[8] += 1;
+    assert!(
+        read_artifact::<<P as ProvingSystem<C>>::Proof, _>(bad_version.as_slice(), &context)
+            .is_err()
+    );
+    // tampered graph
+    let mut bad_graph = graph_bytes.clone();
+    let last = bad_graph.len() - 1;
+    bad_graph[last] ^= 1;
+    assert!(read_computation_graph::<C, Pcs, _>(bad_graph.as_slice()).is_err());
+    // trailing and missing bytes
+    let mut trailing = proof_bytes.clone();
+    trailing.push(0);
+    let err = read_artifact::<<P as ProvingSystem<C>>::Proof, _>(trailing.as_slice(), &context)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::DeserializationFailed);
+    let mut trailing_graph = graph_bytes.clone();
+    trailing_graph.extend_from_slice(&graph_bytes);
+    let err = read_computation_graph::<C, Pcs, _>(trailing_graph.as_slice())
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::DeserializationFailed);
+    let truncated = &proof_bytes[..proof_bytes.len() - 1];
+    let err = read_artifact::<<P as ProvingSystem<C>>::Proof, _>(truncated, &context)
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::DeserializationFailed);
+    P::post_process();
+}
+
+#[kernel]
+fn square_and_accumulate<C: Config>(
+    api: &mut API<C>,
+    a: &InputVariable,
+    b: &mut OutputVariable,
+    c: &mut InputOutputVariable,
+) {
+    api.assert_is_non_zero(*a);
+    *b = api.mul(*a, *a);
+    *c = api.add(*c, *a);
+}
+
+fn context_masked_kernel_impl<P: ProvingSystem<M31Config>>() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    let f = |x: u32| F::from(x);
+    let kernel = compile_square_and_accumulate::<C>().unwrap();
+    let masked = kernel.masked().unwrap();
+    assert!(masked.is_masked());
+    assert!(masked.masked().is_err());
+
+    let mut ctx: Context<C> = Context::default();
+    let mask = ctx.copy_to_device(&vec![f(1), f(0), f(1), f(0)]);
+    // the inactive instances would violate the assertion
+    let a = ctx.copy_to_device(&vec![f(1), f(0), f(3), f(0)]);
+    let c = ctx.copy_to_device(&vec![f(10), f(20), f(30), f(40)]);
+    let mut ios = [a.clone(), None, c.clone()];
+    assert!(ctx.call_kernel_masked(&kernel, 4, &mask, &mut ios).is_err());
+    ctx.call_kernel_masked(&masked, 4, &mask, &mut ios).unwrap();
+    let [_, b, c] = ios;
+    assert_eq!(ctx.copy_to_host::<Vec<F>>(b), vec![f(1), f(0), f(9), f(0)]);
+    assert_eq!(
+        ctx.copy_to_host::<Vec<F>>(c),
+        vec![f(11), f(20), f(33), f(40)]
+    );
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    assert!(computation_graph.proof_templates()[0].is_masked());
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    P::post_process();
+}
+
+#[test]
+#[allow(deprecated)]
+fn context_masked_kernel() {
+    context_masked_kernel_impl::<DummyProvingSystem<M31Config>>();
+    context_masked_kernel_impl::<Expander<M31Config>>();
+}
+
+fn context_masked_kernel_mixed_lanes_impl<P: ProvingSystem<M31Config>>() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    let f = |x: u32| F::from(x);
+    let masked = compile_square_and_accumulate::<C>()
+        .unwrap()
+        .masked()
+        .unwrap();
+
+    // instance 0 is active in every lane, instance 1 only in the odd lanes, where a is non-zero
+    let lanes = (0..SIMDField::<C>::PACK_SIZE as u32).collect::<Vec<_>>();
+    let active = |lane: u32| lane % 2;
+    let mut ctx: Context<C> = Context::default();
+    let mask = ctx.copy_to_device_and_pack_simd(
+        &lanes
+            .iter()
+            .map(|&l| vec![f(1), f(active(l))])
+            .collect::<Vec<_>>(),
+    );
+    let a = ctx.copy_to_device_and_pack_simd(
+        &lanes
+            .iter()
+            .map(|&l| vec![f(l + 1), f(active(l) * (l + 1))])
+            .collect::<Vec<_>>(),
+    );
+    let c = ctx.copy_to_device_and_pack_simd(
+        &lanes.iter().map(|_| vec![f(10), f(20)]).collect::<Vec<_>>(),
+    );
+    let mut ios = [a, None, c];
+    ctx.call_kernel_masked(&masked, 2, &mask, &mut ios).unwrap();
+    let [_, b, c] = ios;
+    let b: Vec<Vec<F>> = ctx.copy_to_host_and_unpack_simd(b);
+    let c: Vec<Vec<F>> = ctx.copy_to_host_and_unpack_simd(c);
+    for &l in lanes.iter() {
+        let x = l + 1;
+        assert_eq!(b[l as usize], vec![f(x * x), f(active(l) * x * x)]);
+        assert_eq!(c[l as usize], vec![f(10 + x), f(20 + active(l) * x)]);
+    }
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    P::post_process();
+}
+
+#[test]
+#[allow(deprecated)]
+fn context_masked_kernel_mixed_lanes() {
+    context_masked_kernel_mixed_lanes_impl::<DummyProvingSystem<M31Config>>();
+    context_masked_kernel_mixed_lanes_impl::<Expander<M31Config>>();
+}
+
+#[test]
+fn context_masked_kernel_invalid_mask() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    let masked = compile_square_and_accumulate::<C>()
+        .unwrap()
+        .masked()
+        .unwrap();
+    let mut ctx: Context<C> = Context::default();
+    let mask = ctx.copy_to_device(&vec![F::from(2u32), F::from(1u32)]);
+    let a = ctx.copy_to_device(&vec![F::from(1u32), F::from(2u32)]);
+    let c = ctx.copy_to_device(&vec![F::from(3u32), F::from(4u32)]);
+    let mut ios = [a, None, c];
+    assert!(ctx.call_kernel_masked(&masked, 2, &mask, &mut ios).is_err());
+
+    // the mask can't be broadcasted
+    let mask = ctx.copy_to_device(&F::from(1u32));
+    let mut ios = [ios[0].clone(), None, ios[2].clone()];
+    assert!(ctx.call_kernel_masked(&masked, 2, &mask, &mut ios).is_err());
+}
+
+#[kernel]
+fn fibonacci_step<C: Config>(api: &mut API<C>, s: &mut [InputOutputVariable; 2]) {
+    let next = api.add(s[0], s[1]);
+    s[0] = s[1];
+    s[1] = next;
+}
+
+#[test]
+fn context_repeat_kernel() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    type P = DummyProvingSystem<C>;
+    let f = |x: u32| F::from(x);
+    let kernel = compile_fibonacci_step::<C>().unwrap();
+    assert!(kernel.repeated(1).is_err());
+    assert!(compile_square_and_accumulate::<C>()
+        .unwrap()
+        .repeated(3)
+        .is_err());
+
+    let mut ctx: Context<C> = Context::default();
+    let mut state = ctx.copy_to_device(&vec![vec![f(0), f(1)], vec![f(2), f(3)]]);
+    ctx.repeat_kernel(&kernel, 10, &mut state).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(state.clone()),
+        vec![vec![f(55), f(89)], vec![f(288), f(466)]]
+    );
+    // a single iteration is a plain kernel call
+    ctx.repeat_kernel(&kernel, 1, &mut state).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(state.clone()),
+        vec![vec![f(89), f(144)], vec![f(466), f(754)]]
+    );
+    let mut unbatched = ctx.copy_to_device(&vec![f(0), f(1)]);
+    assert!(ctx.repeat_kernel(&kernel, 10, &mut unbatched).is_err());
+    // a copy of the kernel shares its repeated kernel
+    let copy = kernel.clone();
+    ctx.repeat_kernel(&copy, 10, &mut state).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(state.clone()),
+        vec![vec![f(10946), f(17711)], vec![f(57314), f(92736)]]
+    );
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    assert_eq!(computation_graph.proof_templates().len(), 3);
+    // the initial state, the intermediate states and the final state
+    assert_eq!(
+        computation_graph.proof_templates()[0]
+            .commitment_indices()
+            .len(),
+        3
+    );
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    P::post_process();
+}
+
+#[test]
+fn context_device_memory_views() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    type P = DummyProvingSystem<C>;
+    let f = |x: u32| F::from(x);
+    let rows = |v: &[[u32; 3]]| {
+        v.iter()
+            .map(|r| r.iter().map(|&x| f(x)).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+
+    let mut ctx: Context<C> = Context::default();
+    let a = ctx.copy_to_device(&rows(&[[1, 2, 3], [4, 5, 6]]));
+    let b = ctx.copy_to_device(&rows(&[[7, 8, 9], [10, 11, 12]]));
+
+    // selecting everything in order doesn't copy anything
+    let all = ctx.slice(&a, 0, 0..2).unwrap();
+    let all = ctx.concat(&[all], 1).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(all),
+        rows(&[[1, 2, 3], [4, 5, 6]])
+    );
+
+    let s = ctx.slice(&a, 1, 1..3).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(s),
+        vec![vec![f(2), f(3)], vec![f(5), f(6)]]
+    );
+    let g = ctx.gather(&b, 0, &[1, 1, 0]).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(g),
+        rows(&[[10, 11, 12], [10, 11, 12], [7, 8, 9]])
+    );
+    let c = ctx.concat(&[a.clone(), b.clone()], 0).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(c),
+        rows(&[[1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12]])
+    );
+    let c = ctx.concat(&[a.clone(), b.clone()], 1).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(c),
+        vec![
+            [1, 2, 3, 7, 8, 9].map(f).to_vec(),
+            [4, 5, 6, 10, 11, 12].map(f).to_vec()
+        ]
+    );
+    // a transposed handle is read in its logical order
+    let t = ctx
+        .stack(&[a.transpose(&[1, 0]), b.clone().reshape(&[3, 2])], 2)
+        .unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<Vec<F>>>>(t),
+        vec![
+            vec![vec![f(1), f(7)], vec![f(4), f(8)]],
+            vec![vec![f(2), f(9)], vec![f(5), f(10)]],
+            vec![vec![f(3), f(11)], vec![f(6), f(12)]],
+        ]
+    );
+
+    assert!(ctx.slice(&a, 2, 0..1).is_err());
+    assert!(ctx.gather(&a, 0, &[2]).is_err());
+    assert!(ctx.slice(&a, 0, 1..1).is_err());
+    assert!(ctx.concat(&[a.clone(), b.transpose(&[1, 0])], 0).is_err());
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    // one copy kernel call for each of slice, gather, the two concats and stack
+    assert_eq!(computation_graph.proof_templates().len(), 5);
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    P::post_process();
+}
+
+#[test]
+fn context_gather_bit_permutation() {
+    type C = M31Config;
+    type F = CircuitField<C>;
+    type P = DummyProvingSystem<C>;
+    let f = |x: u32| F::from(x);
+    let identity_1 = compile_identity_1::<C>().unwrap();
+
+    let mut ctx: Context<C> = Context::default();
+    let a = ctx.copy_to_device(&(0..8).map(f).collect::<Vec<_>>());
+    // bits 0, 1 and 2 of the index are read from bits 2, 0 and 1
+    let mut g = ctx.gather(&a, 0, &[0, 4, 1, 5, 2, 6, 3, 7]).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<F>>(g.clone()),
+        [0, 4, 1, 5, 2, 6, 3, 7].map(f).to_vec()
+    );
+    let b = ctx.copy_to_device(&vec![
+        (0..4).map(f).collect::<Vec<_>>(),
+        (4..8).map(f).collect(),
+    ]);
+    let h = ctx.gather(&b, 1, &[0, 2, 1, 3]).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<Vec<F>>>(h.clone()),
+        vec![[0, 2, 1, 3].map(f).to_vec(), [4, 6, 5, 7].map(f).to_vec()]
+    );
+    // a bit flip is not a bit permutation
+    let r = ctx.gather(&a, 0, &[1, 0, 3, 2, 5, 4, 7, 6]).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<F>>(r),
+        [1, 0, 3, 2, 5, 4, 7, 6].map(f).to_vec()
+    );
+
+    let mut h = h.reshape(&[8]);
+    call_kernel!(ctx, identity_1, 8, mut g).unwrap();
+    call_kernel!(ctx, identity_1, 8, mut h).unwrap();
+    assert_eq!(
+        ctx.copy_to_host::<Vec<F>>(g),
+        [0, 4, 1, 5, 2, 6, 3, 7].map(f).to_vec()
+    );
+    assert_eq!(
+        ctx.copy_to_host::<Vec<F>>(h),
+        [0, 2, 1, 3, 4, 6, 5, 7].map(f).to_vec()
+    );
+
+    let computation_graph = ctx.compile_computation_graph().unwrap();
+    ctx.solve_witness().unwrap();
+    // the copy kernel of the bit flip and the two identity kernels, no copy for the bit
+    // permutations
+    assert_eq!(computation_graph.proof_templates().len(), 3);
+    let (prover_setup, verifier_setup) = P::setup(&computation_graph);
+    let proof = P::prove(
+        &prover_setup,
+        &computation_graph,
+        ctx.export_device_memories(),
+    );
+    assert!(P::verify(&verifier_setup, &computation_graph, &proof));
+    P::post_process();
+}
@@ -0,0 +1,97 @@
+use expander_compiler::frontend::{extra::PassManager, *};
+
+use super::SourceLine;
+
+static IS_BOOL_LINE: SourceLine = SourceLine::new();
+static IS_EQUAL_LINE: SourceLine = SourceLine::new();
+
+declare_circuit!(Circuit {
+    x: [Variable; 2],
+    sum: Variable,
+});
+
+#[memorized]
+fn check_bit<C: Config, B: RootAPI<C>>(api: &mut B, x: Variable) {
+    IS_BOOL_LINE.mark_next();
+    api.assert_is_bool(x);
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        check_bit(builder, self.x[0]);
+        check_bit(builder, self.x[1]);
+        let sum = builder.add(self.x[0], self.x[1]);
+        IS_EQUAL_LINE.mark_next();
+        builder.assert_is_equal(sum, self.sum);
+    }
+}
+
+#[test]
+fn check_witness_blames_failed_constraints() {
+    let (compile_result, debug_map) = compile_with_constraint_debug_map(
+        &Circuit::default(),
+        CompileOptions::default(),
+        &PassManager::new(),
+    )
+    .unwrap();
+    let assignments = vec![
+        Circuit::<M31> {
+            x: [M31::from(1u32), M31::from(0u32)],
+            sum: M31::from(1u32),
+        },
+        Circuit::<M31> {
+            x: [M31::from(2u32), M31::from(0u32)],
+            sum: M31::from(2u32),
+        },
+        Circuit::<M31> {
+            x: [M31::from(1u32), M31::from(1u32)],
+            sum: M31::from(3u32),
+        },
+    ];
+    let witness = compile_result
+        .witness_solver
+        .solve_witnesses(&assignments)
+        .unwrap();
+    assert_eq!(
+        compile_result.layered_circuit.run(&witness),
+        vec![true, false, false]
+    );
+
+    let report = compile_result
+        .layered_circuit
+        .check_witness(&witness, &debug_map)
+        .unwrap();
+    assert!(!report.is_satisfied());
+    assert!(report
+        .failed_outputs
+        .iter()
+        .all(|(i, _)| *i == 1 || *i == 2));
+    assert_eq!(report.failed_constraints.len(), 2);
+
+    let failed = &report.failed_constraints[0];
+    assert_eq!(failed.witness_index, 1);
+    assert_eq!(failed.origin.sub_circuit_path.len(), 2);
+    assert_eq!(failed.origin.sub_circuit_path[0], 0);
+    let location = failed.origin.location.unwrap();
+    assert!(location.file().ends_with("check_witness.rs"));
+    assert_eq!(location.line(), IS_BOOL_LINE.line());
+
+    let failed = &report.failed_constraints[1];
+    assert_eq!(failed.witness_index, 2);
+    assert_eq!(failed.origin.sub_circuit_path, vec![0]);
+    let location = failed.origin.location.unwrap();
+    assert_eq!(location.line(), IS_EQUAL_LINE.line());
+    assert!(report
+        .to_string()
+        .contains(&IS_EQUAL_LINE.site("check_witness.rs")));
+
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignments[0])
+        .unwrap();
+    let report = compile_result
+        .layered_circuit
+        .check_witness(&witness, &debug_map)
+        .unwrap();
+    assert!(report.is_satisfied());
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use expander_compiler::frontend::*;
+use rayon::prelude::*;
+
+declare_circuit!(Circuit {
+    x: Variable,
+    y: PublicVariable,
+});
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let y = builder.new_hint("closure_hint.table_lookup", &[self.x], 1)[0];
+        builder.assert_is_equal(y, self.y);
+    }
+}
+
+fn square_table() -> Vec<u32> {
+    (0..16u32).map(|i| i * i).collect()
+}
+
+#[test]
+fn closure_hint_with_captured_table() {
+    let table = square_table();
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register_closure(
+        "closure_hint.table_lookup",
+        Box::new(move |x: &[M31], y: &mut [M31]| {
+            let idx = x[0].to_u256().as_usize();
+            match table.get(idx) {
+                Some(v) => {
+                    y[0] = M31::from(*v);
+                    Ok(())
+                }
+                None => Err(Error::UserError(format!("index {idx} out of table"))),
+            }
+        }),
+    );
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    for i in 0..16u32 {
+        let assignment = Circuit::<M31> {
+            x: M31::from(i),
+            y: M31::from(i * i),
+        };
+        let witness = compile_result
+            .witness_solver
+            .solve_witness_with_hints(&assignment, &mut hint_registry)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![true]);
+    }
+
+    let assignment = Circuit::<M31> {
+        x: M31::from(16u32),
+        y: M31::from(0u32),
+    };
+    assert!(compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .is_err());
+}
+
+#[test]
+fn arc_hint_shared_across_rayon_workers() {
+    let table = Arc::new(square_table());
+    let hint: Arc<dyn Fn(&[M31], &mut [M31]) -> Result<(), Error> + Send + Sync> =
+        Arc::new(move |x: &[M31], y: &mut [M31]| {
+            y[0] = M31::from(table[x[0].to_u256().as_usize()]);
+            Ok(())
+        });
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignments = (0..64u32)
+        .map(|i| Circuit::<M31> {
+            x: M31::from(i % 16),
+            y: M31::from((i % 16) * (i % 16)),
+        })
+        .collect::<Vec<_>>();
+    let witnesses = assignments
+        .par_chunks(16)
+        .map(|chunk| {
+            let mut hint_registry = HintRegistry::<M31>::new();
+            hint_registry.register_arc("closure_hint.table_lookup", Arc::clone(&hint));
+            compile_result
+                .witness_solver
+                .solve_witnesses_with_hints(chunk, &hint_registry)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    for witness in witnesses {
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![true; 16]);
+    }
+}
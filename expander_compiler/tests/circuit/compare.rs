@@ -0,0 +1,124 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: Variable,
+    y: Variable,
+    lt: Variable,
+    gt: Variable,
+    geq: Variable,
+});
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let r = builder.lt_bounded(self.x, self.y, 12);
+        builder.assert_is_equal(r, self.lt);
+        // both bounds are known here, so gt and geq don't decompose the whole field
+        builder.to_binary(self.x, 10);
+        builder.assert_in_range(self.y, 12);
+        let r = builder.gt(self.x, self.y);
+        builder.assert_is_equal(r, self.gt);
+        let r = builder.geq(self.x, self.y);
+        builder.assert_is_equal(r, self.geq);
+    }
+}
+
+declare_circuit!(UnboundedCircuit {
+    x: Variable,
+    y: Variable,
+    gt: Variable,
+});
+
+impl Define<M31Config> for UnboundedCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let r = builder.gt(self.x, self.y);
+        builder.assert_is_equal(r, self.gt);
+    }
+}
+
+#[test]
+fn lt_bounded_and_tracked_bounds() {
+    let compile_result =
+        compile::<M31Config, _>(&Circuit::default(), CompileOptions::default()).unwrap();
+    let cases: [(u32, u32); 6] = [(0, 0), (3, 7), (7, 3), (1023, 4095), (1000, 999), (5, 5)];
+    for (x, y) in cases {
+        for wrong in [false, true] {
+            let assignment = Circuit::<M31> {
+                x: M31::from(x),
+                y: M31::from(y),
+                lt: M31::from((x < y) as u32),
+                gt: M31::from((x > y) as u32 ^ wrong as u32),
+                geq: M31::from((x >= y) as u32),
+            };
+            let witness = compile_result
+                .witness_solver
+                .solve_witness(&assignment)
+                .unwrap();
+            let output = compile_result.layered_circuit.run(&witness);
+            assert_eq!(output, vec![!wrong], "x = {x}, y = {y}");
+        }
+    }
+
+    let unbounded =
+        compile::<M31Config, _>(&UnboundedCircuit::default(), CompileOptions::default()).unwrap();
+    assert!(
+        compile_result.layered_circuit.get_stats().num_inputs
+            < unbounded.layered_circuit.get_stats().num_inputs
+    );
+}
+
+declare_circuit!(LtCircuit {
+    x: Variable,
+    y: Variable,
+    lt: Variable,
+});
+
+impl Define<M31Config> for LtCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let r = builder.lt_bounded(self.x, self.y, 12);
+        builder.assert_is_equal(r, self.lt);
+    }
+}
+
+#[test]
+fn lt_bounded_checks_the_bound() {
+    let compile_result =
+        compile::<M31Config, _>(&LtCircuit::default(), CompileOptions::default()).unwrap();
+    // x = p - 1 and y = 0 would give y - x - 1 + 2^12 = 2^12, which claims x < y
+    let cases = [
+        (M31::from(3u32), M31::from(4000u32), true),
+        (-M31::one(), M31::zero(), false),
+        (M31::from(4096u32), M31::from(4095u32), false),
+    ];
+    for (x, y, expected) in cases {
+        let assignment = LtCircuit::<M31> {
+            x,
+            y,
+            lt: M31::one(),
+        };
+        let witness = compile_result
+            .witness_solver
+            .solve_witness(&assignment)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![expected], "x = {x:?}, y = {y:?}");
+    }
+}
+
+#[test]
+fn lt_bounded_debug() {
+    let assignment = Circuit::<M31> {
+        x: M31::from(3u32),
+        y: M31::from(5000u32),
+        lt: M31::from(1u32),
+        gt: M31::from(0u32),
+        geq: M31::from(0u32),
+    };
+    let report = extra::debug_eval_report(&Circuit::default(), &assignment, EmptyHintCaller);
+    // y doesn't fit in the 12 bits declared to lt_bounded
+    assert!(!report.is_satisfied());
+    assert_eq!(
+        report.failures[0].kind,
+        extra::AssertionKind::InRange { bits: 12 }
+    );
+    assert_eq!(report.failures[0].values, vec![M31::from(5000u32)]);
+}
@@ -0,0 +1,104 @@
+use std::fs;
+
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    y: Variable,
+});
+
+declare_circuit!(Circuit2 {
+    x: [Variable; 4],
+    y: Variable,
+});
+
+#[memorized]
+fn cube<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable) -> Variable {
+    let a2 = api.mul(a, a);
+    api.mul(a2, a)
+}
+
+#[memorized]
+fn cube_sum<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable, b: Variable) -> Variable {
+    let a3 = cube(api, a);
+    let b3 = cube(api, b);
+    api.add(a3, b3)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let s1 = cube_sum(builder, self.x[0], self.x[1]);
+        let s2 = cube_sum(builder, self.x[2], self.x[3]);
+        let s = builder.add(s1, s2);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+// same sub circuits, different root circuit
+impl Define<M31Config> for Circuit2<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let s1 = cube_sum(builder, self.x[0], self.x[1]);
+        let s2 = cube_sum(builder, self.x[2], self.x[3]);
+        let s = builder.mul(s1, s2);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+fn num_entries(dir: &std::path::Path, stage: &str) -> usize {
+    fs::read_dir(dir)
+        .unwrap()
+        .filter(|e| {
+            let name = e.as_ref().unwrap().file_name();
+            let name = name.to_str().unwrap();
+            name.starts_with(&format!("{stage}-")) && name.ends_with(".bin")
+        })
+        .count()
+}
+
+#[test]
+fn compile_cache_reuses_sub_circuits() {
+    let dir = std::env::temp_dir().join(format!(
+        "expander_compiler_cache_test_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    let options = CompileOptions::default().with_cache_dir(&dir);
+
+    let uncached = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let first = compile(&Circuit::default(), options.clone()).unwrap();
+    let n1 = num_entries(&dir, "final_build");
+    assert!(n1 >= 3);
+    let h1 = num_entries(&dir, "hint_normalize");
+    assert!(h1 >= 3);
+    assert_eq!(num_entries(&dir, "layered"), 1);
+
+    let second = compile(&Circuit::default(), options.clone()).unwrap();
+    assert_eq!(num_entries(&dir, "final_build"), n1);
+    assert_eq!(num_entries(&dir, "hint_normalize"), h1);
+    assert_eq!(num_entries(&dir, "layered"), 1);
+    assert_eq!(first.layered_circuit, uncached.layered_circuit);
+    assert_eq!(second.layered_circuit, uncached.layered_circuit);
+
+    let assignment = Circuit::<M31> {
+        x: [
+            M31::from(1u32),
+            M31::from(2u32),
+            M31::from(3u32),
+            M31::from(4u32),
+        ],
+        y: M31::from(100u32),
+    };
+    let witness = second.witness_solver.solve_witness(&assignment).unwrap();
+    assert_eq!(second.layered_circuit.run(&witness), vec![true]);
+
+    // only the changed root circuit is built again
+    let other = compile(&Circuit2::default(), options).unwrap();
+    let n2 = num_entries(&dir, "final_build");
+    assert!(n2 > n1 && n2 - n1 < n1);
+    assert_eq!(num_entries(&dir, "hint_normalize"), h1 + 1);
+    assert_eq!(num_entries(&dir, "layered"), 2);
+    let uncached_other = compile(&Circuit2::default(), CompileOptions::default()).unwrap();
+    assert_eq!(other.layered_circuit, uncached_other.layered_circuit);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
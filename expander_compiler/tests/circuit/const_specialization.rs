@@ -0,0 +1,59 @@
+use expander_compiler::circuit::layered::{Circuit as LayeredCircuit, NormalInputType};
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 3],
+    y: Variable,
+});
+
+// x^3 * k + x^2 + k
+#[memorized]
+fn poly<C: Config, B: RootAPI<C>>(api: &mut B, x: Variable, k: Variable) -> Variable {
+    let x2 = api.mul(x, x);
+    let x3 = api.mul(x2, x);
+    let t = api.mul(x3, k);
+    let t = api.add(t, x2);
+    api.add(t, k)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let zero = builder.constant(0);
+        let one = builder.constant(1);
+        let a = poly(builder, self.x[0], zero);
+        let b = poly(builder, self.x[1], one);
+        let c = poly(builder, self.x[2], zero);
+        let s = builder.add(a, b);
+        let s = builder.add(s, c);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+fn num_mul_gates(lc: &LayeredCircuit<M31Config, NormalInputType>) -> usize {
+    lc.segments.iter().map(|seg| seg.gate_muls.len()).sum()
+}
+
+#[test]
+fn specialize_constant_arguments() {
+    let plain = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let specialized = compile(
+        &Circuit::default(),
+        CompileOptions::default().with_const_specialization_budget(100),
+    )
+    .unwrap();
+    assert!(num_mul_gates(&specialized.layered_circuit) < num_mul_gates(&plain.layered_circuit));
+
+    // 4 + (8 + 4 + 1) + 1
+    let assignment = Circuit::<M31> {
+        x: [M31::from(2u32), M31::from(2u32), M31::from(1u32)],
+        y: M31::from(18u32),
+    };
+    for res in [&plain, &specialized] {
+        let witness = res.witness_solver.solve_witness(&assignment).unwrap();
+        assert_eq!(res.layered_circuit.run(&witness), vec![true]);
+    }
+    let mut bad = assignment.clone();
+    bad.y = M31::from(19u32);
+    let witness = specialized.witness_solver.solve_witness(&bad).unwrap();
+    assert_eq!(specialized.layered_circuit.run(&witness), vec![false]);
+}
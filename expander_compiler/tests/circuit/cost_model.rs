@@ -0,0 +1,174 @@
+use expander_compiler::circuit::layered::stats::Stats;
+use expander_compiler::frontend::extra::*;
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    y: Variable,
+});
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let a = builder.add(self.x[0], self.x[1]);
+        let b = builder.add(self.x[2], self.x[3]);
+        let c = builder.mul(a, b);
+        let d = builder.mul(c, a);
+        builder.assert_is_equal(d, self.y);
+    }
+}
+
+fn check_cost_model(cost_model: CostModel) {
+    let compile_result = compile(
+        &Circuit::default(),
+        CompileOptions::default().with_cost_model(cost_model),
+    )
+    .unwrap();
+    let stats = compile_result
+        .layered_circuit
+        .get_stats_with_cost_model(&cost_model);
+    assert!(stats.total_cost > 0);
+    let assignment = Circuit::<M31> {
+        x: [
+            M31::from(1u32),
+            M31::from(2u32),
+            M31::from(3u32),
+            M31::from(4u32),
+        ],
+        y: M31::from(63u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(compile_result.layered_circuit.run(&witness), vec![true]);
+}
+
+#[test]
+fn custom_cost_models() {
+    check_cost_model(CostModel::of::<M31Config>());
+    let default = CostModel::of::<M31Config>();
+    check_cost_model(CostModel {
+        mul: default.mul * 100,
+        ..default
+    });
+    check_cost_model(CostModel {
+        input: 1,
+        variable: 1,
+        mul: 1,
+        add: 1,
+        constant: 1,
+    });
+}
+
+declare_circuit!(MuxCircuit {
+    s: [Variable; 2],
+    v: [Variable; 4],
+    y: Variable,
+});
+
+impl Define<M31Config> for MuxCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let r = builder.mux(&self.s, &self.v);
+        builder.assert_is_equal(r, self.y);
+    }
+}
+
+fn mux_stats(cost_model: CostModel) -> Stats {
+    let compile_result = compile(
+        &MuxCircuit::default(),
+        CompileOptions::default().with_cost_model(cost_model),
+    )
+    .unwrap();
+    let assignment = MuxCircuit::<M31> {
+        s: [M31::from(1u32), M31::from(1u32)],
+        v: [5u32, 6, 7, 8].map(M31::from),
+        y: M31::from(8u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(compile_result.layered_circuit.run(&witness), vec![true]);
+    compile_result.layered_circuit.get_stats()
+}
+
+#[test]
+fn skewed_cost_model_changes_layout() {
+    let flat = CostModel {
+        input: 1,
+        variable: 1,
+        mul: 1,
+        add: 1,
+        constant: 1,
+    };
+    // expensive multiplications select the tree of selects, expensive additions the
+    // multilinear expansion, which multiplies every coefficient by a product of selectors
+    let mul_heavy = mux_stats(CostModel { mul: 1000, ..flat });
+    let add_heavy = mux_stats(CostModel { add: 1000, ..flat });
+    assert!(mul_heavy.num_expanded_mul < add_heavy.num_expanded_mul);
+}
+
+declare_circuit!(ProductCircuit {
+    x: [Variable; 4],
+    y: Variable,
+});
+
+impl Define<M31Config> for ProductCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let a = builder.add(self.x[0], self.x[1]);
+        let b = builder.add(self.x[2], self.x[3]);
+        let c = builder.mul(a, b);
+        builder.assert_is_equal(c, self.y);
+    }
+}
+
+fn product_stats(cost_model: CostModel) -> Stats {
+    let compile_result = compile(
+        &ProductCircuit::default(),
+        CompileOptions::default().with_cost_model(cost_model),
+    )
+    .unwrap();
+    let assignment = ProductCircuit::<M31> {
+        x: [1u32, 2, 3, 4].map(M31::from),
+        y: M31::from(21u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(compile_result.layered_circuit.run(&witness), vec![true]);
+    compile_result.layered_circuit.get_stats()
+}
+
+#[test]
+fn skewed_cost_model_changes_layers() {
+    let flat = CostModel {
+        input: 1,
+        variable: 1,
+        mul: 1,
+        add: 1,
+        constant: 1,
+    };
+    // expensive multiplications compress both sums into variables on a layer of their own,
+    // so that their product is a single gate; expensive variables expand the product instead
+    let mul_heavy = product_stats(CostModel { mul: 1000, ..flat });
+    let variable_heavy = product_stats(CostModel {
+        variable: 1000,
+        ..flat
+    });
+    assert!(mul_heavy.num_expanded_mul < variable_heavy.num_expanded_mul);
+    assert!(mul_heavy.num_layers > variable_heavy.num_layers);
+}
+
+// runs the Expander prover, which is slow
+#[test]
+#[ignore]
+fn calibrated_cost_model() {
+    let cost_model = calibrate_cost_model::<M31Config>(8, 2);
+    assert!(cost_model.input >= 1);
+    assert!(cost_model.variable >= 1);
+    assert!(cost_model.mul >= 1);
+    assert!(cost_model.add >= 1);
+    assert!(cost_model.constant >= 1);
+    check_cost_model(cost_model);
+}
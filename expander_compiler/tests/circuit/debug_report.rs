@@ -0,0 +1,67 @@
+use expander_compiler::frontend::extra::{debug_eval_report, AssertionKind};
+use expander_compiler::frontend::*;
+
+use super::SourceLine;
+
+static IS_BOOL_LINE: SourceLine = SourceLine::new();
+static IS_EQUAL_LINE: SourceLine = SourceLine::new();
+
+declare_circuit!(Circuit {
+    x: [Variable; 3],
+    sum: PublicVariable,
+});
+
+#[memorized]
+fn check_bits<C: Config, B: RootAPI<C>>(api: &mut B, x: &Vec<Variable>) {
+    for v in x.iter() {
+        IS_BOOL_LINE.mark_next();
+        api.assert_is_bool(*v);
+    }
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        check_bits(builder, &self.x.to_vec());
+        let mut sum = builder.constant(0);
+        for x in self.x.iter() {
+            sum = builder.add(sum, x);
+        }
+        IS_EQUAL_LINE.mark_next();
+        builder.assert_is_equal(sum, self.sum);
+    }
+}
+
+#[test]
+fn debug_eval_report_collects_all_failures() {
+    let assignment = Circuit::<M31> {
+        x: [1u32, 0, 1].map(M31::from),
+        sum: M31::from(2u32),
+    };
+    let report = debug_eval_report(&Circuit::default(), &assignment, EmptyHintCaller);
+    assert!(report.is_satisfied());
+    assert!(report.to_result().is_ok());
+
+    let assignment = Circuit::<M31> {
+        x: [2u32, 0, 3].map(M31::from),
+        sum: M31::from(4u32),
+    };
+    let report = debug_eval_report(&Circuit::default(), &assignment, EmptyHintCaller);
+    assert_eq!(report.failures.len(), 3);
+
+    let bits = &report.failures[0];
+    assert_eq!(bits.kind, AssertionKind::IsBool);
+    assert_eq!(bits.values, vec![M31::from(2u32)]);
+    assert_eq!(bits.location.line(), IS_BOOL_LINE.line());
+    assert_eq!(bits.sub_circuit_stack.len(), 1);
+    assert_eq!(report.failures[1].values, vec![M31::from(3u32)]);
+
+    let sum = &report.failures[2];
+    assert_eq!(sum.kind, AssertionKind::IsEqual);
+    assert_eq!(sum.values, vec![M31::from(5u32), M31::from(4u32)]);
+    assert_eq!(sum.location.line(), IS_EQUAL_LINE.line());
+    assert!(sum.sub_circuit_stack.is_empty());
+
+    let err = report.to_result().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConstraintUnsatisfied);
+    assert!(err.to_string().contains("3 assertion(s) failed"));
+}
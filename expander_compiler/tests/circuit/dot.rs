@@ -0,0 +1,34 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    y: PublicVariable,
+});
+
+#[memorized]
+fn square_sum<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable, b: Variable) -> Variable {
+    let a2 = api.mul(a, a);
+    let b2 = api.mul(b, b);
+    api.add(a2, b2)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let s1 = square_sum(builder, self.x[0], self.x[1]);
+        let s2 = square_sum(builder, self.x[2], self.x[3]);
+        let p = builder.mul(s1, s2);
+        let p = builder.add(p, 7);
+        builder.assert_is_equal(p, self.y);
+    }
+}
+
+#[test]
+fn layered_to_dot() {
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let lc = compile_result.layered_circuit;
+    let dot = lc.to_dot();
+    assert!(dot.starts_with("digraph"));
+    for (i, id) in lc.layer_ids.iter().enumerate() {
+        assert!(dot.contains(&format!("l{i} -> s{id} [style=dashed];")));
+    }
+}
@@ -0,0 +1,117 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    num_non_zero: PublicVariable,
+});
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let (len, values) = builder
+            .new_dynamic_hint("dynamic_hint.non_zero", &self.x, 4)
+            .unwrap();
+        builder.assert_is_equal(len, self.num_non_zero);
+        let mut sum_x = builder.constant(0);
+        let mut sum_values = builder.constant(0);
+        for (x, v) in self.x.iter().zip(values.iter()) {
+            sum_x = builder.add(sum_x, x);
+            sum_values = builder.add(sum_values, v);
+        }
+        builder.assert_is_equal(sum_x, sum_values);
+    }
+}
+
+fn non_zero_hint(x: &[M31]) -> Result<Vec<M31>, Error> {
+    Ok(x.iter().filter(|v| !v.is_zero()).copied().collect())
+}
+
+fn too_many_outputs_hint(x: &[M31]) -> Result<Vec<M31>, Error> {
+    Ok(vec![x[0]; x.len() + 1])
+}
+
+#[test]
+fn dynamic_hint_pads_unused_outputs() {
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register_dynamic("dynamic_hint.non_zero", non_zero_hint);
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let cases: [([u32; 4], u32); 4] = [
+        ([0, 0, 0, 0], 0),
+        ([1, 0, 3, 0], 2),
+        ([0, 5, 0, 0], 1),
+        ([7, 8, 9, 10], 4),
+    ];
+    for (x, num_non_zero) in cases {
+        let assignment = Circuit::<M31> {
+            x: x.map(M31::from),
+            num_non_zero: M31::from(num_non_zero),
+        };
+        let witness = compile_result
+            .witness_solver
+            .solve_witness_with_hints(&assignment, &mut hint_registry)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![true]);
+    }
+}
+
+#[test]
+fn dynamic_hint_rejects_overflow() {
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register_dynamic("dynamic_hint.non_zero", too_many_outputs_hint);
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignment = Circuit::<M31> {
+        x: [1, 2, 3, 4].map(M31::from),
+        num_non_zero: M31::from(4u32),
+    };
+    assert!(compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .is_err());
+}
+
+// claims 2 outputs, but writes the third input after them
+fn bad_padding_hint(x: &[M31], outputs: &mut [M31]) -> Result<(), Error> {
+    outputs[0] = M31::from(2u32);
+    outputs[1..4].copy_from_slice(&x[0..3]);
+    Ok(())
+}
+
+#[test]
+fn dynamic_hint_constrains_padding() {
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register("dynamic_hint.non_zero", bad_padding_hint);
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignment = Circuit::<M31> {
+        x: [1, 2, 3, 0].map(M31::from),
+        num_non_zero: M31::from(2u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .unwrap();
+    let output = compile_result.layered_circuit.run(&witness);
+    assert_eq!(output, vec![false]);
+}
+
+declare_circuit!(BitCircuit { x: [Variable; 2] });
+
+impl Define<GF2Config> for BitCircuit<Variable> {
+    fn define<Builder: RootAPI<GF2Config>>(&self, builder: &mut Builder) {
+        // the padding constraints can't count past the characteristic
+        assert!(builder
+            .new_dynamic_hint("dynamic_hint.bits", &self.x, 2)
+            .is_err());
+        let (len, values) = builder
+            .new_dynamic_hint("dynamic_hint.bits", &self.x, 1)
+            .unwrap();
+        builder.assert_is_equal(len, values[0]);
+    }
+}
+
+#[test]
+fn dynamic_hint_needs_large_characteristic() {
+    compile(&BitCircuit::default(), CompileOptions::default()).unwrap();
+}
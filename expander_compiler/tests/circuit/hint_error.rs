@@ -0,0 +1,129 @@
+use expander_compiler::frontend::*;
+use serdes::ExpSerde;
+
+use super::SourceLine;
+
+static HINT_LINE: SourceLine = SourceLine::new();
+
+declare_circuit!(Circuit { x: Variable });
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        HINT_LINE.mark_next();
+        let y = builder.new_hint("hint_error.must_be_small", &[self.x], 1)[0];
+        builder.assert_is_equal(y, self.x);
+    }
+}
+
+// the same key is used at two places, only the one in the sub circuit sees a large value
+static SUB_HINT_LINE: SourceLine = SourceLine::new();
+
+declare_circuit!(TwoSitesCircuit { x: Variable });
+
+fn check_small<B: RootAPI<M31Config>>(builder: &mut B, inputs: &Vec<Variable>) -> Vec<Variable> {
+    SUB_HINT_LINE.mark_next();
+    let y = builder.new_hint("hint_error.must_be_small", &inputs[1..], 1)[0];
+    builder.assert_is_equal(y, inputs[1]);
+    vec![y]
+}
+
+impl Define<M31Config> for TwoSitesCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let small = builder.constant(1);
+        let y = builder.new_hint("hint_error.must_be_small", &[small], 1)[0];
+        builder.assert_is_equal(y, small);
+        builder.memorized_simple_call(check_small, &[small, self.x]);
+    }
+}
+
+fn must_be_small_hint(x: &[M31], y: &mut [M31]) -> Result<(), Error> {
+    if x[0].to_u256().as_u64() >= 100 {
+        return Err(Error::UserError("value too large".to_string()));
+    }
+    y[0] = x[0];
+    Ok(())
+}
+
+#[test]
+fn hint_error_reports_key_and_call_site() {
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register("hint_error.must_be_small", must_be_small_hint);
+
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignment = Circuit::<M31> { x: M31::from(5u32) };
+    assert!(compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .is_ok());
+
+    let assignment = Circuit::<M31> {
+        x: M31::from(500u32),
+    };
+    let err = compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .unwrap_err();
+    assert!(err.is_user());
+    let msg = err.to_string();
+    assert!(msg.contains("hint `hint_error.must_be_small`"));
+    let call_site = HINT_LINE.site("hint_error.rs");
+    assert!(msg.contains(&call_site));
+    assert!(msg.contains("value too large"));
+
+    let err = compile_result
+        .witness_solver
+        .solve_witnesses_with_hints(&[assignment], &EmptyHintCaller)
+        .unwrap_err();
+    assert!(err.to_string().contains("hint `hint_error.must_be_small`"));
+}
+
+#[test]
+fn hint_error_reports_failing_call_site_and_sub_circuit_name() {
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register("hint_error.must_be_small", must_be_small_hint);
+
+    let compile_result = compile(&TwoSitesCircuit::default(), CompileOptions::default()).unwrap();
+    let assignment = TwoSitesCircuit::<M31> {
+        x: M31::from(500u32),
+    };
+    let err = compile_result
+        .witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .unwrap_err();
+    let msg = err.to_string();
+    let call_site = SUB_HINT_LINE.site("hint_error.rs");
+    assert!(msg.contains(&format!("(created at {call_site}")));
+    assert!(!msg.contains("one of"));
+    assert!(msg.contains("sub-circuit path root -> "));
+    assert!(msg.contains("check_small"));
+}
+
+#[test]
+fn hint_infos_are_not_serialized() {
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let mut bytes = Vec::new();
+    compile_result
+        .witness_solver
+        .serialize_into(&mut bytes)
+        .unwrap();
+    // the layout is the one of witness solvers without hint infos
+    let mut circuit_bytes = Vec::new();
+    compile_result
+        .witness_solver
+        .circuit
+        .serialize_into(&mut circuit_bytes)
+        .unwrap();
+    assert_eq!(bytes, circuit_bytes);
+
+    let witness_solver = WitnessSolver::<M31Config>::deserialize_from(bytes.as_slice()).unwrap();
+    assert_eq!(
+        witness_solver.circuit,
+        compile_result.witness_solver.circuit
+    );
+    let assignment = Circuit::<M31> { x: M31::from(5u32) };
+    let mut hint_registry = HintRegistry::<M31>::new();
+    hint_registry.register("hint_error.must_be_small", must_be_small_hint);
+    assert!(witness_solver
+        .solve_witness_with_hints(&assignment, &mut hint_registry)
+        .is_ok());
+}
@@ -0,0 +1,68 @@
+use expander_compiler::circuit::layered::{Circuit as LayeredCircuit, NormalInputType};
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    y: PublicVariable,
+});
+
+#[memorized]
+fn square_sum<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable, b: Variable) -> Variable {
+    let a2 = api.mul(a, a);
+    let b2 = api.mul(b, b);
+    api.add(a2, b2)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let s1 = square_sum(builder, self.x[0], self.x[1]);
+        let s2 = square_sum(builder, self.x[2], self.x[3]);
+        let p = builder.mul(s1, s2);
+        let p = builder.add(p, 7);
+        builder.assert_is_equal(p, self.y);
+    }
+}
+
+#[test]
+fn layered_json_round_trip() {
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let lc = compile_result.layered_circuit;
+    let text = lc.to_json_string();
+    let lc2 = LayeredCircuit::<M31Config, NormalInputType>::from_json_str(&text).unwrap();
+    assert_eq!(lc, lc2);
+
+    let assignment = Circuit::<M31> {
+        x: [
+            M31::from(1u32),
+            M31::from(2u32),
+            M31::from(3u32),
+            M31::from(4u32),
+        ],
+        y: M31::from(132u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(lc2.run(&witness), vec![true]);
+
+    let cross = compile_cross_layer(&Circuit::default(), CompileOptions::default()).unwrap();
+    let cross_lc = cross.layered_circuit;
+    let cross_lc2 = LayeredCircuit::from_json(&cross_lc.to_json()).unwrap();
+    assert_eq!(cross_lc, cross_lc2);
+}
+
+#[test]
+fn layered_json_rejects_invalid() {
+    let compile_result = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let mut v = compile_result.layered_circuit.to_json();
+
+    let mut bad = v.clone();
+    bad["segments"][0]["num_outputs"] = 3.into();
+    let err = LayeredCircuit::<M31Config, NormalInputType>::from_json(&bad).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::IrValidationFailed);
+
+    v["layer_ids"] = "oops".into();
+    let err = LayeredCircuit::<M31Config, NormalInputType>::from_json(&v).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::DeserializationFailed);
+}
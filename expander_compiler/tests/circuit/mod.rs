@@ -1,16 +1,64 @@
+mod check_witness;
+mod closure_hint;
+mod compare;
+mod compile_cache;
+mod const_specialization;
+mod cost_model;
+mod debug_report;
+mod dot;
+mod dynamic_hint;
 mod example;
 mod example_call_expander;
+mod hint_error;
 mod keccak_gf2;
 mod keccak_gf2_full;
 mod keccak_gf2_full_crosslayer;
 mod keccak_gf2_vec;
 mod keccak_non_gf2;
+mod layered_json;
 
 mod mul_fanout_limit;
 mod multithreading_witness;
+mod mux;
+mod parallel_compile;
+mod pass_manager;
+mod profile;
+mod range_check;
 
 mod simple_add_m31;
 mod sub_circuit_macro;
+mod table_lookup;
 mod to_binary_builtin;
 mod to_binary_hint;
 mod to_binary_unconstrained_api;
+
+use std::{
+    panic::Location,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// The line of a statement in a circuit definition, for tests on the source locations
+/// reported by the compiler. `mark_next` is called on the line right above the statement.
+#[derive(Default)]
+pub struct SourceLine(AtomicU32);
+
+impl SourceLine {
+    pub const fn new() -> Self {
+        Self(AtomicU32::new(0))
+    }
+
+    #[track_caller]
+    pub fn mark_next(&self) {
+        self.0
+            .store(Location::caller().line() + 1, Ordering::Relaxed);
+    }
+
+    pub fn line(&self) -> u32 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `file:line:` as printed by `Location`, without the column.
+    pub fn site(&self, file: &str) -> String {
+        format!("{file}:{}:", self.line())
+    }
+}
@@ -0,0 +1,81 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    s: [Variable; 3],
+    v: [Variable; 8],
+    out: [Variable; 5],
+});
+
+impl<C: Config> Define<C> for Circuit<Variable> {
+    fn define<Builder: RootAPI<C>>(&self, builder: &mut Builder) {
+        let r = builder.mux(&self.s, &self.v);
+        builder.assert_is_equal(r, self.out[0]);
+        let r = builder.select(self.s[0], self.v[0], self.v[1]);
+        builder.assert_is_equal(r, self.out[1]);
+        let r = builder.lookup2(self.s[0], self.s[1], 1, 0, 0, 1);
+        builder.assert_is_equal(r, self.out[2]);
+        let (a, b) = builder.cond_swap(self.s[2], self.v[0], self.v[1]);
+        builder.assert_is_equal(a, self.out[3]);
+        builder.assert_is_equal(b, self.out[4]);
+    }
+}
+
+fn expected_outputs(s: [u32; 3], v: [u32; 8]) -> [u32; 5] {
+    let index = (s[0] + 2 * s[1] + 4 * s[2]) as usize;
+    let select = if s[0] == 1 { v[0] } else { v[1] };
+    let lookup2 = (s[0] == s[1]) as u32;
+    let (a, b) = if s[2] == 1 {
+        (v[1], v[0])
+    } else {
+        (v[0], v[1])
+    };
+    [v[index], select, lookup2, a, b]
+}
+
+fn check<C: Config>(s: [u32; 3], v: [u32; 8], out: [u32; 5], expected: bool) {
+    let compile_result = compile::<C, _>(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignment = Circuit::<CircuitField<C>> {
+        s: s.map(CircuitField::<C>::from),
+        v: v.map(CircuitField::<C>::from),
+        out: out.map(CircuitField::<C>::from),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    let output = compile_result.layered_circuit.run(&witness);
+    assert_eq!(
+        output,
+        vec![expected],
+        "s = {s:?}, v = {v:?}, out = {out:?}"
+    );
+}
+
+#[test]
+fn mux_m31() {
+    let v = [10, 11, 12, 13, 14, 15, 16, 17];
+    for i in 0..8u32 {
+        let s = [i & 1, (i >> 1) & 1, i >> 2];
+        check::<M31Config>(s, v, expected_outputs(s, v), true);
+    }
+    let s = [1, 0, 1];
+    let mut out = expected_outputs(s, v);
+    out[0] += 1;
+    check::<M31Config>(s, v, out, false);
+    // non-boolean selector
+    let s = [2, 0, 0];
+    check::<M31Config>(s, v, [12, 11, 0, 10, 11], false);
+}
+
+#[test]
+fn mux_gf2() {
+    let v = [0, 1, 1, 0, 1, 0, 0, 1];
+    for i in 0..8u32 {
+        let s = [i & 1, (i >> 1) & 1, i >> 2];
+        check::<GF2Config>(s, v, expected_outputs(s, v), true);
+    }
+    let s = [0, 1, 1];
+    let mut out = expected_outputs(s, v);
+    out[3] ^= 1;
+    check::<GF2Config>(s, v, out, false);
+}
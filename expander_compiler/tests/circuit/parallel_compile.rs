@@ -0,0 +1,66 @@
+use expander_compiler::frontend::*;
+
+const N: usize = 16;
+
+declare_circuit!(Circuit {
+    x: [Variable; N],
+    y: Variable,
+});
+
+#[memorized]
+fn step<C: Config, B: RootAPI<C>>(api: &mut B, x: Variable, k: u32) -> Variable {
+    let x2 = api.mul(x, x);
+    api.add(x2, k)
+}
+
+#[memorized]
+fn chain<C: Config, B: RootAPI<C>>(api: &mut B, x: Variable, k: u32) -> Variable {
+    let a = step(api, x, k);
+    step(api, a, k + 1)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let mut sum = builder.constant(0);
+        for (i, x) in self.x.iter().enumerate() {
+            let c = chain(builder, *x, i as u32);
+            sum = builder.add(sum, c);
+        }
+        builder.assert_is_equal(sum, self.y);
+    }
+}
+
+fn compile_with_threads(num_threads: usize) -> CompileResult<M31Config> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .unwrap();
+    pool.install(|| compile(&Circuit::default(), CompileOptions::default()).unwrap())
+}
+
+#[test]
+fn parallel_compile_is_deterministic() {
+    let sequential = compile_with_threads(1);
+    for num_threads in [2, 4, 8] {
+        let parallel = compile_with_threads(num_threads);
+        assert_eq!(parallel.layered_circuit, sequential.layered_circuit);
+        assert_eq!(
+            parallel.witness_solver.circuit,
+            sequential.witness_solver.circuit
+        );
+    }
+
+    let step = |x: M31, k: u32| x * x + M31::from(k);
+    let mut assignment = Circuit::<M31>::default();
+    let mut y = M31::zero();
+    for i in 0..N {
+        assignment.x[i] = M31::from(i as u32 + 3);
+        y += step(step(assignment.x[i], i as u32), i as u32 + 1);
+    }
+    assignment.y = y;
+    let witness = sequential
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(sequential.layered_circuit.run(&witness), vec![true]);
+}
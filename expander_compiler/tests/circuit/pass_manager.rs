@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use expander_compiler::frontend::{
+    extra::{PassManager, PassPosition},
+    *,
+};
+
+declare_circuit!(Circuit {
+    x: [Variable; 2],
+    y: Variable,
+});
+
+#[memorized]
+fn square<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable) -> Variable {
+    api.mul(a, a)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let a = square(builder, self.x[0]);
+        let b = square(builder, self.x[1]);
+        let s = builder.add(a, b);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+fn assignment() -> Circuit<M31> {
+    Circuit::<M31> {
+        x: [M31::from(3u32), M31::from(4u32)],
+        y: M31::from(25u32),
+    }
+}
+
+#[test]
+fn user_passes_and_dumps() {
+    let dir = std::env::temp_dir().join(format!(
+        "expander_compiler_pass_manager_test_{}",
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut passes = PassManager::<M31Config>::new().with_dump_dir(&dir);
+    let c1 = calls.clone();
+    let c2 = calls.clone();
+    passes
+        .add_dest_pass("count_dest", move |r| {
+            c1.fetch_add(1, Ordering::Relaxed);
+            Ok(r)
+        })
+        .add_layered_pass("count_layered", move |lc| {
+            c2.fetch_add(1, Ordering::Relaxed);
+            Ok(lc)
+        });
+
+    let plain = compile(&Circuit::default(), CompileOptions::default()).unwrap();
+    let res = compile_with_passes(&Circuit::default(), CompileOptions::default(), &passes).unwrap();
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+    assert_eq!(res.layered_circuit, plain.layered_circuit);
+    let witness = res.witness_solver.solve_witness(&assignment()).unwrap();
+    assert_eq!(res.layered_circuit.run(&witness), vec![true]);
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    let expected = [
+        "source-source_opt",
+        "hint_normalized-hint_normalized_opt",
+        "hint_less-export_hints",
+        "dest_relaxed-final_build",
+        "dest-dest_opt",
+        "dest-count_dest",
+        "layered-layered_opt",
+        "layered-count_layered",
+    ];
+    for name in expected.iter() {
+        assert!(
+            names.iter().any(|n| n.ends_with(&format!("-{name}.txt"))),
+            "missing dump {name}"
+        );
+    }
+    // the dumps are numbered in the order the passes ran
+    let pos = |name: &str| {
+        names
+            .iter()
+            .position(|n| n.ends_with(&format!("-{name}.txt")))
+            .unwrap()
+    };
+    for w in expected.windows(2) {
+        assert!(pos(w[0]) < pos(w[1]));
+    }
+    let layered = fs::read_to_string(dir.join(&names[pos("layered-count_layered")])).unwrap();
+    assert_eq!(layered, res.layered_circuit.to_string());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn user_pass_rewrites_circuit() {
+    // replace the assertion s == y by s == y + 1
+    let mut passes = PassManager::<M31Config>::new();
+    passes.add_layered_pass("shift_output", |mut lc| {
+        let root = *lc.layer_ids.last().unwrap();
+        let seg = &mut lc.segments[root];
+        seg.gate_consts
+            .push(expander_compiler::circuit::layered::GateConst {
+                inputs: [],
+                output: 0,
+                coef: expander_compiler::circuit::layered::Coef::Constant(M31::from(1u32)),
+            });
+        Ok(lc)
+    });
+    let res = compile_with_passes(&Circuit::default(), CompileOptions::default(), &passes).unwrap();
+    let witness = res.witness_solver.solve_witness(&assignment()).unwrap();
+    assert_eq!(res.layered_circuit.run(&witness), vec![false]);
+}
+
+#[test]
+fn user_pass_errors() {
+    let mut passes = PassManager::<M31Config>::new();
+    passes.add_hint_less_pass("add_input", |mut r| {
+        r.circuits.get_mut(&0).unwrap().num_inputs += 1;
+        Ok(r)
+    });
+    let err = compile_with_passes(&Circuit::default(), CompileOptions::default(), &passes)
+        .err()
+        .unwrap();
+    assert!(err.is_user());
+    assert!(format!("{err:?}").contains("add_input"));
+
+    let mut passes = PassManager::<M31Config>::new();
+    passes.add_source_pass("fail", |_| {
+        Err(Error::UserError("not supported".to_string()))
+    });
+    let err = compile_with_passes(&Circuit::default(), CompileOptions::default(), &passes)
+        .err()
+        .unwrap();
+    assert!(format!("{err:?}").contains("pass fail failed"));
+}
+
+#[test]
+fn user_pass_positions() {
+    let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut passes = PassManager::<M31Config>::new();
+    let (o1, o2, o3, o4, o5) = (
+        order.clone(),
+        order.clone(),
+        order.clone(),
+        order.clone(),
+        order.clone(),
+    );
+    passes
+        .add_dest_relaxed_pass("relaxed_end", move |r| {
+            o1.lock().unwrap().push("relaxed_end");
+            Ok(r)
+        })
+        .insert_dest_relaxed_pass(
+            PassPosition::After("final_build"),
+            "after_final_build",
+            move |r| {
+                // sub circuits are still there before split_to_single_layer
+                assert!(r.circuits.len() > 1);
+                o2.lock().unwrap().push("after_final_build");
+                Ok(r)
+            },
+        )
+        .unwrap()
+        .insert_source_pass(
+            PassPosition::Before("source_opt"),
+            "source_input",
+            move |r| {
+                o3.lock().unwrap().push("source_input");
+                Ok(r)
+            },
+        )
+        .unwrap()
+        .insert_hint_less_pass(
+            PassPosition::Before("hint_less_opt"),
+            "exported",
+            move |r| {
+                o4.lock().unwrap().push("exported");
+                Ok(r)
+            },
+        )
+        .unwrap()
+        .insert_layered_pass(PassPosition::After("layering"), "layering", move |lc| {
+            o5.lock().unwrap().push("layering");
+            Ok(lc)
+        })
+        .unwrap();
+
+    // the profile is computed with the passes
+    let (res, profile) =
+        compile_with_profile(&Circuit::default(), CompileOptions::default(), &passes).unwrap();
+    assert_eq!(
+        *order.lock().unwrap(),
+        vec![
+            "source_input",
+            "exported",
+            "after_final_build",
+            "relaxed_end",
+            "layering"
+        ]
+    );
+    assert!(profile.get_by_name("square").is_some());
+    let witness = res.witness_solver.solve_witness(&assignment()).unwrap();
+    assert_eq!(res.layered_circuit.run(&witness), vec![true]);
+}
+
+#[test]
+fn user_pass_bad_positions() {
+    let mut passes = PassManager::<M31Config>::new();
+    let err = passes
+        .insert_dest_pass(PassPosition::After("final_build"), "p", Ok)
+        .err()
+        .unwrap();
+    assert!(err.is_user());
+    let err = passes
+        .insert_hint_normalized_pass(PassPosition::Before("hint_normalize"), "p", Ok)
+        .err()
+        .unwrap();
+    assert!(err.is_user());
+    assert!(passes
+        .insert_hint_normalized_pass(PassPosition::Before("hint_normalized_opt"), "p", Ok)
+        .is_ok());
+}
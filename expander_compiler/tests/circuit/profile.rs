@@ -0,0 +1,120 @@
+use expander_compiler::frontend::{extra::PassManager, *};
+
+declare_circuit!(Circuit {
+    x: [Variable; 4],
+    y: Variable,
+});
+
+#[memorized]
+fn cube<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable) -> Variable {
+    let a2 = api.mul(a, a);
+    api.mul(a2, a)
+}
+
+#[memorized]
+fn cube_sum<C: Config, B: RootAPI<C>>(api: &mut B, a: Variable, b: Variable) -> Variable {
+    let a3 = cube(api, a);
+    let b3 = cube(api, b);
+    api.add(a3, b3)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let s1 = cube_sum(builder, self.x[0], self.x[1]);
+        let s2 = cube_sum(builder, self.x[2], self.x[3]);
+        let s = builder.add(s1, s2);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+declare_circuit!(SharedCircuit {
+    x: [Variable; 3],
+    y: Variable,
+});
+
+impl Define<M31Config> for SharedCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let c = cube(builder, self.x[0]);
+        let s = cube_sum(builder, self.x[1], self.x[2]);
+        let s = builder.add(c, s);
+        builder.assert_is_equal(s, self.y);
+    }
+}
+
+#[test]
+fn profile_sub_circuits() {
+    let (compile_result, profile) = compile_with_profile(
+        &Circuit::default(),
+        CompileOptions::default(),
+        &PassManager::new(),
+    )
+    .unwrap();
+
+    let root = profile.root();
+    assert_eq!(root.name, "define");
+    assert_eq!(root.multiplicity, 1);
+    assert_eq!(root.calls.len(), 1);
+    let cube_sum = profile.get_by_name("cube_sum").unwrap();
+    assert_eq!(root.calls[0], (cube_sum.circuit_id, 2));
+    assert_eq!(cube_sum.multiplicity, 2);
+    let cube = profile.get_by_name("cube").unwrap();
+    assert_eq!(cube_sum.calls, vec![(cube.circuit_id, 2)]);
+    assert_eq!(cube.multiplicity, 4);
+    assert!(cube.self_gates.num_mul > 0);
+    assert!(cube.num_layers >= 2);
+    assert_eq!(
+        profile.total_gates(root.circuit_id).num_mul,
+        root.self_gates.num_mul + 2 * (cube_sum.self_gates.num_mul + 2 * cube.self_gates.num_mul)
+    );
+    assert_eq!(
+        profile.expanded_gates(),
+        profile.total_gates(root.circuit_id)
+    );
+
+    let folded = profile.to_folded_stacks();
+    let cube_line = folded
+        .lines()
+        .find(|l| l.starts_with("define;cube_sum;cube "))
+        .unwrap();
+    let value: usize = cube_line.rsplit(' ').next().unwrap().parse().unwrap();
+    assert_eq!(value, 4 * cube.self_gates.total());
+
+    let assignment = Circuit::<M31> {
+        x: [
+            M31::from(1u32),
+            M31::from(2u32),
+            M31::from(3u32),
+            M31::from(4u32),
+        ],
+        y: M31::from(100u32),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    assert_eq!(compile_result.layered_circuit.run(&witness), vec![true]);
+}
+
+#[test]
+fn folded_stacks_split_shared_sub_circuits_by_call_path() {
+    let (_, profile) = compile_with_profile(
+        &SharedCircuit::default(),
+        CompileOptions::default(),
+        &PassManager::new(),
+    )
+    .unwrap();
+    let cube = profile.get_by_name("cube").unwrap();
+    assert_eq!(cube.multiplicity, 3);
+
+    let folded = profile.to_folded_stacks();
+    let value_of = |path: &str| -> usize {
+        let prefix = format!("{path} ");
+        let line = folded.lines().find(|l| l.starts_with(&prefix)).unwrap();
+        line[prefix.len()..].parse().unwrap()
+    };
+    assert_eq!(value_of("define;cube"), cube.self_gates.total());
+    assert_eq!(
+        value_of("define;cube_sum;cube"),
+        2 * cube.self_gates.total()
+    );
+}
@@ -0,0 +1,65 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit { x: [Variable; 3] });
+
+#[memorized]
+fn check_small<C: Config, B: RootAPI<C>>(api: &mut B, x: Variable) {
+    api.assert_in_range(x, 5);
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        builder.assert_in_range(self.x[0], 8);
+        builder.assert_in_range(self.x[1], 20);
+        check_small(builder, self.x[2]);
+        check_small(builder, self.x[2]);
+    }
+}
+
+impl Define<GF2Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<GF2Config>>(&self, builder: &mut Builder) {
+        for x in self.x.iter() {
+            builder.assert_in_range(x, 1);
+        }
+    }
+}
+
+#[test]
+fn range_check_lookup() {
+    let compile_result =
+        compile::<M31Config, _>(&Circuit::default(), CompileOptions::default()).unwrap();
+    let cases: [([u32; 3], bool); 6] = [
+        ([255, (1 << 20) - 1, 31], true),
+        ([0, 0, 0], true),
+        ([256, 0, 0], false),
+        ([0, 1 << 20, 0], false),
+        ([0, 0, 32], false),
+        ([(1 << 31) - 2, 0, 0], false),
+    ];
+    for (x, expected) in cases {
+        let assignment = Circuit::<M31> {
+            x: x.map(M31::from),
+        };
+        let witness = compile_result
+            .witness_solver
+            .solve_witness(&assignment)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![expected], "x = {x:?}");
+    }
+}
+
+#[test]
+fn range_check_gf2_fallback() {
+    let compile_result =
+        compile::<GF2Config, _>(&Circuit::default(), CompileOptions::default()).unwrap();
+    let assignment = Circuit::<GF2> {
+        x: [1u32, 0, 1].map(GF2::from),
+    };
+    let witness = compile_result
+        .witness_solver
+        .solve_witness(&assignment)
+        .unwrap();
+    let output = compile_result.layered_circuit.run(&witness);
+    assert_eq!(output, vec![true]);
+}
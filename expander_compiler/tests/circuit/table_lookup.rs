@@ -0,0 +1,131 @@
+use expander_compiler::frontend::*;
+
+declare_circuit!(Circuit {
+    x: [Variable; 3],
+    sq: Variable,
+    set: [Variable; 4],
+    member: Variable,
+});
+
+fn square_table<C: Config, B: RootAPI<C>>(builder: &mut B) -> Table {
+    let keys: Vec<Variable> = (0..16u32).map(|i| builder.constant(i)).collect();
+    let values: Vec<Vec<Variable>> = (0..16u32)
+        .map(|i| vec![builder.constant(i * i), builder.constant(i * i * i)])
+        .collect();
+    builder.new_table(&keys, &values)
+}
+
+impl Define<M31Config> for Circuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let squares = square_table(builder);
+        let r = builder.lookup(squares, self.x[0]);
+        builder.assert_is_equal(r[0], self.sq);
+        // the constant table is used from a sub circuit, which is called twice
+        let cube_sum = move |api: &mut Builder, x: &Vec<Variable>| {
+            let a = api.lookup(squares, x[0]);
+            let b = api.lookup(squares, x[1]);
+            vec![api.add(a[1], b[1])]
+        };
+        let s1 = builder.memorized_simple_call(cube_sum, &[self.x[1], self.x[2]]);
+        let s2 = builder.memorized_simple_call(cube_sum, &[self.x[2], self.x[1]]);
+        builder.assert_is_equal(s1[0], s2[0]);
+        // a table of variables without values is a set
+        let set = builder.new_table(&self.set, &[vec![], vec![], vec![], vec![]]);
+        let r = builder.lookup(set, self.member);
+        assert!(r.is_empty());
+    }
+}
+
+fn assignment(x: [u32; 3], sq: u32, set: [u32; 4], member: u32) -> Circuit<M31> {
+    Circuit {
+        x: x.map(M31::from),
+        sq: M31::from(sq),
+        set: set.map(M31::from),
+        member: M31::from(member),
+    }
+}
+
+#[test]
+fn table_lookup() {
+    let compile_result =
+        compile::<M31Config, _>(&Circuit::default(), CompileOptions::default()).unwrap();
+    let cases = [
+        (assignment([3, 4, 15], 9, [5, 6, 7, 8], 7), true),
+        (assignment([0, 0, 0], 0, [0, 0, 0, 0], 0), true),
+        (assignment([3, 4, 15], 10, [5, 6, 7, 8], 7), false),
+        (assignment([16, 4, 15], 256, [5, 6, 7, 8], 7), false),
+        (assignment([3, 16, 15], 9, [5, 6, 7, 8], 7), false),
+        (assignment([3, 4, 15], 9, [5, 6, 7, 8], 9), false),
+    ];
+    for (i, (assignment, expected)) in cases.iter().enumerate() {
+        let witness = compile_result
+            .witness_solver
+            .solve_witness(assignment)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![*expected], "case {i}");
+    }
+}
+
+#[test]
+fn table_lookup_debug_eval() {
+    let report = extra::debug_eval_report(
+        &Circuit::default(),
+        &assignment([3, 4, 20], 9, [5, 6, 7, 8], 7),
+        EmptyHintCaller,
+    );
+    assert_eq!(report.failures.len(), 2);
+    for failure in report.failures.iter() {
+        assert_eq!(failure.kind, extra::AssertionKind::Lookup { table: 0 });
+        assert_eq!(failure.values, vec![M31::from(20u32)]);
+    }
+}
+
+declare_circuit!(VariableTableCircuit {
+    keys: [Variable; 4],
+    values: [Variable; 4],
+    queries: [Variable; 2],
+    sum: Variable,
+});
+
+impl Define<M31Config> for VariableTableCircuit<Variable> {
+    fn define<Builder: RootAPI<M31Config>>(&self, builder: &mut Builder) {
+        let values: Vec<Vec<Variable>> = self.values.iter().map(|v| vec![*v]).collect();
+        let table = builder.new_table(&self.keys, &values);
+        // a table of variables is used from a sub circuit
+        let pair_sum = move |api: &mut Builder, x: &Vec<Variable>| {
+            let a = api.lookup(table, x[0]);
+            let b = api.lookup(table, x[1]);
+            vec![api.add(a[0], b[0])]
+        };
+        let s = builder.memorized_simple_call(pair_sum, &self.queries);
+        builder.assert_is_equal(s[0], self.sum);
+    }
+}
+
+#[test]
+fn variable_table_lookup_in_sub_circuit() {
+    let compile_result =
+        compile::<M31Config, _>(&VariableTableCircuit::default(), CompileOptions::default())
+            .unwrap();
+    let assignment = |queries: [u32; 2], sum: u32| VariableTableCircuit::<M31> {
+        keys: [10, 20, 30, 40].map(M31::from),
+        values: [1, 2, 3, 4].map(M31::from),
+        queries: queries.map(M31::from),
+        sum: M31::from(sum),
+    };
+    let cases = [
+        (assignment([20, 40], 6), true),
+        (assignment([10, 10], 2), true),
+        (assignment([20, 40], 7), false),
+        (assignment([20, 50], 2), false),
+    ];
+    for (i, (assignment, expected)) in cases.iter().enumerate() {
+        let witness = compile_result
+            .witness_solver
+            .solve_witness(assignment)
+            .unwrap();
+        let output = compile_result.layered_circuit.run(&witness);
+        assert_eq!(output, vec![*expected], "case {i}");
+    }
+}